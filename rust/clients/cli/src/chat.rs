@@ -23,6 +23,9 @@ mod proto_file {
     include!("../../../libqaul/src/rpc/protobuf_generated/rust/qaul.net.chatfile.rs");
 }
 
+/// number of search results per page
+const SEARCH_PAGE_SIZE: u32 = 20;
+
 /// chat module function handling
 pub struct Chat {}
 
@@ -118,6 +121,232 @@ impl Chat {
                     }
                 }
             }
+            // request failed messages
+            cmd if cmd.starts_with("failed") => {
+                Self::request_failed_messages();
+            }
+            // resend failed message
+            cmd if cmd.starts_with("resend ") => {
+                let message_id_str = cmd.strip_prefix("resend ").unwrap().trim();
+                match bs58::decode(message_id_str).into_vec() {
+                    Ok(message_id) => Self::resend_message(message_id),
+                    Err(e) => log::error!("invalid message id: {}", e),
+                }
+            }
+            // search the chat history and the feed
+            cmd if cmd.starts_with("search ") => {
+                let mut request = proto::ChatSearchRequest {
+                    query: String::new(),
+                    group_id: Vec::new(),
+                    sender_id: Vec::new(),
+                    sent_after: 0,
+                    sent_before: 0,
+                    include_feed: true,
+                    offset: 0,
+                    limit: SEARCH_PAGE_SIZE,
+                };
+
+                // options precede the query
+                let mut words = cmd.strip_prefix("search ").unwrap().split_whitespace();
+                let mut query: Vec<&str> = Vec::new();
+                while let Some(word) = words.next() {
+                    if !query.is_empty() {
+                        query.push(word);
+                        continue;
+                    }
+                    match (word, words.clone().next()) {
+                        ("--group", Some(group_id_str)) => {
+                            request.group_id =
+                                match Self::id_string_to_bin(group_id_str.to_string()) {
+                                    Ok(id) => id,
+                                    _ => match Self::uuid_string_to_bin(group_id_str.to_string()) {
+                                        Ok(id) => id,
+                                        _ => {
+                                            log::error!("invalid group id format");
+                                            return;
+                                        }
+                                    },
+                                };
+                            words.next();
+                        }
+                        ("--sender", Some(sender_id_str)) => {
+                            request.sender_id = match bs58::decode(sender_id_str).into_vec() {
+                                Ok(id) => id,
+                                Err(e) => {
+                                    log::error!("invalid sender id: {}", e);
+                                    return;
+                                }
+                            };
+                            words.next();
+                        }
+                        ("--page", Some(page_str)) => {
+                            match page_str.parse::<u32>() {
+                                Ok(page) if page > 0 => {
+                                    request.offset = (page - 1) * SEARCH_PAGE_SIZE
+                                }
+                                _ => {
+                                    log::error!("invalid page number");
+                                    return;
+                                }
+                            }
+                            words.next();
+                        }
+                        _ => query.push(word),
+                    }
+                }
+                request.query = query.join(" ");
+
+                if request.query.is_empty() {
+                    log::error!("chat search command incorrectly formatted");
+                    return;
+                }
+                Self::search(request);
+            }
+            // export conversations
+            cmd if cmd.starts_with("export ") => {
+                let mut request = proto::ChatExportRequest {
+                    group_id: Vec::new(),
+                    format: proto::ExportFormat::Json as i32,
+                    path: String::new(),
+                };
+
+                // options precede the path
+                let mut words = cmd.strip_prefix("export ").unwrap().split_whitespace();
+                let mut path: Vec<&str> = Vec::new();
+                while let Some(word) = words.next() {
+                    if !path.is_empty() {
+                        path.push(word);
+                        continue;
+                    }
+                    match (word, words.clone().next()) {
+                        ("--group", Some(group_id_str)) => {
+                            request.group_id =
+                                match Self::id_string_to_bin(group_id_str.to_string()) {
+                                    Ok(id) => id,
+                                    _ => match Self::uuid_string_to_bin(group_id_str.to_string()) {
+                                        Ok(id) => id,
+                                        _ => {
+                                            log::error!("invalid group id format");
+                                            return;
+                                        }
+                                    },
+                                };
+                            words.next();
+                        }
+                        ("--format", Some(format_str)) => {
+                            request.format = match format_str {
+                                "json" => proto::ExportFormat::Json as i32,
+                                "markdown" => proto::ExportFormat::Markdown as i32,
+                                "html" => proto::ExportFormat::Html as i32,
+                                _ => {
+                                    log::error!("invalid export format");
+                                    return;
+                                }
+                            };
+                            words.next();
+                        }
+                        _ => path.push(word),
+                    }
+                }
+                request.path = path.join(" ");
+
+                if request.path.is_empty() {
+                    log::error!("chat export command incorrectly formatted");
+                    return;
+                }
+                Self::export(request);
+            }
+            // import conversations
+            cmd if cmd.starts_with("import ") => {
+                let path = cmd.strip_prefix("import ").unwrap().trim();
+                if path.is_empty() {
+                    log::error!("chat import command incorrectly formatted");
+                    return;
+                }
+                Self::import(path.to_string());
+            }
+            // chat privacy settings
+            cmd if cmd.starts_with("privacy") => {
+                match cmd.strip_prefix("privacy").unwrap().trim() {
+                    "" => Self::request_privacy(),
+                    "read_receipts on" => Self::set_privacy(true),
+                    "read_receipts off" => Self::set_privacy(false),
+                    _ => log::error!("chat privacy command incorrectly formatted"),
+                }
+            }
+            // reply to a message
+            cmd if cmd.starts_with("reply ") => {
+                let command_string = cmd.strip_prefix("reply ").unwrap().trim();
+                let mut iter = command_string.splitn(3, ' ');
+                match (iter.next(), iter.next(), iter.next()) {
+                    (Some(group_id_str), Some(message_id_str), Some(text)) => {
+                        let group_id = match Self::id_string_to_bin(group_id_str.to_string()) {
+                            Ok(id) => id,
+                            _ => match Self::uuid_string_to_bin(group_id_str.to_string()) {
+                                Ok(id) => id,
+                                _ => {
+                                    log::error!("invalid group id format");
+                                    return;
+                                }
+                            },
+                        };
+                        match bs58::decode(message_id_str).into_vec() {
+                            Ok(message_id) => {
+                                Self::send_reply(group_id, message_id, text.trim().to_string());
+                                println!("chat reply sent [{}] {}", message_id_str, text);
+                            }
+                            Err(e) => log::error!("invalid message id: {}", e),
+                        }
+                    }
+                    _ => log::error!("chat reply command incorrectly formatted"),
+                }
+            }
+            // add or remove a reaction on a message
+            cmd if cmd.starts_with("react ") || cmd.starts_with("unreact ") => {
+                let remove = cmd.starts_with("unreact ");
+                let command_string = cmd.split_once(' ').unwrap().1.trim();
+                match command_string.split_once(' ') {
+                    Some((message_id_str, emoji)) => {
+                        match bs58::decode(message_id_str).into_vec() {
+                            Ok(message_id) => {
+                                Self::send_reaction(message_id, emoji.trim().to_string(), remove)
+                            }
+                            Err(e) => log::error!("invalid message id: {}", e),
+                        }
+                    }
+                    None => log::error!("chat reaction command incorrectly formatted"),
+                }
+            }
+            // edit a sent message
+            cmd if cmd.starts_with("edit ") => {
+                let command_string = cmd.strip_prefix("edit ").unwrap().trim();
+                match command_string.split_once(' ') {
+                    Some((message_id_str, text)) => match bs58::decode(message_id_str).into_vec() {
+                        Ok(message_id) => {
+                            Self::edit_message(message_id, text.trim().to_string());
+                            println!("chat message edit sent [{}] {}", message_id_str, text);
+                        }
+                        Err(e) => log::error!("invalid message id: {}", e),
+                    },
+                    None => log::error!("chat edit command incorrectly formatted"),
+                }
+            }
+            // delete a sent message for everyone
+            cmd if cmd.starts_with("delete ") => {
+                let message_id_str = cmd.strip_prefix("delete ").unwrap().trim();
+                match bs58::decode(message_id_str).into_vec() {
+                    Ok(message_id) => Self::delete_message(message_id),
+                    Err(e) => log::error!("invalid message id: {}", e),
+                }
+            }
+            // request the edit history of a message
+            cmd if cmd.starts_with("edits ") => {
+                let message_id_str = cmd.strip_prefix("edits ").unwrap().trim();
+                match bs58::decode(message_id_str).into_vec() {
+                    Ok(message_id) => Self::request_edit_history(message_id),
+                    Err(e) => log::error!("invalid message id: {}", e),
+                }
+            }
             // unknown command
             _ => log::error!("unknown chat command"),
         }
@@ -193,6 +422,230 @@ impl Chat {
         Rpc::send_message(buf, super::rpc::proto::Modules::Chat.into(), "".to_string());
     }
 
+    /// Request the failed messages via rpc
+    fn request_failed_messages() {
+        // create failed request message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::FailedRequest(
+                proto::ChatFailedRequest {},
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Resend a failed message via rpc
+    fn resend_message(message_id: Vec<u8>) {
+        // create resend request message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::ResendRequest(
+                proto::ChatResendRequest { message_id },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Search the chat history and the feed via rpc
+    fn search(request: proto::ChatSearchRequest) {
+        // create search request message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::SearchRequest(request)),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Export conversations via rpc
+    fn export(request: proto::ChatExportRequest) {
+        // create export request message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::ExportRequest(request)),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Import conversations via rpc
+    fn import(path: String) {
+        // create import request message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::ImportRequest(
+                proto::ChatImportRequest { path },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Request the chat privacy settings via rpc
+    fn request_privacy() {
+        // create privacy request message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::PrivacyRequest(
+                proto::ChatPrivacyRequest {},
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Update the chat privacy settings via rpc
+    fn set_privacy(read_receipts: bool) {
+        // create privacy message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::Privacy(proto::ChatPrivacy {
+                read_receipts,
+            })),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Reply to a message via rpc
+    fn send_reply(group_id: Vec<u8>, message_id: Vec<u8>, content: String) {
+        // create reply message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::Reply(proto::ChatReplySend {
+                group_id,
+                message_id,
+                content,
+            })),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Add or remove a reaction on a message via rpc
+    fn send_reaction(message_id: Vec<u8>, emoji: String, remove: bool) {
+        // create reaction message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::Reaction(proto::ChatReactionSend {
+                message_id,
+                emoji,
+                remove,
+            })),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Edit a sent message via rpc
+    fn edit_message(message_id: Vec<u8>, content: String) {
+        // create edit request message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::Edit(proto::ChatEditRequest {
+                message_id,
+                content,
+            })),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Delete a sent message for everyone via rpc
+    fn delete_message(message_id: Vec<u8>) {
+        // create delete request message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::Delete(proto::ChatDeleteRequest {
+                message_id,
+            })),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Request the edit history of a message via rpc
+    fn request_edit_history(message_id: Vec<u8>) {
+        // create edit history request message
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::EditHistoryRequest(
+                proto::ChatEditHistoryRequest { message_id },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Print a failed message
+    fn print_failed_message(message: proto::ChatFailedMessage) {
+        println!(
+            "[{}] to {}",
+            bs58::encode(message.message_id).into_string(),
+            bs58::encode(message.receiver_id).into_string()
+        );
+        if !message.group_id.is_empty() {
+            match uuid::Uuid::from_slice(&message.group_id) {
+                Ok(group_id) => println!("\tGroup: {}", group_id),
+                Err(_) => println!("\tGroup: {}", bs58::encode(message.group_id).into_string()),
+            }
+        }
+        println!("\tCreated at: {}", message.created_at);
+        println!("\tLast try: {}", message.last_try);
+        println!("\tAttempts: {}", message.try_count);
+        println!("\tReason: {}", message.reason);
+    }
+
     fn analyze_content(content: &Vec<u8>) -> Result<Vec<String>, String> {
         let mut res: Vec<String> = vec![];
 
@@ -200,6 +653,45 @@ impl Chat {
             match content_message.message {
                 Some(proto::chat_content_message::Message::ChatContent(chat_content)) => {
                     res.push(chat_content.text);
+                    if chat_content.edited_at > 0 {
+                        res.push(
+                            "(edited at ".to_string()
+                                + chat_content.edited_at.to_string().as_str()
+                                + ")",
+                        );
+                    }
+                    return Ok(res);
+                }
+                Some(proto::chat_content_message::Message::Reply(reply)) => {
+                    res.push(
+                        "reply to [".to_string()
+                            + bs58::encode(reply.message_id).into_string().as_str()
+                            + "]: > "
+                            + reply.quote.as_str(),
+                    );
+                    res.push(reply.text);
+                    if reply.edited_at > 0 {
+                        res.push(
+                            "(edited at ".to_string() + reply.edited_at.to_string().as_str() + ")",
+                        );
+                    }
+                    return Ok(res);
+                }
+                Some(proto::chat_content_message::Message::Reaction(reaction)) => {
+                    res.push(
+                        "reacted ".to_string()
+                            + reaction.emoji.as_str()
+                            + " on ["
+                            + bs58::encode(reaction.message_id).into_string().as_str()
+                            + "]",
+                    );
+                    return Ok(res);
+                }
+                Some(proto::chat_content_message::Message::MessageDeleted(deleted)) => {
+                    res.push(
+                        "This message was deleted at ".to_string()
+                            + deleted.deleted_at.to_string().as_str(),
+                    );
                     return Ok(res);
                 }
                 Some(proto::chat_content_message::Message::FileContent(file_content)) => {
@@ -286,6 +778,12 @@ impl Chat {
                                     Ok(proto::MessageStatus::ConfirmedByAll) => print!("✓✓✓| "),
                                     Ok(proto::MessageStatus::Receiving) => print!("🚚 | "),
                                     Ok(proto::MessageStatus::Received) => print!("📨 | "),
+                                    Ok(proto::MessageStatus::WaitingForKeyExchange) => {
+                                        print!("🔑 | ")
+                                    }
+                                    Ok(proto::MessageStatus::Failed) => print!("❌ | "),
+                                    Ok(proto::MessageStatus::Pending) => print!("⏸ | "),
+                                    Ok(proto::MessageStatus::Cancelled) => print!("🚫 | "),
                                     Err(_) => {}
                                 }
 
@@ -300,11 +798,117 @@ impl Chat {
                                 for s in ss {
                                     println!("\t{}", s);
                                 }
+                                for confirmation in message.message_reception_confirmed {
+                                    print!(
+                                        "\t✓ {} received {}",
+                                        bs58::encode(confirmation.user_id).into_string(),
+                                        confirmation.confirmed_at
+                                    );
+                                    if confirmation.read_at > 0 {
+                                        print!(", read {}", confirmation.read_at);
+                                    }
+                                    println!("");
+                                }
+                                for reaction in message.reactions {
+                                    println!("\t{} {}", reaction.emoji, reaction.user_ids.len());
+                                }
                                 println!("");
                             }
                         }
                     }
-
+                    Some(proto::chat::Message::DeliveryFailed(delivery_failed)) => {
+                        if let Some(message) = delivery_failed.message {
+                            println!("Message delivery failed");
+                            Self::print_failed_message(message);
+                        }
+                    }
+                    Some(proto::chat::Message::SearchResult(search_result)) => {
+                        println!("");
+                        println!(
+                            "Search results for \"{}\": {} to {} of {}",
+                            search_result.query,
+                            (search_result.offset + 1).min(search_result.total),
+                            search_result.offset as usize + search_result.hits.len(),
+                            search_result.total
+                        );
+                        println!("");
+                        for hit in search_result.hits {
+                            let message = match hit.message {
+                                Some(message) => message,
+                                None => continue,
+                            };
+                            match proto::SearchSource::try_from(hit.source) {
+                                Ok(proto::SearchSource::FeedMessage) => println!("Feed"),
+                                _ => match uuid::Uuid::from_slice(&message.group_id) {
+                                    Ok(group_id) => println!("{} [ {} ]", hit.group_name, group_id),
+                                    Err(_) => println!("{}", hit.group_name),
+                                },
+                            }
+                            println!(
+                                " {} | {}",
+                                message.sent_at,
+                                bs58::encode(message.sender_id).into_string()
+                            );
+                            println!(" [{}]", bs58::encode(message.message_id).into_string());
+                            if let Ok(ss) = Self::analyze_content(&message.content) {
+                                for s in ss {
+                                    println!("\t{}", s);
+                                }
+                            }
+                            println!("");
+                        }
+                    }
+                    Some(proto::chat::Message::ExportResponse(export_response)) => {
+                        if export_response.success {
+                            println!(
+                                "Exported {} conversations with {} messages",
+                                export_response.conversations, export_response.messages
+                            );
+                            for file in export_response.files {
+                                println!("\t{}", file);
+                            }
+                        } else {
+                            println!("Chat export failed: {}", export_response.error);
+                        }
+                    }
+                    Some(proto::chat::Message::ImportResponse(import_response)) => {
+                        if import_response.success {
+                            println!(
+                                "Imported {} messages into {} conversations",
+                                import_response.messages, import_response.conversations
+                            );
+                        } else {
+                            println!("Chat import failed: {}", import_response.error);
+                        }
+                    }
+                    Some(proto::chat::Message::Privacy(privacy)) => {
+                        println!("Chat Privacy");
+                        println!("\tread receipts: {}", privacy.read_receipts);
+                    }
+                    Some(proto::chat::Message::EditHistory(edit_history)) => {
+                        println!("");
+                        println!(
+                            "Edit History [{}]",
+                            bs58::encode(edit_history.message_id).into_string()
+                        );
+                        for edit in edit_history.edits {
+                            if edit.edited_at == 0 {
+                                println!("\toriginal message");
+                            } else {
+                                println!("\tedited at {}", edit.edited_at);
+                            }
+                            println!("\t\t{}", edit.text);
+                        }
+                        println!("");
+                    }
+                    Some(proto::chat::Message::FailedList(failed_list)) => {
+                        println!("");
+                        println!("Failed Messages");
+                        for message in failed_list.messages {
+                            Self::print_failed_message(message);
+                        }
+                        println!("");
+                    }
                     _ => {
                         log::error!("unprocessable RPC chat message");
                     }
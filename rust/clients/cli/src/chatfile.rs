@@ -85,6 +85,52 @@ impl ChatFile {
 
                 Self::send_file_history_command(offset as u32, limit as u32);
             }
+            // cancel sending or receiving a file
+            cmd if cmd.starts_with("cancel ") => {
+                match cmd.strip_prefix("cancel ").unwrap().trim().parse::<u64>() {
+                    Ok(file_id) => Self::send_message(proto::chat_file::Message::FileCancel(
+                        proto::FileCancelRequest { file_id },
+                    )),
+                    Err(_) => log::error!("invalid file id"),
+                }
+            }
+            // accept a file waiting for manual acceptance
+            cmd if cmd.starts_with("accept ") => {
+                match cmd.strip_prefix("accept ").unwrap().trim().parse::<u64>() {
+                    Ok(file_id) => Self::send_message(proto::chat_file::Message::FileAccept(
+                        proto::FileAcceptRequest { file_id },
+                    )),
+                    Err(_) => log::error!("invalid file id"),
+                }
+            }
+            // set the file receive policy
+            cmd if cmd.starts_with("policy set ") => {
+                let command_string = cmd.strip_prefix("policy set ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                let max_size = iter.next().and_then(|value| value.parse::<u64>().ok());
+                let trusted_only = iter.next().and_then(|value| value.parse::<bool>().ok());
+                let manual_accept = iter.next().and_then(|value| value.parse::<bool>().ok());
+
+                match (max_size, trusted_only, manual_accept) {
+                    (Some(max_auto_accept_size), Some(trusted_only), Some(manual_accept)) => {
+                        Self::send_message(proto::chat_file::Message::FilePolicy(
+                            proto::FilePolicy {
+                                max_auto_accept_size,
+                                trusted_only,
+                                manual_accept,
+                            },
+                        ))
+                    }
+                    _ => log::error!("file policy set command incorrectly formatted"),
+                }
+            }
+            // request the file receive policy
+            cmd if cmd.starts_with("policy") => {
+                Self::send_message(proto::chat_file::Message::FilePolicyRequest(
+                    proto::FilePolicyRequest {},
+                ));
+            }
             // unknown command
             _ => log::error!("unknown file command"),
         }
@@ -142,6 +188,19 @@ impl ChatFile {
         );
     }
 
+    /// send a chat file message via rpc
+    fn send_message(message: proto::chat_file::Message) {
+        let proto_message = proto::ChatFile {
+            message: Some(message),
+        };
+
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chatfile.into(),
+            "".to_string(),
+        );
+    }
+
     /// send file history list command via rpc
     fn send_file_history_command(offset: u32, limit: u32) {
         // create file history message
@@ -194,9 +253,46 @@ impl ChatFile {
                                 "\t FileSize: {}, Description: {}",
                                 entry.file_size, entry.file_description
                             );
+                            println!("\t Verified: {}", entry.verified);
+                            if let Ok(state) = proto::FileState::try_from(entry.state) {
+                                println!("\t State: {}", state.as_str_name());
+                            }
                             println!("");
                         }
                     }
+                    Some(proto::chat_file::Message::SendFileResponse(response)) => {
+                        if response.success {
+                            println!("file {} scheduled for sending", response.file_id);
+                        } else {
+                            println!("file sending failed: {}", response.error);
+                        }
+                    }
+                    Some(proto::chat_file::Message::FileProgress(progress)) => {
+                        let direction = if progress.incoming {
+                            "received"
+                        } else {
+                            "sent"
+                        };
+                        println!(
+                            "file {}: {}/{} chunks {}, {}/{} bytes, {} bytes/s",
+                            progress.file_id,
+                            progress.chunks_done,
+                            progress.chunks_total,
+                            direction,
+                            progress.bytes_done,
+                            progress.file_size,
+                            progress.rate
+                        );
+                    }
+                    Some(proto::chat_file::Message::FilePolicy(policy)) => {
+                        println!("File Receive Policy");
+                        println!(
+                            "\t Max Auto Accept Size: {} bytes",
+                            policy.max_auto_accept_size
+                        );
+                        println!("\t Trusted Senders Only: {}", policy.trusted_only);
+                        println!("\t Manual Accept: {}", policy.manual_accept);
+                    }
                     _ => {
                         log::error!("unprocessable RPC file message");
                     }
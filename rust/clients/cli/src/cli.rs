@@ -9,6 +9,7 @@ use super::ble::Ble;
 use super::chat::Chat;
 use super::chatfile::ChatFile;
 use super::connections::Connections;
+use super::crypto::Crypto;
 use super::debug::Debug;
 use super::dtn::Dtn;
 use super::feed::Feed;
@@ -78,6 +79,10 @@ impl Cli {
             cmd if cmd.starts_with("dtn ") => {
                 Dtn::cli(cmd.strip_prefix("dtn ").unwrap());
             }
+            // crypto functions
+            cmd if cmd.starts_with("crypto ") => {
+                Crypto::cli(cmd.strip_prefix("crypto ").unwrap());
+            }
             // unknown command
             _ => log::error!("unknown command"),
         }
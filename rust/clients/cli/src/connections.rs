@@ -107,6 +107,8 @@ impl Connections {
                     address,
                     name,
                     enabled: true,
+                    reconnect_attempts: 0,
+                    reconnect_next: 0,
                 },
             )),
         };
@@ -124,6 +126,8 @@ impl Connections {
                     address,
                     name,
                     enabled: true,
+                    reconnect_attempts: 0,
+                    reconnect_next: 0,
                 },
             )),
         };
@@ -143,6 +147,8 @@ impl Connections {
                     address,
                     name: String::from(""),
                     enabled: false,
+                    reconnect_attempts: 0,
+                    reconnect_next: 0,
                 },
             )),
         };
@@ -162,6 +168,8 @@ impl Connections {
                     address,
                     name: String::from(""),
                     enabled: true,
+                    reconnect_attempts: 0,
+                    reconnect_next: 0,
                 },
             )),
         };
@@ -180,6 +188,8 @@ impl Connections {
                     address,
                     name: String::from(""),
                     enabled: false,
+                    reconnect_attempts: 0,
+                    reconnect_next: 0,
                 },
             )),
         };
@@ -252,12 +262,19 @@ impl Connections {
                         };
 
                         println!("Internet Peer Nodes List");
-                        println!("No. | Address | Name | Enabled");
+                        println!(
+                            "No. | Address | Name | Enabled | Reconnect Attempts | Next Reconnect"
+                        );
 
                         for node in proto_list.nodes {
                             println!(
-                                "{} | {} | {} | {}",
-                                line, node.address, node.name, node.enabled
+                                "{} | {} | {} | {} | {} | {}",
+                                line,
+                                node.address,
+                                node.name,
+                                node.enabled,
+                                node.reconnect_attempts,
+                                node.reconnect_next
                             );
                             line += 1;
                         }
@@ -0,0 +1,155 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Crypto module functions
+
+use super::rpc::Rpc;
+use prost::Message;
+use std::fmt;
+
+/// include generated protobuf RPC rust definition file
+mod proto {
+    include!("../../../libqaul/src/rpc/protobuf_generated/rust/qaul.rpc.crypto.rs");
+}
+
+/// Crypto module function handling
+pub struct Crypto {}
+
+impl Crypto {
+    /// CLI command interpretation
+    ///
+    /// The CLI commands of crypto are processed here
+    pub fn cli(command: &str) {
+        match command {
+            // list sessions
+            cmd if cmd.starts_with("sessions") => {
+                let user_id_str = cmd.strip_prefix("sessions").unwrap().trim();
+                if user_id_str.is_empty() {
+                    Self::crypto_sessions(Vec::new());
+                } else if let Ok(id) = Self::id_string_to_bin(user_id_str.to_string()) {
+                    Self::crypto_sessions(id);
+                } else {
+                    log::error!("invalid user id");
+                }
+            }
+            // reset sessions
+            cmd if cmd.starts_with("reset ") => match cmd.strip_prefix("reset ") {
+                Some(user_id_str) => {
+                    if let Ok(id) = Self::id_string_to_bin(user_id_str.trim().to_string()) {
+                        Self::crypto_reset(id);
+                    } else {
+                        log::error!("invalid user id");
+                    }
+                }
+                None => {
+                    log::error!("invalid command parameter");
+                }
+            },
+            // unknown command
+            _ => log::error!("unknown crypto command"),
+        }
+    }
+
+    /// Convert User ID from String to Binary
+    fn id_string_to_bin(id: String) -> Result<Vec<u8>, String> {
+        // check length
+        if id.len() < 52 {
+            return Err("User ID not long enough".to_string());
+        }
+
+        // convert input
+        match bs58::decode(id).into_vec() {
+            Ok(id_bin) => Ok(id_bin),
+            Err(e) => {
+                let err = fmt::format(format_args!("{}", e));
+                Err(err)
+            }
+        }
+    }
+
+    /// request the crypto sessions with a user
+    fn crypto_sessions(remote_id: Vec<u8>) {
+        // create crypto send message
+        let proto_message = proto::Crypto {
+            message: Some(proto::crypto::Message::SessionsRequest(
+                proto::CryptoSessionsRequest { remote_id },
+            )),
+        };
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Crypto.into(),
+            "".to_string(),
+        );
+    }
+
+    /// reset the crypto sessions with a user
+    fn crypto_reset(remote_id: Vec<u8>) {
+        // create crypto send message
+        let proto_message = proto::Crypto {
+            message: Some(proto::crypto::Message::ResetRequest(
+                proto::CryptoResetRequest { remote_id },
+            )),
+        };
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Crypto.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Process received RPC message
+    ///
+    /// Decodes received protobuf encoded binary RPC message
+    /// of the crypto module.
+    pub fn rpc(data: Vec<u8>) {
+        match proto::Crypto::decode(&data[..]) {
+            Ok(crypto) => match crypto.message {
+                Some(proto::crypto::Message::SessionsResponse(resp)) => {
+                    println!("====================================");
+                    println!("Crypto Sessions");
+                    for session in resp.sessions {
+                        let state = match proto::CryptoSessionState::try_from(session.state) {
+                            Ok(proto::CryptoSessionState::HalfOutgoing) => "handshake sent",
+                            Ok(proto::CryptoSessionState::HalfIncoming) => "handshake received",
+                            Ok(proto::CryptoSessionState::Transport) => "established",
+                            Err(_) => "unknown",
+                        };
+                        println!(
+                            "{} session {}{}",
+                            bs58::encode(session.remote_id).into_string(),
+                            session.session_id,
+                            if session.active { " (active)" } else { "" }
+                        );
+                        println!("\tState: {}", state);
+                        println!("\tInitiator: {}", session.initiator);
+                        println!("\tCreated at: {}", session.created_at);
+                        println!("\tLast used: {}", session.last_used);
+                        println!("\tSent messages: {}", session.sent);
+                        println!("\tHighest received index: {}", session.received);
+                    }
+                }
+                Some(proto::crypto::Message::ResetResponse(resp)) => {
+                    println!("====================================");
+                    println!(
+                        "Crypto Reset {}",
+                        bs58::encode(resp.remote_id).into_string()
+                    );
+                    if resp.success {
+                        println!("\tSuccess");
+                    } else {
+                        println!("\tFailed");
+                        println!("\t{}", resp.error_message);
+                    }
+                }
+                _ => {
+                    log::error!("unprocessable RPC crypto message");
+                }
+            },
+            Err(error) => {
+                log::error!("{:?}", error);
+            }
+        }
+    }
+}
@@ -46,6 +46,10 @@ impl Debug {
             // request storage path location
             cmd if cmd.starts_with("path") => {
                 Self::debug_path_send();
+            },
+            // request messaging statistics
+            cmd if cmd.starts_with("messaging") => {
+                Self::messaging_stats_send();
             },            
             // unknown command
             _ => log::error!("unknown debug command"),
@@ -131,6 +135,23 @@ impl Debug {
         Rpc::send_message(buf, super::rpc::proto::Modules::Debug.into(), "".to_string());        
     }
 
+    /// request the statistics of the forwarded
+    /// and dropped messages from libqaul
+    fn messaging_stats_send() {
+        // create MessagingStatsRequest
+        let proto_message = proto::Debug {
+            message: Some(proto::debug::Message::MessagingStatsRequest(
+                proto::MessagingStatsRequest{}
+            )),
+        };
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message.encode(&mut buf).expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(buf, super::rpc::proto::Modules::Debug.into(), "".to_string());
+    }
+
     /// send a debugging message to libqaul that
     /// let's it panic.
     fn panic() {
@@ -149,6 +170,16 @@ impl Debug {
         Rpc::send_message(buf, super::rpc::proto::Modules::Debug.into(), "".to_string());
     }
 
+    /// print compression statistics and the compression ratio
+    fn print_compression_stats(title: &str, stats: &proto::CompressionStats) {
+        println!("{}: {}", title, stats.messages);
+        println!("\tUncompressed: {} bytes", stats.bytes_uncompressed);
+        println!("\tCompressed: {} bytes", stats.bytes_compressed);
+        if stats.bytes_uncompressed > 0 {
+            println!("\tRatio: {:.1}%", stats.bytes_compressed as f64 * 100.0 / stats.bytes_uncompressed as f64);
+        }
+    }
+
     /// Process received RPC message
     /// 
     /// Decodes received protobuf encoded binary RPC message
@@ -165,6 +196,28 @@ impl Debug {
                         // printout path
                         println!("Storage Path: {}", storage_path_response.storage_path);
                     },
+                    Some(proto::debug::Message::MessagingStatsResponse(stats)) => {
+                        // printout statistics
+                        println!("Forwarded Messages: {}", stats.forwarded);
+                        println!("Dropped Messages");
+                        println!("\tHop limit exhausted: {}", stats.dropped_hop_limit);
+                        println!("\tExpired: {}", stats.dropped_expired);
+                        if let Some(queue) = stats.send_queue {
+                            println!("Scheduled Messages");
+                            println!("\tControl: {}", queue.control);
+                            println!("\tConfirmation: {}", queue.confirmation);
+                            println!("\tChat: {}", queue.chat);
+                            println!("\tGroup: {}", queue.group);
+                            println!("\tFile: {}", queue.file);
+                            println!("\tForward: {}", queue.forward);
+                        }
+                        if let Some(compression) = stats.message_compression {
+                            Self::print_compression_stats("Compressed Messages", &compression);
+                        }
+                        if let Some(compression) = stats.routing_compression {
+                            Self::print_compression_stats("Compressed Routing Information", &compression);
+                        }
+                    },
                     _ => {
                         log::error!("unprocessable RPC debug message");
                     },
@@ -183,6 +183,15 @@ impl Dtn {
                     println!("\tUsed Storage Size: {} MB", dtn_state.used_size);
                     println!("\tDTN Messages: {}", dtn_state.dtn_message_count);
                     println!("\tUnconfirmed Messages: {}", dtn_state.unconfirmed_count);
+                    if let Some(queue) = dtn_state.send_queue {
+                        println!("\tScheduled Messages");
+                        println!("\t\tControl: {}", queue.control);
+                        println!("\t\tConfirmation: {}", queue.confirmation);
+                        println!("\t\tChat: {}", queue.chat);
+                        println!("\t\tGroup: {}", queue.group);
+                        println!("\t\tFile: {}", queue.file);
+                        println!("\t\tForward: {}", queue.forward);
+                    }
                 }
                 Some(proto::dtn::Message::DtnConfigResponse(dtn_config)) => {
                     println!("====================================");
@@ -435,6 +435,16 @@ impl Group {
                     );
                     println!("\t\t{}", file_content.file_description);
                 }
+                Some(proto_chat::chat_content_message::Message::Reply(reply)) => {
+                    println!("\t\t> {}", reply.quote);
+                    println!("\t\t{}", reply.text);
+                }
+                Some(proto_chat::chat_content_message::Message::Reaction(reaction)) => {
+                    println!("\t\treacted {}", reaction.emoji);
+                }
+                Some(proto_chat::chat_content_message::Message::MessageDeleted(_)) => {
+                    println!("\t\tThis message was deleted");
+                }
                 Some(proto_chat::chat_content_message::Message::GroupEvent(group_event)) => {
                     match proto_chat::GroupEventType::try_from(group_event.event_type) {
                         Ok(proto_chat::GroupEventType::Joined) => {
@@ -112,6 +112,9 @@ impl Rpc {
                     Ok(proto::Modules::Dtn) => {
                         super::dtn::Dtn::rpc(message.data);
                     }
+                    Ok(proto::Modules::Crypto) => {
+                        super::crypto::Crypto::rpc(message.data);
+                    }
                     Ok(proto::Modules::None) => {}
                     Err(_) => {}
                 }
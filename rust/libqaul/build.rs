@@ -26,6 +26,14 @@ fn main() {
         "MessageReceptionConfirmed",
         "#[derive(serde::Serialize, serde::Deserialize)]",
     );
+    // reply, reaction and read receipt fields of chat messages are filled in
+    // when read from the data base, they are not saved with the message
+    prost_build.field_attribute(".qaul.rpc.chat.ChatMessage.reply_to", "#[serde(skip)]");
+    prost_build.field_attribute(".qaul.rpc.chat.ChatMessage.reactions", "#[serde(skip)]");
+    prost_build.field_attribute(
+        ".qaul.rpc.chat.MessageReceptionConfirmed.read_at",
+        "#[serde(skip)]",
+    );
 
     // make network messaging serializable
     // in order to save them in the data base
@@ -88,6 +96,7 @@ fn main() {
                 "services/messaging/messaging.proto",
                 "services/dtn/dtn_rpc.proto",
                 "services/crypto/crypto_net.proto",
+                "services/crypto/crypto_rpc.proto",
             ],
             &["src"],
         )
@@ -159,6 +168,11 @@ fn main() {
         to.join("qaul.rpc.dtn.rs"),
     )
     .unwrap();
+    fs::copy(
+        Path::new(&out_dir).join("qaul.rpc.crypto.rs"),
+        to.join("qaul.rpc.crypto.rs"),
+    )
+    .unwrap();
     fs::copy(
         Path::new(&out_dir).join("qaul.rpc.rtc.rs"),
         to.join("qaul.rpc.rtc.rs"),
@@ -279,6 +293,11 @@ fn main() {
         Path::new("src/services/dtn/qaul.rpc.dtn.rs"),
     )
     .unwrap();
+    fs::copy(
+        &Path::new(&out_dir).join("qaul.rpc.crypto.rs"),
+        Path::new("src/services/crypto/qaul.rpc.crypto.rs"),
+    )
+    .unwrap();
     fs::copy(
         &Path::new(&out_dir).join("qaul.rpc.rtc.rs"),
         Path::new("src/services/rtc/qaul.rpc.rtc.rs"),
@@ -0,0 +1,192 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # LAN Beacon Discovery
+//!
+//! **Discover LAN nodes via UDP broadcast beacons.**
+//!
+//! Many networks block multicast traffic, which prevents mdns
+//! from discovering neighbours.
+//! As an alternative, each node periodically broadcasts a small
+//! beacon to the configured UDP port, containing its node ID and
+//! the ports the LAN module listens on.
+//!
+//! A node receiving a beacon dials the advertised addresses,
+//! using the IP address the beacon was sent from.
+
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+
+use crate::utilities::timestamp::Timestamp;
+
+/// prefix of every beacon datagram
+const BEACON_MAGIC: &[u8; 4] = b"qaul";
+
+/// maximal size of a beacon datagram
+const BEACON_MAX_SIZE: usize = 1024;
+
+/// Beacon message broadcasted into the LAN
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct BeaconMessage {
+    /// node id of the sending node
+    node_id: Vec<u8>,
+    /// addresses the LAN module of the sending node listens on
+    addresses: Vec<Vec<u8>>,
+}
+
+/// UDP Broadcast Beacon
+pub struct Beacon {
+    /// socket the beacons are sent and received on
+    socket: UdpSocket,
+    /// UDP port the beacons are broadcasted to
+    port: u16,
+    /// interval between two beacons in milliseconds
+    interval: u64,
+    /// timestamp of the last beacon sent
+    last_sent: u64,
+}
+
+impl Beacon {
+    /// Create the beacon socket
+    ///
+    /// Returns None if the socket could not be created.
+    pub fn new(port: u16, interval: u64) -> Option<Self> {
+        let socket = match UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port))) {
+            Ok(socket) => socket,
+            Err(e) => {
+                log::error!("LAN beacon socket binding to port {} failed: {}", port, e);
+                return None;
+            }
+        };
+        if let Err(e) = socket.set_broadcast(true) {
+            log::error!("LAN beacon broadcast not permitted: {}", e);
+            return None;
+        }
+        if let Err(e) = socket.set_nonblocking(true) {
+            log::error!("LAN beacon socket configuration error: {}", e);
+            return None;
+        }
+
+        Some(Beacon {
+            socket,
+            port,
+            interval: interval * 1000,
+            last_sent: 0,
+        })
+    }
+
+    /// Broadcast a beacon if the interval has passed
+    ///
+    /// Only the IPv4 listening addresses are advertised,
+    /// as the beacon is only broadcasted via IPv4.
+    pub fn send(&mut self, node_id: &PeerId, listeners: &Vec<Multiaddr>) {
+        let now = Timestamp::get_timestamp();
+        if now - self.last_sent < self.interval {
+            return;
+        }
+        self.last_sent = now;
+
+        let mut addresses: Vec<Vec<u8>> = Vec::new();
+        for address in listeners {
+            match address.iter().next() {
+                Some(Protocol::Ip4(ip)) if !ip.is_loopback() => {
+                    addresses.push(address.to_vec());
+                }
+                _ => {}
+            }
+        }
+        if addresses.is_empty() {
+            return;
+        }
+
+        let message = BeaconMessage {
+            node_id: node_id.to_bytes(),
+            addresses,
+        };
+        let mut data = BEACON_MAGIC.to_vec();
+        match bincode::serialize(&message) {
+            Ok(encoded) => data.extend(encoded),
+            Err(e) => {
+                log::error!("LAN beacon encoding error: {}", e);
+                return;
+            }
+        }
+
+        if let Err(e) = self
+            .socket
+            .send_to(&data, SocketAddr::from((Ipv4Addr::BROADCAST, self.port)))
+        {
+            log::debug!("LAN beacon sending error: {}", e);
+        }
+    }
+
+    /// Receive all pending beacons
+    ///
+    /// Returns a list of the discovered nodes and their addresses.
+    /// The IP addresses of the advertised addresses are replaced by the
+    /// IP address the beacon was received from.
+    /// Beacons from the node itself are ignored.
+    pub fn receive(&self, node_id: &PeerId) -> Vec<(PeerId, Vec<Multiaddr>)> {
+        let mut discovered: Vec<(PeerId, Vec<Multiaddr>)> = Vec::new();
+        let mut buffer = [0u8; BEACON_MAX_SIZE];
+
+        loop {
+            let (size, sender) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    log::debug!("LAN beacon receiving error: {}", e);
+                    break;
+                }
+            };
+
+            if let Some((peer_id, addresses)) = Self::decode(&buffer[..size], &sender) {
+                if peer_id == *node_id {
+                    continue;
+                }
+                log::trace!("LAN beacon from {} received: {:?}", peer_id, addresses);
+                discovered.push((peer_id, addresses));
+            }
+        }
+
+        discovered
+    }
+
+    /// decode a beacon datagram
+    fn decode(data: &[u8], sender: &SocketAddr) -> Option<(PeerId, Vec<Multiaddr>)> {
+        let sender_ip = match sender {
+            SocketAddr::V4(sender) => *sender.ip(),
+            SocketAddr::V6(_) => return None,
+        };
+
+        let encoded = data.strip_prefix(&BEACON_MAGIC[..])?;
+        let message: BeaconMessage = bincode::deserialize(encoded).ok()?;
+        let peer_id = PeerId::from_bytes(&message.node_id).ok()?;
+
+        let mut addresses: Vec<Multiaddr> = Vec::new();
+        for bytes in message.addresses {
+            let address = match Multiaddr::try_from(bytes) {
+                Ok(address) => address,
+                Err(_) => continue,
+            };
+            // replace the advertised IP with the sender IP
+            let address: Multiaddr = address
+                .iter()
+                .map(|protocol| match protocol {
+                    Protocol::Ip4(_) => Protocol::Ip4(sender_ip),
+                    protocol => protocol,
+                })
+                .collect();
+            if !addresses.contains(&address) {
+                addresses.push(address);
+            }
+        }
+
+        if addresses.is_empty() {
+            return None;
+        }
+
+        Some((peer_id, addresses))
+    }
+}
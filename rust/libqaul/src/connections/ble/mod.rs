@@ -9,6 +9,8 @@
 //!
 //! * Android
 //! * iOS
+//!
+//! On Linux the BLE module can be tested via the BLE simulator.
 
 use libp2p::{floodsub::Topic, PeerId};
 use prost::Message;
@@ -23,6 +25,9 @@ use crate::rpc::{sys::Sys, Rpc};
 use crate::services::{feed, messaging};
 use crate::utilities::{qaul_id::QaulId, timestamp::Timestamp};
 
+#[cfg(all(unix, not(any(target_os = "android", target_os = "ios"))))]
+pub mod simulator;
+
 /// Protobuf BLE system communication with BLE module
 ///
 /// Import protobuf message definition generated by
@@ -128,6 +133,10 @@ impl Ble {
             BLE.set(RwLock::new(ble));
         }
 
+        // initialize the BLE simulator
+        #[cfg(all(unix, not(any(target_os = "android", target_os = "ios"))))]
+        simulator::BleSimulator::init();
+
         //#[cfg(target_os = "android")]
         Self::info_send_request();
     }
@@ -307,7 +316,9 @@ impl Ble {
         log::info!("BLE send stop request");
 
         // create stop message
-        let message = proto::BleStopRequest {};
+        let message = proto::Ble {
+            message: Some(proto::ble::Message::StopRequest(proto::BleStopRequest {})),
+        };
 
         // encode message
         let mut buf = Vec::with_capacity(message.encoded_len());
@@ -0,0 +1,505 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # BLE Simulator
+//!
+//! **Software stand-in for the BLE system module.**
+//!
+//! On Android and iOS the BLE module of libqaul communicates
+//! with the BLE stack of the operating system via the
+//! `qaul.sys.ble` protobuf messages.
+//! This simulator implements the system side of these messages
+//! on Linux, so that the BLE logic and routing can be developed
+//! and tested without BLE hardware.
+//!
+//! All simulated devices share a directory.
+//! Each started device binds a Unix datagram socket in this
+//! directory, named after the hex encoded small id of the node.
+//!
+//! The simulator emulates:
+//!
+//! * advertising and discovery: devices periodically advertise
+//!   themselves to all other sockets in the directory.
+//! * range: optionally only the configured neighbours are visible.
+//! * RSSI: discovered devices are reported with the configured
+//!   signal strength and a random deviation.
+//! * MTU: messages are split into packets of the configured MTU size
+//!   and reassembled by the receiver.
+//! * disappearance: a device that stopped advertising is reported
+//!   unavailable after the configured timeout.
+
+use prost::Message;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use state::InitCell;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::proto;
+use crate::rpc::sys::Sys;
+use crate::storage::configuration::{self, Configuration};
+use crate::utilities::timestamp::Timestamp;
+
+/// Simulator State
+static SIMULATOR: InitCell<RwLock<BleSimulator>> = InitCell::new();
+
+/// maximal random deviation of the simulated RSSI in dBm
+const RSSI_DEVIATION: i32 = 5;
+
+/// Packet exchanged between the simulated devices
+#[derive(Serialize, Deserialize, Clone, Debug)]
+enum SimulatorPacket {
+    /// advertisement of a device
+    Advertisement { qaul_id: Vec<u8> },
+    /// MTU sized part of a direct message
+    Fragment {
+        sender_id: Vec<u8>,
+        message_id: Vec<u8>,
+        index: u32,
+        count: u32,
+        data: Vec<u8>,
+    },
+}
+
+/// Message in reassembly
+struct Reassembly {
+    /// received fragments
+    fragments: Vec<Option<Vec<u8>>>,
+    /// number of received fragments
+    received: usize,
+    /// timestamp of the last received fragment
+    timestamp: u64,
+}
+
+/// A started simulated device
+struct SimulatedDevice {
+    /// small id of this node
+    qaul_id: Vec<u8>,
+    /// socket path of this node
+    path: PathBuf,
+    /// socket of this node
+    socket: Arc<UnixDatagram>,
+    /// flag to stop the device thread
+    running: Arc<AtomicBool>,
+    /// devices in range with the timestamp they were last seen
+    devices: Arc<RwLock<BTreeMap<Vec<u8>, u64>>>,
+}
+
+/// BLE Simulator
+pub struct BleSimulator {
+    /// simulator configuration
+    config: configuration::BleSimulator,
+    /// the simulated device, if started
+    device: Option<SimulatedDevice>,
+}
+
+impl BleSimulator {
+    /// initialize the simulator from the configuration
+    pub fn init() {
+        let config = Configuration::get().ble.simulator.clone();
+        if config.enabled {
+            log::info!("BLE simulator enabled in {}", config.path);
+        }
+
+        SIMULATOR.set(RwLock::new(BleSimulator {
+            config,
+            device: None,
+        }));
+    }
+
+    /// process a SYS message sent by the BLE module
+    ///
+    /// Messages are ignored if the simulator is not enabled.
+    pub fn sys_send(data: Vec<u8>) {
+        match SIMULATOR.try_get() {
+            Some(simulator) if simulator.read().unwrap().config.enabled => {}
+            _ => return,
+        }
+
+        let ble = match proto::Ble::decode(&data[..]) {
+            Ok(ble) => ble,
+            Err(e) => {
+                log::error!("BLE simulator: {:?}", e);
+                return;
+            }
+        };
+
+        match ble.message {
+            Some(proto::ble::Message::InfoRequest(_)) => {
+                Self::info_response();
+            }
+            Some(proto::ble::Message::StartRequest(start_request)) => {
+                let result = Self::start(start_request.qaul_id);
+                Self::reply(proto::ble::Message::StartResult(proto::BleStartResult {
+                    success: result.is_ok(),
+                    error_reason: proto::BleError::UnknownError.into(),
+                    error_message: result.err().unwrap_or_default(),
+                }));
+            }
+            Some(proto::ble::Message::StopRequest(_)) => {
+                Self::stop();
+                Self::reply(proto::ble::Message::StopResult(proto::BleStopResult {
+                    success: true,
+                    error_reason: proto::BleError::UnknownError.into(),
+                    error_message: String::from(""),
+                }));
+            }
+            Some(proto::ble::Message::DirectSend(direct_send)) => {
+                let id = direct_send.message_id.clone();
+                let result = Self::direct_send(direct_send);
+                Self::reply(proto::ble::Message::DirectSendResult(
+                    proto::BleDirectSendResult {
+                        id,
+                        success: result.is_ok(),
+                        error_message: result.err().unwrap_or_default(),
+                    },
+                ));
+            }
+            _ => {
+                log::error!("BLE simulator: unprocessable BLE Sys message");
+            }
+        }
+    }
+
+    /// send the simulated device info
+    fn info_response() {
+        let mtu = SIMULATOR.get().read().unwrap().config.mtu;
+
+        let device = proto::BleDeviceInfo {
+            ble_support: true,
+            id: String::from("00:00:00:00:00:00"),
+            name: String::from("qaul BLE simulator"),
+            bluetooth_on: true,
+            adv_extended: true,
+            adv_extended_bytes: mtu as u32,
+            le_2m: false,
+            le_coded: false,
+            le_audio: false,
+            le_periodic_adv_support: false,
+            le_multiple_adv_support: false,
+            offload_filter_support: false,
+            offload_scan_batching_support: false,
+        };
+
+        Self::reply(proto::ble::Message::InfoResponse(proto::BleInfoResponse {
+            device: Some(device),
+        }));
+    }
+
+    /// start the simulated device
+    fn start(qaul_id: Vec<u8>) -> Result<(), String> {
+        let mut simulator = SIMULATOR.get().write().unwrap();
+        if simulator.device.is_some() {
+            return Err(String::from("BLE simulator already started"));
+        }
+        if simulator.config.mtu == 0 {
+            return Err(String::from("BLE simulator MTU needs to be bigger than 0"));
+        }
+
+        let directory = Path::new(&simulator.config.path);
+        if let Err(e) = fs::create_dir_all(directory) {
+            return Err(format!("BLE simulator directory: {}", e));
+        }
+        let path = directory.join(Self::socket_name(&qaul_id));
+        let _ = fs::remove_file(&path);
+
+        let socket = UnixDatagram::bind(&path).map_err(|e| e.to_string())?;
+        socket
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .map_err(|e| e.to_string())?;
+
+        let device = SimulatedDevice {
+            qaul_id,
+            path,
+            socket: Arc::new(socket),
+            running: Arc::new(AtomicBool::new(true)),
+            devices: Arc::new(RwLock::new(BTreeMap::new())),
+        };
+
+        // run the device
+        let config = simulator.config.clone();
+        let qaul_id = device.qaul_id.clone();
+        let socket = device.socket.clone();
+        let running = device.running.clone();
+        let devices = device.devices.clone();
+        thread::spawn(move || Self::run(config, qaul_id, socket, running, devices));
+
+        log::info!("BLE simulator started on {:?}", device.path);
+        simulator.device = Some(device);
+
+        Ok(())
+    }
+
+    /// stop the simulated device
+    fn stop() {
+        let mut simulator = SIMULATOR.get().write().unwrap();
+        if let Some(device) = simulator.device.take() {
+            device.running.store(false, Ordering::Relaxed);
+            let _ = fs::remove_file(&device.path);
+            log::info!("BLE simulator stopped");
+        }
+    }
+
+    /// send a direct message to a device in range
+    ///
+    /// The message is split into MTU sized packets.
+    fn direct_send(message: proto::BleDirectSend) -> Result<(), String> {
+        let simulator = SIMULATOR.get().read().unwrap();
+        let device = match &simulator.device {
+            Some(device) => device,
+            None => return Err(String::from("BLE simulator not started")),
+        };
+
+        if !device
+            .devices
+            .read()
+            .unwrap()
+            .contains_key(&message.receiver_id)
+        {
+            return Err(String::from("device unavailable"));
+        }
+
+        let path = Path::new(&simulator.config.path).join(Self::socket_name(&message.receiver_id));
+        let mut chunks: Vec<&[u8]> = message.data.chunks(simulator.config.mtu).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            let packet = SimulatorPacket::Fragment {
+                sender_id: device.qaul_id.clone(),
+                message_id: message.message_id.clone(),
+                index: index as u32,
+                count: chunks.len() as u32,
+                data: chunk.to_vec(),
+            };
+            let data = bincode::serialize(&packet).map_err(|e| e.to_string())?;
+            device
+                .socket
+                .send_to(&data, &path)
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /// device thread: advertise, receive and expire devices
+    fn run(
+        config: configuration::BleSimulator,
+        qaul_id: Vec<u8>,
+        socket: Arc<UnixDatagram>,
+        running: Arc<AtomicBool>,
+        devices: Arc<RwLock<BTreeMap<Vec<u8>, u64>>>,
+    ) {
+        let own_name = Self::socket_name(&qaul_id);
+        let mut reassemblies: BTreeMap<(Vec<u8>, Vec<u8>), Reassembly> = BTreeMap::new();
+        let mut buffer = vec![0u8; config.mtu + 1024];
+        let mut last_advertisement = 0;
+
+        while running.load(Ordering::Relaxed) {
+            let now = Timestamp::get_timestamp();
+
+            // advertise to all devices in the directory
+            if now - last_advertisement >= config.interval {
+                last_advertisement = now;
+                Self::advertise(&config, &own_name, &qaul_id, &socket);
+            }
+
+            // report silent devices as unavailable
+            let expired: Vec<Vec<u8>> = devices
+                .read()
+                .unwrap()
+                .iter()
+                .filter(|(_, last_seen)| now - **last_seen > config.timeout)
+                .map(|(id, _)| id.clone())
+                .collect();
+            for id in expired {
+                devices.write().unwrap().remove(&id);
+                reassemblies.retain(|(sender_id, _), _| *sender_id != id);
+                log::debug!(
+                    "BLE simulator: device {} unavailable",
+                    Self::socket_name(&id)
+                );
+                Self::reply(proto::ble::Message::DeviceUnavailable(
+                    proto::BleDeviceUnavailable { qaul_id: id },
+                ));
+            }
+            reassemblies.retain(|_, reassembly| now - reassembly.timestamp <= config.timeout);
+
+            // receive packets
+            let size = match socket.recv(&mut buffer) {
+                Ok(size) => size,
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                    continue
+                }
+                Err(e) => {
+                    log::debug!("BLE simulator receiving error: {}", e);
+                    continue;
+                }
+            };
+            let packet: SimulatorPacket = match bincode::deserialize(&buffer[..size]) {
+                Ok(packet) => packet,
+                Err(e) => {
+                    log::debug!("BLE simulator decoding error: {}", e);
+                    continue;
+                }
+            };
+
+            match packet {
+                SimulatorPacket::Advertisement { qaul_id } => {
+                    if !Self::in_range(&config, &qaul_id) {
+                        continue;
+                    }
+                    let discovered = devices
+                        .write()
+                        .unwrap()
+                        .insert(qaul_id.clone(), now)
+                        .is_none();
+                    if discovered {
+                        let rssi = config.rssi
+                            + rand::thread_rng().gen_range(-RSSI_DEVIATION..=RSSI_DEVIATION);
+                        Self::reply(proto::ble::Message::DeviceDiscovered(
+                            proto::BleDeviceDiscovered { qaul_id, rssi },
+                        ));
+                    }
+                }
+                SimulatorPacket::Fragment {
+                    sender_id,
+                    message_id,
+                    index,
+                    count,
+                    data,
+                } => {
+                    // only devices in range can send
+                    if !devices.read().unwrap().contains_key(&sender_id)
+                        || count == 0
+                        || index >= count
+                    {
+                        continue;
+                    }
+
+                    let key = (sender_id, message_id);
+                    let reassembly = reassemblies.entry(key.clone()).or_insert(Reassembly {
+                        fragments: vec![None; count as usize],
+                        received: 0,
+                        timestamp: now,
+                    });
+                    if reassembly.fragments.len() != count as usize {
+                        continue;
+                    }
+                    reassembly.timestamp = now;
+                    if reassembly.fragments[index as usize].is_none() {
+                        reassembly.fragments[index as usize] = Some(data);
+                        reassembly.received += 1;
+                    }
+
+                    if reassembly.received == reassembly.fragments.len() {
+                        if let Some(reassembly) = reassemblies.remove(&key) {
+                            let data: Vec<u8> = reassembly
+                                .fragments
+                                .into_iter()
+                                .flatten()
+                                .flatten()
+                                .collect();
+                            Self::reply(proto::ble::Message::DirectReceived(
+                                proto::BleDirectReceived { from: key.0, data },
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// send an advertisement to all other sockets in the directory
+    ///
+    /// Sockets without a listening device are left over from
+    /// crashed nodes and are removed.
+    fn advertise(
+        config: &configuration::BleSimulator,
+        own_name: &str,
+        qaul_id: &Vec<u8>,
+        socket: &UnixDatagram,
+    ) {
+        let packet = SimulatorPacket::Advertisement {
+            qaul_id: qaul_id.clone(),
+        };
+        let data = match bincode::serialize(&packet) {
+            Ok(data) => data,
+            Err(e) => {
+                log::error!("BLE simulator encoding error: {}", e);
+                return;
+            }
+        };
+
+        let entries = match fs::read_dir(&config.path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::debug!("BLE simulator directory error: {}", e);
+                return;
+            }
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".sock") || name == own_name {
+                continue;
+            }
+            match socket.send_to(&data, entry.path()) {
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+                    let _ = fs::remove_file(entry.path());
+                }
+                Err(e) => {
+                    log::trace!("BLE simulator advertisement to {} failed: {}", name, e);
+                }
+            }
+        }
+    }
+
+    /// check if a device is within the simulated range
+    fn in_range(config: &configuration::BleSimulator, qaul_id: &Vec<u8>) -> bool {
+        if config.neighbours.is_empty() {
+            return true;
+        }
+        let id = Self::hex(qaul_id);
+        config
+            .neighbours
+            .iter()
+            .any(|neighbour| neighbour.to_lowercase() == id)
+    }
+
+    /// socket file name of a device
+    fn socket_name(qaul_id: &Vec<u8>) -> String {
+        format!("{}.sock", Self::hex(qaul_id))
+    }
+
+    /// lower case hex representation of an id
+    fn hex(id: &Vec<u8>) -> String {
+        id.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// send a SYS message to the BLE module
+    fn reply(message: proto::ble::Message) {
+        let message = proto::Ble {
+            message: Some(message),
+        };
+
+        let mut buf = Vec::with_capacity(message.encoded_len());
+        message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        Sys::send_to_libqaul(buf);
+    }
+}
@@ -22,17 +22,53 @@
 //!   - /ip4/0.0.0.0/tcp/9229
 //!   - /ip6/::/udp/9229/quic-v1
 //!   - /ip6/::/tcp/9229
+//!   - /ip4/0.0.0.0/tcp/9230/ws
+//!   websocket:
+//!     tls_certificate: ""
+//!     tls_private_key: ""
+//!   proxy:
+//!     enabled: false
+//!     address: 127.0.0.1:9050
+//!   reconnect:
+//!     initial_delay: 10
+//!     max_delay: 600
+//!     jitter: 20
 //! ```
+//!
+//! Peers that can't be reached are redialed with an exponential
+//! backoff, starting at `initial_delay` seconds and doubling with
+//! every failed attempt, up to `max_delay` seconds.
+//! A random jitter of `jitter` percent is applied to each delay.
+//! The backoff is reset once a connection was successfully established,
+//! and all peers are redialed immediately when the local network
+//! interfaces change.
+//!
+//! Next to TCP and QUIC, the module listens on and dials websocket
+//! addresses (`/ws` and `/wss`), which allows to reach community nodes
+//! behind reverse proxies and restrictive firewalls.
+//! Listening on `/wss` addresses requires the paths to a TLS
+//! certificate chain and its private key to be configured
+//! in the `websocket` section.
+//!
+//! All outgoing connections can be routed through a SOCKS5 proxy,
+//! e.g. a local Tor daemon, by enabling the `proxy` section.
+//! Host names and `.onion` addresses are resolved by the proxy.
+//! In this mode QUIC is disabled and the module doesn't listen
+//! for incoming connections.
 
+use base64::Engine;
 use libp2p::{
+    core::{upgrade, Transport},
+    dns,
     floodsub::{Floodsub, FloodsubEvent},
     identify,
     identity::Keypair,
     noise, ping,
     swarm::{NetworkBehaviour, Swarm},
-    tcp, yamux, Multiaddr, PeerId, SwarmBuilder,
+    tcp, websocket, yamux, Multiaddr, PeerId, SwarmBuilder,
 };
 use prost::Message;
+use rand::{thread_rng, Rng};
 use state::InitCell;
 use std::time::Duration;
 use std::{
@@ -40,11 +76,11 @@ use std::{
     sync::RwLock,
 };
 
-use crate::connections::{events, ConnectionModule};
+use crate::connections::{events, socks5::Socks5Transport, ConnectionModule};
 use crate::node::Node;
 use crate::services::feed::proto_net;
 use crate::services::feed::Feed;
-use crate::storage::configuration::Configuration;
+use crate::storage::configuration::{Configuration, InternetWebsocket};
 use crate::utilities::timestamp::Timestamp;
 use qaul_info::{QaulInfo, QaulInfoEvent};
 use qaul_messaging::{QaulMessaging, QaulMessagingEvent};
@@ -131,10 +167,15 @@ impl QaulInternetBehaviour {
     }
 }
 
+/// Reconnection state of an unreachable Internet peer
 pub struct InternetReConnection {
     pub address: Multiaddr,
+    /// number of failed redial attempts since the last successful connection
     pub attempt: u32,
+    /// timestamp of the last redial attempt
     pub last_try: u64,
+    /// timestamp of the next scheduled redial attempt
+    pub next_try: u64,
 }
 pub struct InternetReConnections {
     peers: HashMap<Multiaddr, InternetReConnection>,
@@ -220,7 +261,82 @@ impl Internet {
         };
         behaviour.floodsub.subscribe(Node::get_topic());
 
-        let mut swarm = SwarmBuilder::with_existing_identity(node_keys.to_owned())
+        // create swarm
+        //
+        // If a SOCKS5 proxy is configured, all connections
+        // are dialed via the proxy.
+        let proxy = config.internet.proxy.clone();
+        let websocket_tls = Self::websocket_tls_config(&config.internet.websocket);
+        drop(config);
+
+        let mut swarm = if proxy.enabled {
+            Self::create_proxy_swarm(node_keys, behaviour, proxy.address.clone())
+        } else {
+            Self::create_swarm(node_keys, behaviour, websocket_tls).await
+        };
+
+        log::trace!("Internet.init() swarm created");
+
+        // connect swarm to the listening interfaces defined in
+        // the configuration array config.internet.listen
+        let config = Configuration::get();
+
+        if proxy.enabled {
+            log::info!(
+                "INTERNET dialing via SOCKS5 proxy {}, listening is disabled",
+                proxy.address
+            );
+        } else {
+            for listen in &config.internet.listen {
+                match Swarm::listen_on(&mut swarm, listen.parse().expect("can get a local socket"))
+                {
+                    Ok(listener_id) => {
+                        log::info!(
+                            "INTERNET listening on `{}` with ID {:?}",
+                            listen,
+                            listener_id
+                        );
+                    }
+                    Err(e) => {
+                        log::error!("Error INTERNET start listening on `{}`: {}", listen, e);
+                    }
+                }
+            }
+        }
+
+        // connect to remote peers that are specified in
+        // the configuration config.internet.peers
+        Self::peer_connect(&config, &mut swarm);
+
+        log::trace!("Internet.init() peer_connect");
+
+        // construct internet object
+        let internet = Internet { swarm };
+
+        internet
+    }
+
+    /// create the swarm with direct TCP, QUIC and websocket connections
+    async fn create_swarm(
+        node_keys: &Keypair,
+        behaviour: QaulInternetBehaviour,
+        websocket_tls: Option<websocket::tls::Config>,
+    ) -> Swarm<QaulInternetBehaviour> {
+        // create websocket transport
+        //
+        // The websocket transport listens on and dials
+        // `/ws` and `/wss` multiaddresses.
+        let websocket_tcp = dns::async_std::Transport::system(tcp::async_io::Transport::new(
+            tcp::Config::new().nodelay(true),
+        ))
+        .await
+        .unwrap();
+        let mut websocket = websocket::WsConfig::new(websocket_tcp);
+        if let Some(tls_config) = websocket_tls {
+            websocket.set_tls_config(tls_config);
+        }
+
+        SwarmBuilder::with_existing_identity(node_keys.to_owned())
             .with_async_std()
             .with_tcp(
                 tcp::Config::new().nodelay(true),
@@ -229,6 +345,15 @@ impl Internet {
             )
             .unwrap()
             .with_quic()
+            .with_other_transport(
+                |key| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
+                    Ok(websocket
+                        .upgrade(upgrade::Version::V1Lazy)
+                        .authenticate(noise::Config::new(key)?)
+                        .multiplex(yamux::Config::default()))
+                },
+            )
+            .unwrap()
             .with_behaviour(|key| {
                 log::trace!("internal INTERNET node ID: {:?}", key.public().to_peer_id());
                 Ok(behaviour)
@@ -237,39 +362,137 @@ impl Internet {
             .with_swarm_config(|cfg| {
                 cfg.with_idle_connection_timeout(Duration::from_secs(u64::MAX))
             })
-            .build();
+            .build()
+    }
 
-        log::trace!("Internet.init() swarm created");
+    /// create the swarm dialing all connections via a SOCKS5 proxy
+    ///
+    /// TCP and websocket connections are routed through the proxy.
+    /// QUIC is disabled, as it can't be proxied via SOCKS5.
+    fn create_proxy_swarm(
+        node_keys: &Keypair,
+        behaviour: QaulInternetBehaviour,
+        proxy: String,
+    ) -> Swarm<QaulInternetBehaviour> {
+        let socks5 = Socks5Transport::new(proxy);
+        let websocket = websocket::WsConfig::new(socks5.clone());
+
+        SwarmBuilder::with_existing_identity(node_keys.to_owned())
+            .with_async_std()
+            .with_other_transport(
+                |key| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
+                    Ok(socks5
+                        .upgrade(upgrade::Version::V1Lazy)
+                        .authenticate(noise::Config::new(key)?)
+                        .multiplex(yamux::Config::default()))
+                },
+            )
+            .unwrap()
+            .with_other_transport(
+                |key| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
+                    Ok(websocket
+                        .upgrade(upgrade::Version::V1Lazy)
+                        .authenticate(noise::Config::new(key)?)
+                        .multiplex(yamux::Config::default()))
+                },
+            )
+            .unwrap()
+            .with_behaviour(|key| {
+                log::trace!("internal INTERNET node ID: {:?}", key.public().to_peer_id());
+                Ok(behaviour)
+            })
+            .unwrap()
+            .with_swarm_config(|cfg| {
+                cfg.with_idle_connection_timeout(Duration::from_secs(u64::MAX))
+            })
+            .build()
+    }
 
-        // connect swarm to the listening interfaces defined in
-        // the configuration array config.internet.listen
-        let config = Configuration::get();
+    /// create the TLS configuration for secure websocket connections
+    ///
+    /// The TLS server configuration is needed to listen on `/wss`
+    /// multiaddresses. It is only created if a certificate and a private
+    /// key path are configured. The files can be PEM or DER encoded.
+    fn websocket_tls_config(config: &InternetWebsocket) -> Option<websocket::tls::Config> {
+        if config.tls_certificate.is_empty() || config.tls_private_key.is_empty() {
+            return None;
+        }
 
-        for listen in &config.internet.listen {
-            match Swarm::listen_on(&mut swarm, listen.parse().expect("can get a local socket")) {
-                Ok(listener_id) => {
-                    log::info!(
-                        "INTERNET listening on `{}` with ID {:?}",
-                        listen,
-                        listener_id
-                    );
-                }
-                Err(e) => {
-                    log::error!("Error INTERNET start listening on `{}`: {}", listen, e);
-                }
+        // read certificate chain
+        let certificates = match Self::read_pem_file(&config.tls_certificate) {
+            Some(certificates) => certificates,
+            None => {
+                log::error!(
+                    "websocket TLS certificate not readable: {}",
+                    config.tls_certificate
+                );
+                return None;
             }
-        }
+        };
 
-        // connect to remote peers that are specified in
-        // the configuration config.internet.peers
-        Self::peer_connect(&config, &mut swarm);
+        // read private key
+        let private_key = match Self::read_pem_file(&config.tls_private_key) {
+            Some(mut keys) if !keys.is_empty() => keys.remove(0),
+            _ => {
+                log::error!(
+                    "websocket TLS private key not readable: {}",
+                    config.tls_private_key
+                );
+                return None;
+            }
+        };
 
-        log::trace!("Internet.init() peer_connect");
+        match websocket::tls::Config::new(
+            websocket::tls::PrivateKey::new(private_key),
+            certificates
+                .into_iter()
+                .map(websocket::tls::Certificate::new),
+        ) {
+            Ok(tls_config) => Some(tls_config),
+            Err(e) => {
+                log::error!("websocket TLS configuration error: {}", e);
+                None
+            }
+        }
+    }
 
-        // construct internet object
-        let internet = Internet { swarm };
+    /// read a PEM or DER encoded file
+    ///
+    /// Returns the DER encoded content of all PEM blocks in the file.
+    /// If the file contains no PEM blocks, the entire file content is
+    /// returned as a single DER block.
+    fn read_pem_file(path: &str) -> Option<Vec<Vec<u8>>> {
+        let content = std::fs::read(path).ok()?;
+
+        // DER encoded file
+        let text = match std::str::from_utf8(&content) {
+            Ok(text) if text.contains("-----BEGIN") => text,
+            _ => return Some(vec![content]),
+        };
 
-        internet
+        // decode all PEM blocks
+        let mut blocks: Vec<Vec<u8>> = Vec::new();
+        let mut block: Option<String> = None;
+        for line in text.lines() {
+            let line = line.trim();
+            if line.starts_with("-----BEGIN") {
+                block = Some(String::new());
+            } else if line.starts_with("-----END") {
+                if let Some(encoded) = block.take() {
+                    match base64::engine::general_purpose::STANDARD.decode(encoded) {
+                        Ok(der) => blocks.push(der),
+                        Err(e) => {
+                            log::error!("PEM decoding error in {}: {}", path, e);
+                            return None;
+                        }
+                    }
+                }
+            } else if let Some(encoded) = block.as_mut() {
+                encoded.push_str(line);
+            }
+        }
+
+        Some(blocks)
     }
 
     // check if connection is active
@@ -310,10 +533,16 @@ impl Internet {
     }
 
     /// set tried time
+    ///
+    /// Increases the attempt counter of the peer and schedules
+    /// the next reconnection attempt with an exponential backoff.
     pub fn set_redialed(addresse: &Multiaddr) {
         let mut reconnections = INTERNETRECONNECTIONS.get().write().unwrap();
         if let Some(peer) = reconnections.peers.get_mut(addresse) {
-            peer.last_try = Timestamp::get_timestamp();
+            let now_ts = Timestamp::get_timestamp();
+            peer.attempt = peer.attempt.saturating_add(1);
+            peer.last_try = now_ts;
+            peer.next_try = now_ts + Self::reconnection_delay(peer.attempt);
         }
     }
 
@@ -338,36 +567,94 @@ impl Internet {
     }
 
     /// add reconnection
+    ///
+    /// Schedules a peer for reconnection.
+    /// If the peer is already scheduled, the existing backoff
+    /// schedule is kept.
     pub fn add_reconnection(address: Multiaddr) {
         let mut reconnections = INTERNETRECONNECTIONS.get().write().unwrap();
-        if let Some(peer) = reconnections.peers.get_mut(&address) {
-            peer.last_try = Timestamp::get_timestamp();
-        } else {
+        if !reconnections.peers.contains_key(&address) {
+            let now_ts = Timestamp::get_timestamp();
             reconnections.peers.insert(
                 address.clone(),
                 InternetReConnection {
                     address: address.clone(),
                     attempt: 0,
-                    last_try: Timestamp::get_timestamp(),
+                    last_try: now_ts,
+                    next_try: now_ts + Self::reconnection_delay(0),
                 },
             );
         }
     }
 
+    /// remove reconnection
+    ///
+    /// This is called when a connection to the peer has been
+    /// established and resets the backoff of this peer.
     pub fn remove_reconnection(address: Multiaddr) {
         let mut reconnections = INTERNETRECONNECTIONS.get().write().unwrap();
         reconnections.peers.remove(&address);
     }
 
+    /// reschedule all reconnections immediately
+    ///
+    /// This is called when the local network interfaces change,
+    /// as previously unreachable peers may be reachable now.
+    /// The attempt counters are reset.
+    pub fn reset_reconnections() {
+        let mut reconnections = INTERNETRECONNECTIONS.get().write().unwrap();
+        let now_ts = Timestamp::get_timestamp();
+        for (_addr, peer) in reconnections.peers.iter_mut() {
+            peer.attempt = 0;
+            peer.next_try = now_ts;
+        }
+    }
+
+    /// get the reconnection state of a peer address
+    ///
+    /// Returns a tuple with the failed attempts and the
+    /// timestamp of the next scheduled attempt.
+    pub fn get_reconnection(address: &str) -> Option<(u32, u64)> {
+        let reconnections = INTERNETRECONNECTIONS.get().read().unwrap();
+        for (addr, peer) in reconnections.peers.iter() {
+            if addr.to_string() == address {
+                return Some((peer.attempt, peer.next_try));
+            }
+        }
+        None
+    }
+
     /// check redial
     pub fn check_reconnection() -> Option<Multiaddr> {
         let reconnections = INTERNETRECONNECTIONS.get().read().unwrap();
         let now_ts = Timestamp::get_timestamp();
         for (addr, peer) in reconnections.peers.iter() {
-            if (now_ts - peer.last_try) > 10000 {
+            if now_ts >= peer.next_try {
                 return Some(addr.clone());
             }
         }
         None
     }
+
+    /// calculate the reconnection delay in milliseconds
+    ///
+    /// The delay doubles with every attempt until it reaches the
+    /// configured maximum. A random jitter is added to the delay
+    /// in order to not redial all peers at the same moment.
+    fn reconnection_delay(attempt: u32) -> u64 {
+        let config = Configuration::get();
+        let reconnect = &config.internet.reconnect;
+
+        let initial = reconnect.initial_delay.max(1) * 1000;
+        let max = (reconnect.max_delay * 1000).max(initial);
+        let delay = initial
+            .saturating_mul(2u64.saturating_pow(attempt.min(32)))
+            .min(max);
+
+        let jitter = delay * reconnect.jitter.min(100) as u64 / 100;
+        if jitter == 0 {
+            return delay;
+        }
+        delay - jitter + thread_rng().gen_range(0..=jitter * 2)
+    }
 }
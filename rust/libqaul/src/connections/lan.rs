@@ -18,31 +18,56 @@
 //!   - /ip4/0.0.0.0/tcp/0
 //!   - /ip6/::/udp/0/quic-v1
 //!   - /ip6/::/tcp/0
+//!   mdns:
+//!     enabled: true
+//!     ttl: 300
+//!     query_interval: 30
+//!   beacon:
+//!     enabled: false
+//!     port: 9228
+//!     interval: 10
+//!   static_peers:
+//!     enabled: true
+//!     peers:
+//!     - /ip4/192.168.1.10/tcp/9229
 //! ```
+//!
+//! Neighbours are discovered via the following mechanisms,
+//! which can be enabled individually:
+//!
+//! * mdns: advertise and discover nodes via multicast DNS.
+//! * beacon: broadcast and receive UDP beacons, for networks
+//!   that block multicast traffic.
+//! * static_peers: connect directly to a list of node addresses.
 
 use libp2p::{
     floodsub::{Floodsub, FloodsubEvent},
     identity::Keypair,
     mdns, noise, ping,
-    swarm::{NetworkBehaviour, Swarm},
-    tcp, yamux, SwarmBuilder,
+    swarm::{behaviour::toggle::Toggle, dial_opts::DialOpts, NetworkBehaviour, Swarm},
+    tcp, yamux, Multiaddr, PeerId, SwarmBuilder,
 };
 use prost::Message;
+use std::collections::HashMap;
 use std::time::Duration;
 
-use crate::connections::{events, ConnectionModule};
+use crate::connections::{beacon::Beacon, events, ConnectionModule};
 use crate::node::Node;
 use crate::services::feed::proto_net;
 use crate::services::feed::Feed;
 use crate::storage::configuration::Configuration;
+use crate::utilities::timestamp::Timestamp;
 use qaul_info::{QaulInfo, QaulInfoEvent};
 use qaul_messaging::{QaulMessaging, QaulMessagingEvent};
 
+/// interval to redial disconnected static peers in milliseconds
+const STATIC_PEERS_REDIAL_INTERVAL: u64 = 10_000;
+
 #[derive(NetworkBehaviour)]
 #[behaviour(to_swarm = "QaulLanEvent")]
 pub struct QaulLanBehaviour {
     pub floodsub: Floodsub,
-    pub mdns: mdns::async_io::Behaviour,
+    pub mdns: Toggle<mdns::async_io::Behaviour>,
     pub ping: ping::Behaviour,
     pub qaul_info: QaulInfo,
     pub qaul_messaging: QaulMessaging,
@@ -90,7 +115,11 @@ impl QaulLanBehaviour {
             }
             mdns::Event::Expired(expired_list) => {
                 for (peer, _addr) in expired_list {
-                    if !self.mdns.has_node(&peer) {
+                    let has_node = match self.mdns.as_ref() {
+                        Some(mdns) => mdns.has_node(&peer),
+                        None => false,
+                    };
+                    if !has_node {
                         log::trace!("MdnsEvent::Expired, peer {:?} from floodsub removed", peer);
                         self.floodsub.remove_node_from_partial_view(&peer);
                     }
@@ -153,6 +182,12 @@ impl From<QaulMessagingEvent> for QaulLanEvent {
 
 pub struct Lan {
     pub swarm: Swarm<QaulLanBehaviour>,
+    /// UDP broadcast beacon discovery
+    beacon: Option<Beacon>,
+    /// static peer addresses and the node they are connected to
+    static_peers: HashMap<Multiaddr, Option<PeerId>>,
+    /// timestamp of the last static peers redial
+    static_peers_last_try: u64,
 }
 
 impl Lan {
@@ -170,13 +205,28 @@ impl Lan {
         log::trace!("Lan::init() ping_config");
 
         // create MDNS behaviour
-        // TODO create MdnsConfig {ttl: Duration::from_secs(300), query_interval: Duration::from_secs(30) }
-        let mdns = mdns::async_io::Behaviour::new(mdns::Config::default(), Node::get_id()).unwrap();
+        let mdns = match config.lan.mdns.enabled {
+            true => {
+                let mdns_config = mdns::Config {
+                    ttl: Duration::from_secs(config.lan.mdns.ttl),
+                    query_interval: Duration::from_secs(config.lan.mdns.query_interval),
+                    ..Default::default()
+                };
+                match mdns::async_io::Behaviour::new(mdns_config, Node::get_id()) {
+                    Ok(mdns) => Some(mdns),
+                    Err(e) => {
+                        log::error!("LAN mdns discovery couldn't be started: {}", e);
+                        None
+                    }
+                }
+            }
+            false => None,
+        };
 
         // create behaviour
         let mut behaviour: QaulLanBehaviour = QaulLanBehaviour {
             floodsub: Floodsub::new(Node::get_id()),
-            mdns,
+            mdns: Toggle::from(mdns),
             ping: ping::Behaviour::new(ping_config),
             qaul_info: QaulInfo::new(Node::get_id()),
             qaul_messaging: QaulMessaging::new(Node::get_id()),
@@ -213,8 +263,130 @@ impl Lan {
                 .expect("swarm can be started");
         }
 
-        let lan = Lan { swarm };
+        // create UDP broadcast beacon
+        let mut beacon = None;
+        if config.lan.beacon.enabled {
+            beacon = Beacon::new(config.lan.beacon.port, config.lan.beacon.interval);
+        }
+
+        // read static peers
+        let mut static_peers: HashMap<Multiaddr, Option<PeerId>> = HashMap::new();
+        if config.lan.static_peers.enabled {
+            for peer in &config.lan.static_peers.peers {
+                match peer.parse() {
+                    Ok(address) => {
+                        static_peers.insert(address, None);
+                    }
+                    Err(e) => log::error!("LAN static peer address {} invalid: {}", peer, e),
+                }
+            }
+        }
+
+        let lan = Lan {
+            swarm,
+            beacon,
+            static_peers,
+            static_peers_last_try: 0,
+        };
 
         lan
     }
+
+    /// Discover and connect to LAN neighbours
+    ///
+    /// This function is called periodically from the event loop.
+    /// It broadcasts and receives beacons and redials
+    /// disconnected static peers.
+    pub fn discovery(&mut self) {
+        self.beacon_discovery();
+        self.static_peers_redial();
+    }
+
+    /// Process a new connection
+    ///
+    /// Adds the node to the floodsub partial view, and marks
+    /// static peers as connected.
+    pub fn connection_established(&mut self, peer_id: PeerId, address: Option<Multiaddr>) {
+        self.swarm
+            .behaviour_mut()
+            .floodsub
+            .add_node_to_partial_view(peer_id);
+
+        if let Some(address) = address {
+            if let Some(static_peer) = self.static_peers.get_mut(&address) {
+                *static_peer = Some(peer_id);
+            }
+        }
+    }
+
+    /// Process a closed connection
+    ///
+    /// Removes the node from the floodsub partial view,
+    /// if it is not connected anymore and not discovered via mdns.
+    pub fn connection_closed(&mut self, peer_id: PeerId) {
+        if self.swarm.is_connected(&peer_id) {
+            return;
+        }
+        let has_node = match self.swarm.behaviour().mdns.as_ref() {
+            Some(mdns) => mdns.has_node(&peer_id),
+            None => false,
+        };
+        if !has_node {
+            self.swarm
+                .behaviour_mut()
+                .floodsub
+                .remove_node_from_partial_view(&peer_id);
+        }
+    }
+
+    /// send and receive UDP broadcast beacons
+    fn beacon_discovery(&mut self) {
+        let node_id = Node::get_id();
+        let listeners: Vec<Multiaddr> = self.swarm.listeners().cloned().collect();
+
+        let discovered = match self.beacon.as_mut() {
+            Some(beacon) => {
+                beacon.send(&node_id, &listeners);
+                beacon.receive(&node_id)
+            }
+            None => return,
+        };
+
+        for (peer_id, addresses) in discovered {
+            if self.swarm.is_connected(&peer_id) {
+                continue;
+            }
+            log::trace!("LAN beacon discovered peer {}", peer_id);
+            Self::dial(
+                &mut self.swarm,
+                DialOpts::peer_id(peer_id).addresses(addresses).build(),
+            );
+        }
+    }
+
+    /// redial all disconnected static peers
+    fn static_peers_redial(&mut self) {
+        let now = Timestamp::get_timestamp();
+        if now - self.static_peers_last_try < STATIC_PEERS_REDIAL_INTERVAL {
+            return;
+        }
+        self.static_peers_last_try = now;
+
+        for (address, peer_id) in self.static_peers.iter() {
+            if let Some(peer_id) = peer_id {
+                if self.swarm.is_connected(peer_id) {
+                    continue;
+                }
+            }
+            log::trace!("LAN dial static peer {}", address);
+            Self::dial(&mut self.swarm, DialOpts::from(address.clone()));
+        }
+    }
+
+    /// dial a LAN peer
+    fn dial(swarm: &mut Swarm<QaulLanBehaviour>, opts: DialOpts) {
+        if let Err(e) = swarm.dial(opts) {
+            log::trace!("LAN dial error: {}", e);
+        }
+    }
 }
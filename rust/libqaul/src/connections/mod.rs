@@ -5,10 +5,12 @@
 //!
 //! The modules define how and where to connect to network interfaces.
 
+pub mod beacon;
 pub mod ble;
 pub mod events;
 pub mod internet;
 pub mod lan;
+pub mod socks5;
 
 use libp2p::Multiaddr;
 use prost::Message;
@@ -333,10 +335,20 @@ impl Connections {
 
         // fill all the nodes
         for peer in &config.internet.peers {
+            // get reconnection schedule
+            let mut reconnect_attempts = 0;
+            let mut reconnect_next = 0;
+            if let Some((attempts, next)) = Internet::get_reconnection(&peer.address) {
+                reconnect_attempts = attempts;
+                reconnect_next = next;
+            }
+
             nodes.push(proto::InternetNodesEntry {
                 address: peer.address.clone(),
                 name: peer.name.clone(),
                 enabled: peer.enabled,
+                reconnect_attempts,
+                reconnect_next,
             });
         }
 
@@ -76,6 +76,18 @@ pub struct InternetNodesEntry {
     /// name
     #[prost(string, tag = "3")]
     pub name: ::prost::alloc::string::String,
+    /// number of failed reconnection attempts
+    ///
+    /// This field is only set in internet_nodes_list messages.
+    #[prost(uint32, tag = "4")]
+    pub reconnect_attempts: u32,
+    /// timestamp of the next scheduled reconnection attempt
+    /// in milliseconds since UNIX_EPOCH.
+    /// 0 if no reconnection is scheduled.
+    ///
+    /// This field is only set in internet_nodes_list messages.
+    #[prost(uint64, tag = "5")]
+    pub reconnect_next: u64,
 }
 /// Information about the system actions that led to
 /// the creation of this message.
@@ -0,0 +1,373 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # SOCKS5 Proxy Transport
+//!
+//! **Dial remote peers via a SOCKS5 proxy.**
+//!
+//! This libp2p transport routes all outgoing TCP connections
+//! through a SOCKS5 proxy, e.g. a local Tor daemon.
+//! Host names (including `.onion` addresses) are not resolved
+//! locally, but are passed to the proxy for resolution.
+//!
+//! The transport does not listen for incoming connections,
+//! in order to not reveal the node's IP addresses.
+//!
+//! Supported multiaddresses are:
+//!
+//! * `/ip4/{address}/tcp/{port}`
+//! * `/ip6/{address}/tcp/{port}`
+//! * `/dns/{host}/tcp/{port}`, `/dns4/{host}/tcp/{port}`, `/dns6/{host}/tcp/{port}`
+//! * `/onion3/{onion address}:{port}`
+//!
+//! The specification of the SOCKS5 protocol is RFC 1928.
+
+use async_std::net::TcpStream;
+use futures::future::BoxFuture;
+use futures::prelude::*;
+use libp2p::{
+    core::transport::{ListenerId, TransportError, TransportEvent},
+    multiaddr::Protocol,
+    Multiaddr, Transport,
+};
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// SOCKS5 protocol version
+const SOCKS_VERSION: u8 = 0x05;
+/// authentication method: no authentication required
+const AUTH_NONE: u8 = 0x00;
+/// command: establish a TCP/IP stream connection
+const CMD_CONNECT: u8 = 0x01;
+/// address type: IPv4 address
+const ATYP_IPV4: u8 = 0x01;
+/// address type: fully qualified domain name
+const ATYP_DOMAIN: u8 = 0x03;
+/// address type: IPv6 address
+const ATYP_IPV6: u8 = 0x04;
+
+/// Destination of a connection via the SOCKS5 proxy
+#[derive(Debug, Clone, PartialEq)]
+pub enum Socks5Destination {
+    /// IPv4 or IPv6 socket address
+    Ip(std::net::SocketAddr),
+    /// host name and port, resolved by the proxy
+    Domain(String, u16),
+}
+
+/// SOCKS5 Transport
+///
+/// Dials all addresses via the configured SOCKS5 proxy.
+#[derive(Debug, Clone)]
+pub struct Socks5Transport {
+    /// socket address of the SOCKS5 proxy, e.g. `127.0.0.1:9050`
+    proxy: String,
+}
+
+impl Socks5Transport {
+    /// create a new SOCKS5 transport for the proxy address
+    pub fn new(proxy: String) -> Self {
+        Self { proxy }
+    }
+
+    /// get the connection destination of a multiaddress
+    ///
+    /// Returns None if the multiaddress is not supported by this transport.
+    pub fn destination(addr: &Multiaddr) -> Option<Socks5Destination> {
+        let mut iter = addr.iter();
+
+        let destination = match iter.next()? {
+            Protocol::Ip4(ip) => match iter.next()? {
+                Protocol::Tcp(port) => Socks5Destination::Ip((ip, port).into()),
+                _ => return None,
+            },
+            Protocol::Ip6(ip) => match iter.next()? {
+                Protocol::Tcp(port) => Socks5Destination::Ip((ip, port).into()),
+                _ => return None,
+            },
+            Protocol::Dns(host) | Protocol::Dns4(host) | Protocol::Dns6(host) => {
+                match iter.next()? {
+                    Protocol::Tcp(port) => Socks5Destination::Domain(host.to_string(), port),
+                    _ => return None,
+                }
+            }
+            Protocol::Onion3(onion) => {
+                let host = format!("{}.onion", base32_lower(onion.hash()));
+                Socks5Destination::Domain(host, onion.port())
+            }
+            _ => return None,
+        };
+
+        // only a peer id is allowed to follow
+        match iter.next() {
+            None | Some(Protocol::P2p(_)) => Some(destination),
+            _ => None,
+        }
+    }
+
+    /// connect to the destination via the SOCKS5 proxy
+    pub async fn connect(proxy: String, destination: Socks5Destination) -> io::Result<TcpStream> {
+        let mut stream = TcpStream::connect(proxy).await?;
+        stream.set_nodelay(true)?;
+
+        // method selection: only offer 'no authentication'
+        stream.write_all(&[SOCKS_VERSION, 1, AUTH_NONE]).await?;
+        let mut method = [0u8; 2];
+        stream.read_exact(&mut method).await?;
+        if method[0] != SOCKS_VERSION || method[1] != AUTH_NONE {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "SOCKS5 proxy requires an unsupported authentication method",
+            ));
+        }
+
+        // connection request
+        let mut request = vec![SOCKS_VERSION, CMD_CONNECT, 0x00];
+        let port = match destination {
+            Socks5Destination::Ip(std::net::SocketAddr::V4(addr)) => {
+                request.push(ATYP_IPV4);
+                request.extend_from_slice(&addr.ip().octets());
+                addr.port()
+            }
+            Socks5Destination::Ip(std::net::SocketAddr::V6(addr)) => {
+                request.push(ATYP_IPV6);
+                request.extend_from_slice(&addr.ip().octets());
+                addr.port()
+            }
+            Socks5Destination::Domain(host, port) => {
+                if host.len() > 255 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "host name too long for SOCKS5",
+                    ));
+                }
+                request.push(ATYP_DOMAIN);
+                request.push(host.len() as u8);
+                request.extend_from_slice(host.as_bytes());
+                port
+            }
+        };
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        // connection reply
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await?;
+        if reply[0] != SOCKS_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid SOCKS5 reply",
+            ));
+        }
+        if reply[1] != 0x00 {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("SOCKS5 connection failed with reply code {}", reply[1]),
+            ));
+        }
+
+        // read and discard the bound address and port
+        let bound_len = match reply[3] {
+            ATYP_IPV4 => 4,
+            ATYP_IPV6 => 16,
+            ATYP_DOMAIN => {
+                let mut len = [0u8; 1];
+                stream.read_exact(&mut len).await?;
+                len[0] as usize
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid SOCKS5 address type",
+                ))
+            }
+        };
+        let mut bound = vec![0u8; bound_len + 2];
+        stream.read_exact(&mut bound).await?;
+
+        Ok(stream)
+    }
+}
+
+impl Transport for Socks5Transport {
+    type Output = TcpStream;
+    type Error = io::Error;
+    type ListenerUpgrade = future::Ready<Result<Self::Output, Self::Error>>;
+    type Dial = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+    fn listen_on(
+        &mut self,
+        _id: ListenerId,
+        addr: Multiaddr,
+    ) -> Result<(), TransportError<Self::Error>> {
+        Err(TransportError::MultiaddrNotSupported(addr))
+    }
+
+    fn remove_listener(&mut self, _id: ListenerId) -> bool {
+        false
+    }
+
+    fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+        let destination = match Self::destination(&addr) {
+            Some(destination) => destination,
+            None => return Err(TransportError::MultiaddrNotSupported(addr)),
+        };
+        log::trace!("dial {} via SOCKS5 proxy {}", addr, self.proxy);
+
+        Ok(Self::connect(self.proxy.clone(), destination).boxed())
+    }
+
+    fn dial_as_listener(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<Self::Dial, TransportError<Self::Error>> {
+        self.dial(addr)
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+        Poll::Pending
+    }
+
+    fn address_translation(&self, _listen: &Multiaddr, _observed: &Multiaddr) -> Option<Multiaddr> {
+        None
+    }
+}
+
+/// encode bytes in lower case base32 (RFC 4648) as used by onion addresses
+fn base32_lower(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut result = String::with_capacity((data.len() + 4) / 5 * 8);
+    let mut buffer: u16 = 0;
+    let mut bits: u8 = 0;
+
+    for byte in data {
+        buffer = (buffer << 8) | *byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            result.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        result.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// start a SOCKS5 responder on 127.0.0.1, which accepts a single
+    /// connection and answers its CONNECT request with `reply_code`
+    ///
+    /// Returns the proxy address and a receiver for the
+    /// destination bytes of the CONNECT request:
+    /// {address type}{address}{port}
+    fn start_proxy(reply_code: u8) -> (String, mpsc::Receiver<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = listener.local_addr().unwrap().to_string();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            // method selection
+            let mut methods = [0u8; 3];
+            stream.read_exact(&mut methods).unwrap();
+            assert_eq!(methods, [SOCKS_VERSION, 1, AUTH_NONE]);
+            stream.write_all(&[SOCKS_VERSION, AUTH_NONE]).unwrap();
+
+            // connection request
+            let mut header = [0u8; 4];
+            stream.read_exact(&mut header).unwrap();
+            assert_eq!(header[..3], [SOCKS_VERSION, CMD_CONNECT, 0x00]);
+            let mut destination = vec![header[3]];
+            let address_len = match header[3] {
+                ATYP_IPV4 => 4,
+                ATYP_IPV6 => 16,
+                ATYP_DOMAIN => {
+                    let mut len = [0u8; 1];
+                    stream.read_exact(&mut len).unwrap();
+                    destination.push(len[0]);
+                    len[0] as usize
+                }
+                _ => panic!("invalid address type"),
+            };
+            let mut address = vec![0u8; address_len + 2];
+            stream.read_exact(&mut address).unwrap();
+            destination.extend(address);
+            sender.send(destination).unwrap();
+
+            // connection reply with an IPv4 bound address
+            stream
+                .write_all(&[SOCKS_VERSION, reply_code, 0x00, ATYP_IPV4, 0, 0, 0, 0, 0, 0])
+                .unwrap();
+        });
+
+        (proxy, receiver)
+    }
+
+    /// dial the address via a responder and return the
+    /// destination bytes of the CONNECT request
+    async fn dial(addr: Multiaddr) -> Vec<u8> {
+        let (proxy, receiver) = start_proxy(0x00);
+        let mut transport = Socks5Transport::new(proxy);
+        transport.dial(addr).unwrap().await.unwrap();
+        receiver.recv().unwrap()
+    }
+
+    #[async_std::test]
+    async fn connect_ip_address() {
+        let destination = dial("/ip4/192.168.1.2/tcp/9229".parse().unwrap()).await;
+        assert_eq!(destination, vec![ATYP_IPV4, 192, 168, 1, 2, 0x24, 0x1d]);
+    }
+
+    #[async_std::test]
+    async fn connect_dns_name() {
+        let destination = dial("/dns4/qaul.net/tcp/9229".parse().unwrap()).await;
+        let mut expected = vec![ATYP_DOMAIN, 8];
+        expected.extend_from_slice(b"qaul.net");
+        expected.extend_from_slice(&[0x24, 0x1d]);
+        assert_eq!(destination, expected);
+    }
+
+    #[async_std::test]
+    async fn connect_onion_name() {
+        let hash = [0u8; 35];
+        let addr = Multiaddr::empty().with(Protocol::Onion3((hash, 9229).into()));
+        let destination = dial(addr).await;
+
+        let host = format!("{}.onion", "a".repeat(56));
+        let mut expected = vec![ATYP_DOMAIN, host.len() as u8];
+        expected.extend_from_slice(host.as_bytes());
+        expected.extend_from_slice(&[0x24, 0x1d]);
+        assert_eq!(destination, expected);
+    }
+
+    #[async_std::test]
+    async fn connect_failure_reply() {
+        // reply code 0x05: connection refused
+        let (proxy, receiver) = start_proxy(0x05);
+        let mut transport = Socks5Transport::new(proxy);
+        let result = transport
+            .dial("/ip4/10.0.0.1/tcp/9229".parse().unwrap())
+            .unwrap()
+            .await;
+        receiver.recv().unwrap();
+
+        let error = result.err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionRefused);
+    }
+}
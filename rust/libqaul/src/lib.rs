@@ -73,9 +73,13 @@ enum EventType {
     UserResponse(bool),
     RoutingInfo(bool),
     ReConnecting(bool),
+    LanDiscovery(bool),
     RoutingTable(bool),
     Messaging(bool),
     Retransmit(bool),
+    CryptoCleanup(bool),
+    FileTransfer(bool),
+    FileSend(bool),
 }
 
 /// initialize and start libqaul with a optional custom configuration options
@@ -268,6 +272,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
     // try to connect to intertnet neighbour if there is no connection in internet
     let mut connection_ticker = Ticker::new(Duration::from_millis(1000));
 
+    // discover LAN neighbours via beacons and static peers
+    let mut lan_discovery_ticker = Ticker::new(Duration::from_millis(1000));
+
     // re-create routing table periodically
     let mut routing_table_ticker = Ticker::new(Duration::from_millis(1000));
 
@@ -277,6 +284,15 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
     // manage the message retransmit
     let mut retransmit_ticker = Ticker::new(Duration::from_millis(1000));
 
+    // remove expired crypto sessions
+    let mut crypto_cleanup_ticker = Ticker::new(Duration::from_millis(60000));
+
+    // request missing chunks of stalled file transfers
+    let mut file_transfer_ticker = Ticker::new(Duration::from_millis(10000));
+
+    // schedule the data chunks of files being sent
+    let mut file_send_ticker = Ticker::new(Duration::from_millis(10));
+
     // set initialized flag
     INITIALIZED.set(true);
 
@@ -295,9 +311,13 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
             let userresp_fut = userresp_ticker.next().fuse();
             let routing_info_fut = routing_info_ticker.next().fuse();
             let connection_fut = connection_ticker.next().fuse();
+            let lan_discovery_fut = lan_discovery_ticker.next().fuse();
             let routing_table_fut = routing_table_ticker.next().fuse();
             let messaging_fut = messaging_ticker.next().fuse();
             let retransmit_fut = retransmit_ticker.next().fuse();
+            let crypto_cleanup_fut = crypto_cleanup_ticker.next().fuse();
+            let file_transfer_fut = file_transfer_ticker.next().fuse();
+            let file_send_fut = file_send_ticker.next().fuse();
 
             // This Macro is shown wrong by Rust-Language-Server > 0.2.400
             // You need to downgrade to version 0.2.400 if this happens to you
@@ -313,22 +333,35 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                 userresp_fut,
                 routing_info_fut,
                 connection_fut,
+                lan_discovery_fut,
                 routing_table_fut,
                 messaging_fut,
                 retransmit_fut,
+                crypto_cleanup_fut,
+                file_transfer_fut,
+                file_send_fut,
             );
 
             select! {
                 lan_event = lan_fut => {
                     //log::trace!("Unhandled lan connection module event: {:?}", lan_event);
                     match lan_event.unwrap() {
-                        libp2p::swarm::SwarmEvent::ConnectionEstablished{peer_id,  ..} => {
+                        libp2p::swarm::SwarmEvent::ConnectionEstablished{peer_id, endpoint, ..} => {
                             log::trace!("lan connection established: {:?}", peer_id);
+                            match endpoint {
+                                libp2p::core::ConnectedPoint::Dialer{address, ..} => {
+                                    lan.connection_established(peer_id, Some(address));
+                                }
+                                _ => {
+                                    lan.connection_established(peer_id, None);
+                                }
+                            }
                         }
                         libp2p::swarm::SwarmEvent::ConnectionClosed{peer_id, ..} => {
                             //remove from neighbour table, after then scheduler will auto remove this neighbour
                             log::trace!("lan connection closed: {:?}", peer_id);
                             Neighbours::delete(ConnectionModule::Lan, peer_id);
+                            lan.connection_closed(peer_id);
                         },
                         // libp2p::swarm::SwarmEvent::BannedPeer {peer_id, ..} => {
                         //     //remove from neighbour table, after then scheduler will auto remove this neighbour
@@ -397,6 +430,15 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                         //     log::trace!("internet connection banned: {:?}", peer_id);
                         //     Neighbours::delete(ConnectionModule::Internet, peer_id);
                         // }
+                        libp2p::swarm::SwarmEvent::NewListenAddr{address, ..} => {
+                            // a network interface has changed, redial unreachable peers
+                            log::trace!("internet new listen address: {:?}", address);
+                            Internet::reset_reconnections();
+                        }
+                        libp2p::swarm::SwarmEvent::ExpiredListenAddr{address, ..} => {
+                            log::trace!("internet expired listen address: {:?}", address);
+                            Internet::reset_reconnections();
+                        }
                         libp2p::swarm::SwarmEvent::Behaviour(behaviour) => {
                             internet.swarm.behaviour_mut().process_events(behaviour);
                         }
@@ -413,9 +455,13 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                 _userresp_event = userresp_fut => Some(EventType::UserResponse(true)),
                 _routing_info_event = routing_info_fut => Some(EventType::RoutingInfo(true)),
                 _connection_event = connection_fut => Some(EventType::ReConnecting(true)),
+                _lan_discovery_event = lan_discovery_fut => Some(EventType::LanDiscovery(true)),
                 _routing_table_event = routing_table_fut => Some(EventType::RoutingTable(true)),
                 _messaging_event = messaging_fut => Some(EventType::Messaging(true)),
                 _retransmit_event = retransmit_fut => Some(EventType::Retransmit(true)),
+                _crypto_cleanup_event = crypto_cleanup_fut => Some(EventType::CryptoCleanup(true)),
+                _file_transfer_event = file_transfer_fut => Some(EventType::FileTransfer(true)),
+                _file_send_event = file_send_fut => Some(EventType::FileSend(true)),
             }
         };
 
@@ -654,56 +700,79 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                         Internet::set_redialed(&addr);
                     }
                 }
+                EventType::LanDiscovery(_) => {
+                    lan.discovery();
+                }
                 EventType::RoutingTable(_) => {
                     // create new routing table
                     router::connections::ConnectionTable::create_routing_table();
                 }
                 EventType::Messaging(_) => {
                     // send scheduled messages
-                    if let Some((neighbour_id, connection_module, data)) =
-                        Messaging::check_scheduler()
-                    {
-                        log::trace!(
-                            "sending messaging message via {:?} to {}",
-                            connection_module,
-                            neighbour_id.to_base58()
-                        );
-                        // send messaging message via the best module
-                        match connection_module {
-                            ConnectionModule::Lan => {
-                                lan.swarm
-                                    .behaviour_mut()
-                                    .qaul_messaging
-                                    .send_qaul_messaging_message(neighbour_id, data);
-                            }
-                            ConnectionModule::Internet => {
-                                internet
-                                    .swarm
-                                    .behaviour_mut()
-                                    .qaul_messaging
-                                    .send_qaul_messaging_message(neighbour_id, data);
-                            }
-                            ConnectionModule::Ble => {
-                                Ble::send_messaging_message(neighbour_id, data);
-                            }
-                            ConnectionModule::Local => {
-                                let message = qaul_messaging::types::QaulMessagingReceived {
-                                    received_from: neighbour_id,
-                                    data,
-                                };
-                                // forward to messaging module
-                                Messaging::received(message);
-                            }
-                            ConnectionModule::None => {
-                                // TODO: DTN behaviour
-                                // reschedule it for the moment
+                    // several messages are sent per tick, in the order of their priority
+                    for _ in 0..services::messaging::SEND_BATCH_SIZE {
+                        if let Some((neighbour_id, connection_module, data, journal_key)) =
+                            Messaging::check_scheduler()
+                        {
+                            log::trace!(
+                                "sending messaging message via {:?} to {}",
+                                connection_module,
+                                neighbour_id.to_base58()
+                            );
+                            // send messaging message via the best module
+                            match connection_module {
+                                ConnectionModule::Lan => {
+                                    lan.swarm
+                                        .behaviour_mut()
+                                        .qaul_messaging
+                                        .send_qaul_messaging_message(neighbour_id, data);
+                                    Messaging::on_handed_over(journal_key);
+                                }
+                                ConnectionModule::Internet => {
+                                    internet
+                                        .swarm
+                                        .behaviour_mut()
+                                        .qaul_messaging
+                                        .send_qaul_messaging_message(neighbour_id, data);
+                                    Messaging::on_handed_over(journal_key);
+                                }
+                                ConnectionModule::Ble => {
+                                    Ble::send_messaging_message(neighbour_id, data);
+                                    Messaging::on_handed_over(journal_key);
+                                }
+                                ConnectionModule::Local => {
+                                    let message = qaul_messaging::types::QaulMessagingReceived {
+                                        received_from: neighbour_id,
+                                        data,
+                                    };
+                                    Messaging::on_handed_over(journal_key);
+                                    // forward to messaging module
+                                    Messaging::received(message);
+                                }
+                                ConnectionModule::None => {
+                                    // TODO: DTN behaviour
+                                    // reschedule it for the moment
+                                    Messaging::on_not_handed_over(journal_key);
+                                    break;
+                                }
                             }
+                        } else {
+                            break;
                         }
                     }
                 }
                 EventType::Retransmit(_) => {
                     services::messaging::retransmit::MessagingRetransmit::process();
                 }
+                EventType::CryptoCleanup(_) => {
+                    services::crypto::Crypto::cleanup();
+                }
+                EventType::FileTransfer(_) => {
+                    services::chat::ChatFile::check_transfers();
+                }
+                EventType::FileSend(_) => {
+                    services::chat::ChatFile::process_send_events();
+                }
             }
         }
     }
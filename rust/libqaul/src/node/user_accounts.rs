@@ -142,6 +142,8 @@ impl UserAccounts {
                 id: id.to_string(),
                 keys: keys_config,
                 storage: configuration::StorageOptions::default(),
+                file_policy: configuration::FilePolicy::default(),
+                chat_privacy: configuration::ChatPrivacy::default(),
             });
         }
         Configuration::save();
@@ -175,6 +177,12 @@ impl UserAccounts {
         account_result
     }
 
+    /// get the ids of all user accounts
+    pub fn get_ids() -> Vec<PeerId> {
+        let accounts = USERACCOUNTS.get().read().unwrap();
+        accounts.users.iter().map(|user| user.id).collect()
+    }
+
     /// Return the number of registered user accounts on this node.
     #[allow(dead_code)]
     pub fn len() -> usize {
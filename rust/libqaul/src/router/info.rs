@@ -15,6 +15,11 @@
 //! of them to make sure, each node is only sent the routing
 //! table over one of the interfaces.
 //! The timer needs to be polled manually.
+//!
+//! Every message announces the compression algorithms this node
+//! is able to decompress.
+//! The routing information is compressed for neighbours, that
+//! announced the deflate algorithm.
 
 use crate::utilities::qaul_id::QaulId;
 use libp2p::PeerId;
@@ -34,6 +39,7 @@ use crate::{
         connections::ConnectionTable, neighbours::Neighbours, router_net_proto,
         table::RoutingTable, users::Users,
     },
+    utilities::compression::{Compression, CompressionStats},
     utilities::timestamp::Timestamp,
 };
 
@@ -70,6 +76,9 @@ pub struct Scheduler {
     ///
     /// timestamp of the last propagation update
     propagation_timestamp: u64,
+
+    /// statistics of the compressed routing information
+    compression_stats: CompressionStats,
 }
 
 /// An entry for the scheduler neighbour list
@@ -79,6 +88,8 @@ struct SchedulerEntry {
     /// time of the last send
     timestamp: SystemTime,
     is_first: bool,
+    /// the neighbour announced to support deflate compression
+    compression: bool,
 }
 
 /// RouterInfo Module
@@ -97,6 +108,7 @@ impl RouterInfo {
             interval: Duration::from_secs(interval_seconds),
             propagation_id: 0,
             propagation_timestamp: Timestamp::get_timestamp(),
+            compression_stats: CompressionStats::default(),
         };
         SCHEDULER.set(RwLock::new(scheduler));
     }
@@ -109,6 +121,7 @@ impl RouterInfo {
         let mut found_neighbour: Option<PeerId> = None;
         let mut neighbour_last_sent: u64 = 0;
         let mut neighbour_is_first: bool = false;
+        let mut neighbour_compression: bool = false;
         let mut propagation_id: u32;
         let mut propagation_timestamp: u64;
 
@@ -122,6 +135,7 @@ impl RouterInfo {
                     found_neighbour = Some(id.clone());
                     neighbour_last_sent = Timestamp::get_timestamp_by(&ctx.timestamp);
                     neighbour_is_first = ctx.is_first;
+                    neighbour_compression = ctx.compression;
                     break;
                 }
             }
@@ -167,7 +181,12 @@ impl RouterInfo {
                 }
 
                 // create routing information
-                let data = Self::create(node_id.clone(), neighbour_last_sent, neighbour_is_first);
+                let data = Self::create(
+                    node_id.clone(),
+                    neighbour_last_sent,
+                    neighbour_is_first,
+                    neighbour_compression,
+                );
 
                 // create result
                 return Some((node_id, module, data));
@@ -196,14 +215,45 @@ impl RouterInfo {
                 SchedulerEntry {
                     timestamp: SystemTime::now() - interval,
                     is_first: true,
+                    compression: false,
                 },
             );
         }
     }
 
+    /// update the announced compression support of a neighbour
+    fn set_neighbour_compression(node_id: &PeerId, compression: bool) {
+        {
+            let scheduler = SCHEDULER.get().read().unwrap();
+            match scheduler.neighbours.get(node_id) {
+                Some(entry) if entry.compression != compression => {}
+                _ => return,
+            }
+        }
+
+        let mut scheduler = SCHEDULER.get().write().unwrap();
+        if let Some(entry) = scheduler.neighbours.get_mut(node_id) {
+            entry.compression = compression;
+        }
+    }
+
+    /// Get the statistics of the compressed routing information
+    pub fn get_compression_stats() -> CompressionStats {
+        let scheduler = SCHEDULER.get().read().unwrap();
+        scheduler.compression_stats
+    }
+
+    /// compression algorithms this node is able to decompress
+    fn compression_capabilities() -> Vec<i32> {
+        vec![router_net_proto::CompressionAlgorithm::Deflate as i32]
+    }
+
     /// Create routing information for a neighbour node,
     /// encode the information and return the byte code.
-    pub fn create(neighbour: PeerId, last_sent: u64, is_first: bool) -> Vec<u8> {
+    ///
+    /// The routing information is compressed, if the neighbour
+    /// supports compression and the information is big enough.
+    pub fn create(neighbour: PeerId, last_sent: u64, is_first: bool, compress: bool) -> Vec<u8> {
         let node_id = Node::get_id();
 
         // create routing table
@@ -235,12 +285,26 @@ impl RouterInfo {
             .encode(&mut buf)
             .expect("Vec<u8> provides capacity as needed");
 
+        // compress router info message
+        let mut content_compression = router_net_proto::CompressionAlgorithm::Uncompressed;
+        if compress {
+            if let Some(compressed) = Compression::deflate(&buf) {
+                let mut scheduler = SCHEDULER.get().write().unwrap();
+                scheduler.compression_stats.add(buf.len(), compressed.len());
+
+                content_compression = router_net_proto::CompressionAlgorithm::Deflate;
+                buf = compressed;
+            }
+        }
+
         // create router info content message
         let router_info_proto = router_net_proto::RouterInfoContent {
             id: node_id.to_bytes(),
             router_info_module: router_net_proto::RouterInfoModule::RouterInfo as i32,
             content: buf,
             time: timestamp,
+            compression: Self::compression_capabilities(),
+            content_compression: content_compression as i32,
         };
 
         // encode content message
@@ -288,6 +352,8 @@ impl RouterInfo {
             router_info_module: router_net_proto::RouterInfoModule::FeedRequest as i32,
             content: buf,
             time: timestamp,
+            compression: Self::compression_capabilities(),
+            content_compression: router_net_proto::CompressionAlgorithm::Uncompressed as i32,
         };
 
         // encode message
@@ -344,6 +410,8 @@ impl RouterInfo {
             router_info_module: router_net_proto::RouterInfoModule::FeedResponse as i32,
             content: buf,
             time: timestamp,
+            compression: Self::compression_capabilities(),
+            content_compression: router_net_proto::CompressionAlgorithm::Uncompressed as i32,
         };
 
         // encode message
@@ -389,6 +457,8 @@ impl RouterInfo {
             router_info_module: router_net_proto::RouterInfoModule::UserRequest as i32,
             content: buf,
             time: timestamp,
+            compression: Self::compression_capabilities(),
+            content_compression: router_net_proto::CompressionAlgorithm::Uncompressed as i32,
         };
 
         // encode message
@@ -431,6 +501,8 @@ impl RouterInfo {
             router_info_module: router_net_proto::RouterInfoModule::UserResponse as i32,
             content: buf,
             time: timestamp,
+            compression: Self::compression_capabilities(),
+            content_compression: router_net_proto::CompressionAlgorithm::Uncompressed as i32,
         };
 
         // encode message
@@ -473,7 +545,35 @@ impl RouterInfo {
                     router_net_proto::RouterInfoContent::decode(&container.message[..]);
 
                 match message_result {
-                    Ok(content) => {
+                    Ok(mut content) => {
+                        // update compression support of the neighbour
+                        Self::set_neighbour_compression(
+                            &received.received_from,
+                            content.compression.contains(
+                                &(router_net_proto::CompressionAlgorithm::Deflate as i32),
+                            ),
+                        );
+
+                        // decompress content
+                        match router_net_proto::CompressionAlgorithm::try_from(
+                            content.content_compression,
+                        ) {
+                            Ok(router_net_proto::CompressionAlgorithm::Uncompressed) => {}
+                            Ok(router_net_proto::CompressionAlgorithm::Deflate) => {
+                                match Compression::inflate(&content.content) {
+                                    Some(decompressed) => content.content = decompressed,
+                                    None => {
+                                        log::error!("RouterInfoContent decompression failed");
+                                        return;
+                                    }
+                                }
+                            }
+                            Err(_) => {
+                                log::error!("RouterInfoContent unknown compression algorithm");
+                                return;
+                            }
+                        }
+
                         match router_net_proto::RouterInfoModule::try_from(
                             content.router_info_module,
                         ) {
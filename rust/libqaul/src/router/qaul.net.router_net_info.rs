@@ -25,6 +25,15 @@ pub struct RouterInfoContent {
     /// timestamp in milli seconds
     #[prost(uint64, tag = "4")]
     pub time: u64,
+    /// compression algorithms this node is able to decompress
+    #[prost(enumeration = "CompressionAlgorithm", repeated, tag = "5")]
+    pub compression: ::prost::alloc::vec::Vec<i32>,
+    /// compression algorithm of the message content
+    ///
+    /// The content is only compressed for neighbours,
+    /// that announced the algorithm.
+    #[prost(enumeration = "CompressionAlgorithm", tag = "6")]
+    pub content_compression: i32,
 }
 /// Router information message
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -147,6 +156,35 @@ pub struct FeedMessage {
     #[prost(uint64, tag = "4")]
     pub time: u64,
 }
+/// compression algorithm
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CompressionAlgorithm {
+    /// no compression
+    Uncompressed = 0,
+    /// deflate compression
+    Deflate = 1,
+}
+impl CompressionAlgorithm {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Uncompressed => "UNCOMPRESSED",
+            CompressionAlgorithm::Deflate => "DEFLATE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "UNCOMPRESSED" => Some(Self::Uncompressed),
+            "DEFLATE" => Some(Self::Deflate),
+            _ => None,
+        }
+    }
+}
 /// RouterInfoModule
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
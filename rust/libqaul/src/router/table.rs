@@ -312,7 +312,7 @@ impl RoutingTable {
     /// * Hierarchy of connection modules in the following order:
     ///   Local, LAN, Internet, BLE, None
     ///
-    fn rank_routing_connection(connection: &RoutingConnectionEntry) -> u8 {
+    pub fn rank_routing_connection(connection: &RoutingConnectionEntry) -> u8 {
         match connection.module {
             ConnectionModule::None => return 0,
             ConnectionModule::Ble => return 1,
@@ -165,6 +165,23 @@ impl Users {
         }
     }
 
+    /// check if a known user has been verified
+    pub fn is_verified(user_id: &PeerId) -> bool {
+        let q8id = QaulId::to_q8id(user_id.to_owned());
+        let store = USERS.get().read().unwrap();
+        match store.users.get(&q8id) {
+            Some(user) => user.verified,
+            None => false,
+        }
+    }
+
+    /// get the name of a known user
+    pub fn get_name(user_id: &PeerId) -> Option<String> {
+        let q8id = QaulId::to_q8id(user_id.to_owned());
+        let store = USERS.get().read().unwrap();
+        store.users.get(&q8id).map(|user| user.name.clone())
+    }
+
     /// get user by q8id
     pub fn get_user_id_by_q8id(q8id: Vec<u8>) -> Option<PeerId> {
         let store = USERS.get().read().unwrap();
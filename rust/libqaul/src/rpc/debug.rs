@@ -6,8 +6,12 @@
 //! Messages to debug libqaul
 
 use super::Rpc;
+use crate::router::info::RouterInfo;
+use crate::services::messaging::compression::MessagingCompression;
+use crate::services::messaging::Messaging;
 use crate::storage::configuration::Configuration;
 use crate::storage::Storage;
+use crate::utilities::compression::CompressionStats;
 use crate::utilities::filelogger::FileLogger;
 use prost::Message;
 
@@ -95,6 +99,42 @@ impl Debug {
                             Vec::new(),
                         );
                     }
+                    Some(proto::debug::Message::MessagingStatsRequest(_stats_request)) => {
+                        // create and return messaging statistics
+                        let stats = Messaging::get_stats();
+                        let depth = Messaging::get_queue_depth();
+                        let proto_message = proto::Debug {
+                            message: Some(proto::debug::Message::MessagingStatsResponse(
+                                proto::MessagingStatsResponse {
+                                    forwarded: stats.forwarded,
+                                    dropped_hop_limit: stats.dropped_hop_limit,
+                                    dropped_expired: stats.dropped_expired,
+                                    send_queue: Some(proto::MessagingQueueDepth {
+                                        control: depth.control,
+                                        confirmation: depth.confirmation,
+                                        chat: depth.chat,
+                                        group: depth.group,
+                                        file: depth.file,
+                                        forward: depth.forward,
+                                    }),
+                                    message_compression: Some(Self::compression_stats(
+                                        MessagingCompression::get_stats(),
+                                    )),
+                                    routing_compression: Some(Self::compression_stats(
+                                        RouterInfo::get_compression_stats(),
+                                    )),
+                                },
+                            )),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Debug.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
                     _ => {
                         log::error!("Unhandled RPC Debug Message");
                     }
@@ -105,4 +145,13 @@ impl Debug {
             }
         }
     }
+
+    /// convert compression statistics to the RPC structure
+    fn compression_stats(stats: CompressionStats) -> proto::CompressionStats {
+        proto::CompressionStats {
+            messages: stats.messages,
+            bytes_uncompressed: stats.bytes_uncompressed,
+            bytes_compressed: stats.bytes_compressed,
+        }
+    }
 }
@@ -24,6 +24,7 @@ use crate::router::users::Users;
 use crate::router::Router;
 use crate::services::chat::Chat;
 use crate::services::chat::ChatFile;
+use crate::services::crypto::Crypto;
 use crate::services::dtn::Dtn;
 use crate::services::feed::Feed;
 use crate::services::group::Group;
@@ -185,6 +186,10 @@ impl Rpc {
                         log::trace!("Message Modules::Group received");
                         Dtn::rpc(message.data, message.user_id);
                     }
+                    Ok(Modules::Crypto) => {
+                        log::trace!("Message Modules::Crypto received");
+                        Crypto::rpc(message.data, message.user_id);
+                    }
                     Ok(Modules::None) => {
                         log::error!("Message Modules::None received");
                     }
@@ -12,6 +12,14 @@ pub struct Container {
     /// Message envelope
     #[prost(message, optional, tag = "2")]
     pub envelope: ::core::option::Option<Envelope>,
+    /// remaining hops the message is forwarded
+    ///
+    /// This value is not signed, every forwarding node
+    /// decrements it.
+    /// The message is dropped once it reaches zero.
+    /// Zero means not set by older versions of qaul.
+    #[prost(uint32, tag = "3")]
+    pub hop_limit: u32,
 }
 /// message envelop with sender and receiver
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -27,12 +35,19 @@ pub struct Envelope {
     /// payload
     #[prost(bytes = "vec", tag = "3")]
     pub payload: ::prost::alloc::vec::Vec<u8>,
+    /// creation time of the message
+    ///
+    /// Forwarding nodes drop messages that are older
+    /// than their configured maximum message age.
+    /// Zero means not set by older versions of qaul.
+    #[prost(uint64, tag = "4")]
+    pub created_at: u64,
 }
 /// envelop payload
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EnvelopPayload {
-    #[prost(oneof = "envelop_payload::Payload", tags = "1, 2")]
+    #[prost(oneof = "envelop_payload::Payload", tags = "1, 2, 3, 4")]
     pub payload: ::core::option::Option<envelop_payload::Payload>,
 }
 /// Nested message and enum types in `EnvelopPayload`.
@@ -46,8 +61,37 @@ pub mod envelop_payload {
         /// DTN message
         #[prost(bytes, tag = "2")]
         Dtn(::prost::alloc::vec::Vec<u8>),
+        /// group message, encrypted with the sender key
+        #[prost(message, tag = "3")]
+        GroupEncrypted(super::GroupEncrypted),
+        /// fragment of a payload, that is too big to be sent at once
+        #[prost(message, tag = "4")]
+        Fragment(super::Fragment),
     }
 }
+/// fragment of an envelope payload
+///
+/// Payloads bigger than the maximal message size of the
+/// connection module are split into several fragments.
+/// Each fragment is signed and sent in its own container.
+/// The receiver reassembles the encoded envelope payload,
+/// once all fragments have arrived.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Fragment {
+    /// random id of the fragmented payload
+    #[prost(uint64, tag = "1")]
+    pub fragment_id: u64,
+    /// index of this fragment, starting at 0
+    #[prost(uint32, tag = "2")]
+    pub index: u32,
+    /// total number of fragments
+    #[prost(uint32, tag = "3")]
+    pub count: u32,
+    /// slice of the encoded envelope payload
+    #[prost(bytes = "vec", tag = "4")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
 /// encrypted message data
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -63,6 +107,36 @@ pub struct Encrypted {
     /// of maximally 64KB each.
     #[prost(message, repeated, tag = "3")]
     pub data: ::prost::alloc::vec::Vec<Data>,
+    /// crypto session version of the sender
+    ///
+    /// It is announced in the handshake messages.
+    /// A session uses the lower version of both sides.
+    ///
+    /// * 0: sessions of older nodes
+    /// * 1: transport keys are ratcheted forward
+    #[prost(uint32, tag = "4")]
+    pub session_version: u32,
+}
+/// group message data encrypted with the sender key
+///
+/// The message is encrypted once with the group sending key
+/// of the sender, and sent to all members, who received
+/// the key.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupEncrypted {
+    /// group id
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// id of the sender key
+    #[prost(uint32, tag = "2")]
+    pub key_id: u32,
+    /// message nonce for encryption
+    #[prost(uint64, tag = "3")]
+    pub nonce: u64,
+    /// the encrypted message data
+    #[prost(bytes = "vec", tag = "4")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
 }
 /// encrypted message data
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -85,7 +159,7 @@ pub struct Data {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Messaging {
-    #[prost(oneof = "messaging::Message", tags = "1, 2, 3, 4, 5, 6")]
+    #[prost(oneof = "messaging::Message", tags = "1, 2, 3, 4, 5, 6, 7, 8")]
     pub message: ::core::option::Option<messaging::Message>,
 }
 /// Nested message and enum types in `Messaging`.
@@ -111,8 +185,44 @@ pub mod messaging {
         /// common message
         #[prost(message, tag = "6")]
         CommonMessage(super::CommonMessage),
+        /// compressed messaging message
+        #[prost(message, tag = "7")]
+        Compressed(super::Compressed),
+        /// capabilities of the sending node
+        #[prost(message, tag = "8")]
+        Capabilities(super::Capabilities),
     }
 }
+/// compressed messaging message
+///
+/// The data is compressed before encryption.
+/// It is only sent to users, that announced
+/// the compression algorithm in their capabilities.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Compressed {
+    /// compression algorithm
+    #[prost(enumeration = "CompressionAlgorithm", tag = "1")]
+    pub algorithm: i32,
+    /// compressed encoded Messaging message
+    #[prost(bytes = "vec", tag = "2")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+/// capabilities of the sending node
+///
+/// The capabilities are announced to the remote user
+/// once a crypto session has been established.
+/// They are valid for this session.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Capabilities {
+    /// crypto session id
+    #[prost(uint32, tag = "1")]
+    pub session_id: u32,
+    /// supported compression algorithms
+    #[prost(enumeration = "CompressionAlgorithm", repeated, tag = "2")]
+    pub compression: ::prost::alloc::vec::Vec<i32>,
+}
 /// message received confirmation
 ///
 /// every message that was received by a user
@@ -199,6 +309,13 @@ pub struct ChatMessage {
     /// content
     #[prost(string, tag = "1")]
     pub content: ::prost::alloc::string::String,
+    /// id of the message this message replies to,
+    /// empty if it is not a reply
+    #[prost(bytes = "vec", tag = "2")]
+    pub reply_to: ::prost::alloc::vec::Vec<u8>,
+    /// quoted excerpt of the message replied to
+    #[prost(string, tag = "3")]
+    pub quote: ::prost::alloc::string::String,
 }
 /// file message
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -377,3 +494,32 @@ impl CryptoState {
         }
     }
 }
+/// compression algorithm
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CompressionAlgorithm {
+    /// no compression
+    Uncompressed = 0,
+    /// deflate compression
+    Deflate = 1,
+}
+impl CompressionAlgorithm {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Uncompressed => "UNCOMPRESSED",
+            CompressionAlgorithm::Deflate => "DEFLATE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "UNCOMPRESSED" => Some(Self::Uncompressed),
+            "DEFLATE" => Some(Self::Deflate),
+            _ => None,
+        }
+    }
+}
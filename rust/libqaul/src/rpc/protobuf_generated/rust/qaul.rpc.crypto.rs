@@ -0,0 +1,140 @@
+/// Crypto service RPC message container
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Crypto {
+    /// message type
+    #[prost(oneof = "crypto::Message", tags = "1, 2, 3, 4")]
+    pub message: ::core::option::Option<crypto::Message>,
+}
+/// Nested message and enum types in `Crypto`.
+pub mod crypto {
+    /// message type
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Message {
+        /// crypto sessions request
+        #[prost(message, tag = "1")]
+        SessionsRequest(super::CryptoSessionsRequest),
+        /// crypto sessions response
+        #[prost(message, tag = "2")]
+        SessionsResponse(super::CryptoSessionsResponse),
+        /// reset session request
+        #[prost(message, tag = "3")]
+        ResetRequest(super::CryptoResetRequest),
+        /// reset session response
+        #[prost(message, tag = "4")]
+        ResetResponse(super::CryptoResetResponse),
+    }
+}
+/// Crypto Sessions Request
+///
+/// Requests the crypto sessions of the user account
+/// with a specific remote user.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CryptoSessionsRequest {
+    /// remote user id
+    ///
+    /// If empty, the sessions with all remote users
+    /// are returned.
+    #[prost(bytes = "vec", tag = "1")]
+    pub remote_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// Crypto Sessions Response
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CryptoSessionsResponse {
+    /// list of crypto sessions
+    #[prost(message, repeated, tag = "1")]
+    pub sessions: ::prost::alloc::vec::Vec<CryptoSession>,
+}
+/// Crypto Session Information
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CryptoSession {
+    /// remote user id
+    #[prost(bytes = "vec", tag = "1")]
+    pub remote_id: ::prost::alloc::vec::Vec<u8>,
+    /// session id
+    #[prost(uint32, tag = "2")]
+    pub session_id: u32,
+    /// session state
+    #[prost(enumeration = "CryptoSessionState", tag = "3")]
+    pub state: i32,
+    /// did we initiate this session?
+    #[prost(bool, tag = "4")]
+    pub initiator: bool,
+    /// is this the session used for sending
+    #[prost(bool, tag = "5")]
+    pub active: bool,
+    /// time the session was created
+    #[prost(uint64, tag = "6")]
+    pub created_at: u64,
+    /// time the session was last used
+    #[prost(uint64, tag = "7")]
+    pub last_used: u64,
+    /// amount of sent messages
+    #[prost(uint64, tag = "8")]
+    pub sent: u64,
+    /// highest message index received
+    #[prost(uint64, tag = "9")]
+    pub received: u64,
+}
+/// Reset Session Request
+///
+/// Deletes all crypto sessions with a remote user.
+/// A new session is negotiated with the next message.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CryptoResetRequest {
+    /// remote user id
+    #[prost(bytes = "vec", tag = "1")]
+    pub remote_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// Reset Session Response
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CryptoResetResponse {
+    /// remote user id
+    #[prost(bytes = "vec", tag = "1")]
+    pub remote_id: ::prost::alloc::vec::Vec<u8>,
+    /// was the reset successful
+    #[prost(bool, tag = "2")]
+    pub success: bool,
+    /// error message
+    #[prost(string, tag = "3")]
+    pub error_message: ::prost::alloc::string::String,
+}
+/// State of a Crypto Session
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CryptoSessionState {
+    /// waiting for the second handshake message of the remote user
+    HalfOutgoing = 0,
+    /// waiting to send the second handshake message
+    HalfIncoming = 1,
+    /// the session is established
+    Transport = 2,
+}
+impl CryptoSessionState {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            CryptoSessionState::HalfOutgoing => "HALF_OUTGOING",
+            CryptoSessionState::HalfIncoming => "HALF_INCOMING",
+            CryptoSessionState::Transport => "TRANSPORT",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "HALF_OUTGOING" => Some(Self::HalfOutgoing),
+            "HALF_INCOMING" => Some(Self::HalfIncoming),
+            "TRANSPORT" => Some(Self::Transport),
+            _ => None,
+        }
+    }
+}
@@ -3,7 +3,7 @@
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Debug {
     /// message type
-    #[prost(oneof = "debug::Message", tags = "1, 2, 3, 4, 5, 6, 7")]
+    #[prost(oneof = "debug::Message", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9")]
     pub message: ::core::option::Option<debug::Message>,
 }
 /// Nested message and enum types in `Debug`.
@@ -33,6 +33,12 @@ pub mod debug {
         /// Request for library to delete logs
         #[prost(message, tag = "7")]
         DeleteLibqaulLogsRequest(super::DeleteLibqaulLogsRequest),
+        /// Messaging Statistics Request
+        #[prost(message, tag = "8")]
+        MessagingStatsRequest(super::MessagingStatsRequest),
+        /// Messaging Statistics Response
+        #[prost(message, tag = "9")]
+        MessagingStatsResponse(super::MessagingStatsResponse),
     }
 }
 /// Request a Heartbeat from Libqaul
@@ -92,3 +98,79 @@ pub struct StoragePathResponse {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct DeleteLibqaulLogsRequest {}
+/// MessagingStatsRequest
+///
+/// Request the statistics of the forwarded
+/// and dropped messages
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MessagingStatsRequest {}
+/// MessagingStatsResponse
+///
+/// Statistics of the messages forwarded by this node
+/// since the start of libqaul
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MessagingStatsResponse {
+    /// forwarded messages
+    #[prost(uint64, tag = "1")]
+    pub forwarded: u64,
+    /// dropped messages, as their hop limit was exhausted
+    #[prost(uint64, tag = "2")]
+    pub dropped_hop_limit: u64,
+    /// dropped messages, as they were older than the maximum age
+    #[prost(uint64, tag = "3")]
+    pub dropped_expired: u64,
+    /// messages scheduled for sending
+    #[prost(message, optional, tag = "4")]
+    pub send_queue: ::core::option::Option<MessagingQueueDepth>,
+    /// compression of the messages sent by this node
+    #[prost(message, optional, tag = "5")]
+    pub message_compression: ::core::option::Option<CompressionStats>,
+    /// compression of the routing information sent by this node
+    #[prost(message, optional, tag = "6")]
+    pub routing_compression: ::core::option::Option<CompressionStats>,
+}
+/// Compression Statistics
+///
+/// The compression ratio is the relation of
+/// the compressed to the uncompressed bytes.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CompressionStats {
+    /// number of compressed messages
+    #[prost(uint64, tag = "1")]
+    pub messages: u64,
+    /// total size of the data before compression
+    #[prost(uint64, tag = "2")]
+    pub bytes_uncompressed: u64,
+    /// total size of the compressed data
+    #[prost(uint64, tag = "3")]
+    pub bytes_compressed: u64,
+}
+/// Messaging Queue Depth
+///
+/// Number of messages scheduled for sending
+/// per priority class
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MessagingQueueDepth {
+    /// crypto handshakes
+    #[prost(uint32, tag = "1")]
+    pub control: u32,
+    /// confirmations
+    #[prost(uint32, tag = "2")]
+    pub confirmation: u32,
+    /// chat messages
+    #[prost(uint32, tag = "3")]
+    pub chat: u32,
+    /// group management messages
+    #[prost(uint32, tag = "4")]
+    pub group: u32,
+    /// file data
+    #[prost(uint32, tag = "5")]
+    pub file: u32,
+    /// forwarded messages
+    #[prost(uint32, tag = "6")]
+    pub forward: u32,
+}
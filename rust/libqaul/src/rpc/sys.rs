@@ -103,5 +103,9 @@ impl Sys {
         // send the message
         #[cfg(target_os = "android")]
         Android::send_to_android(data);
+
+        // send the message to the BLE simulator
+        #[cfg(all(unix, not(any(target_os = "android", target_os = "ios"))))]
+        crate::connections::ble::simulator::BleSimulator::sys_send(data);
     }
 }
@@ -0,0 +1,1174 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Chat Export and Import
+//!
+//! Exports the chat conversations of a user account into a folder,
+//! to archive them outside of qaul.
+//!
+//! Every export contains a JSON archive with all messages,
+//! their senders, timestamps, delivery states, reactions and
+//! edit histories.
+//! Optionally a Markdown or an HTML document is written
+//! next to it, which can be read without any further tools.
+//! The files attached to the messages are copied into the
+//! `files` folder of the export.
+//!
+//! The JSON archive can be imported into a user account again.
+//! Conversations that don't exist in the user account are created
+//! as deactivated groups, messages that already exist are skipped.
+
+use libp2p::PeerId;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use uuid::Uuid;
+
+use super::rpc_proto;
+use super::{ChatFile, ChatStorage};
+use crate::node::user_accounts::{UserAccount, UserAccounts};
+use crate::router::users::Users;
+use crate::services::group::{proto_rpc as group_rpc, Group, GroupMember, GroupStorage};
+use crate::utilities::timestamp::Timestamp;
+
+/// file name of the JSON archive
+const ARCHIVE_FILE_NAME: &str = "qaul-chat-export.json";
+
+/// file name of the Markdown document
+const MARKDOWN_FILE_NAME: &str = "qaul-chat-export.md";
+
+/// file name of the HTML document
+const HTML_FILE_NAME: &str = "qaul-chat-export.html";
+
+/// folder of the attached files
+const FILES_FOLDER: &str = "files";
+
+/// version of the JSON archive format
+const ARCHIVE_VERSION: u32 = 1;
+
+/// imported chat message with its edit history and
+/// the path of its attached file
+type ImportedMessage = (
+    rpc_proto::ChatMessage,
+    Vec<rpc_proto::ChatEditEntry>,
+    Option<PathBuf>,
+);
+
+/// imported conversation with the group to create,
+/// if it doesn't exist yet, and its messages
+type ImportedConversation = (Option<Group>, Vec<ImportedMessage>);
+
+/// latest time RFC 3339 can represent in milliseconds,
+/// 9999-12-31T23:59:59Z
+const MAX_RFC3339_TIME: u64 = 253_402_300_799_999;
+
+/// JSON archive of exported conversations
+#[derive(Serialize, Deserialize)]
+struct ExportArchive {
+    /// archive format version
+    version: u32,
+    /// base58 id of the exporting user account
+    account_id: String,
+    /// name of the exporting user account
+    account_name: String,
+    /// time of the export
+    exported_at: u64,
+    /// exported conversations
+    conversations: Vec<ExportConversation>,
+}
+
+/// exported conversation
+#[derive(Serialize, Deserialize)]
+struct ExportConversation {
+    /// group id as hyphenated uuid
+    group_id: String,
+    /// group name
+    name: String,
+    /// is direct chat group
+    is_direct_chat: bool,
+    /// group creation time
+    created_at: u64,
+    /// group revision number
+    revision: u32,
+    /// group members
+    members: Vec<ExportMember>,
+    /// messages, the oldest first
+    messages: Vec<ExportMessage>,
+}
+
+/// exported group member
+#[derive(Serialize, Deserialize)]
+struct ExportMember {
+    /// base58 user id
+    user_id: String,
+    /// user name
+    name: String,
+    /// member role: `User` or `Admin`
+    role: String,
+    /// member state: `Invited` or `Activated`
+    state: String,
+    /// time the member joined
+    joined_at: u64,
+}
+
+/// exported chat message
+#[derive(Serialize, Deserialize)]
+struct ExportMessage {
+    /// base58 message id
+    message_id: String,
+    /// base58 user id of the sender
+    sender_id: String,
+    /// name of the sender
+    sender_name: String,
+    /// time the message was sent
+    sent_at: u64,
+    /// time the message was received
+    received_at: u64,
+    /// delivery status, a `MessageStatus` name
+    status: String,
+    /// reception confirmations of the receivers
+    #[serde(default)]
+    confirmations: Vec<ExportConfirmation>,
+    /// emoji reactions
+    #[serde(default)]
+    reactions: Vec<ExportReaction>,
+    /// previous versions of an edited message, the oldest first
+    #[serde(default)]
+    edits: Vec<ExportEdit>,
+    /// message content
+    content: ExportContent,
+}
+
+/// exported reception confirmation
+#[derive(Serialize, Deserialize)]
+struct ExportConfirmation {
+    /// base58 user id of the receiver
+    user_id: String,
+    /// name of the receiver
+    user_name: String,
+    /// time of the confirmation
+    confirmed_at: u64,
+    /// time the receiver read the message, 0 if unknown
+    #[serde(default)]
+    read_at: u64,
+}
+
+/// exported emoji reaction
+#[derive(Serialize, Deserialize)]
+struct ExportReaction {
+    /// reaction emoji
+    emoji: String,
+    /// base58 ids of the reacting users
+    user_ids: Vec<String>,
+    /// names of the reacting users
+    user_names: Vec<String>,
+}
+
+/// exported previous version of an edited message
+#[derive(Serialize, Deserialize)]
+struct ExportEdit {
+    /// message text
+    text: String,
+    /// time of the edit that created this version,
+    /// 0 for the original message
+    edited_at: u64,
+}
+
+/// exported message content
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ExportContent {
+    /// text message
+    Text {
+        text: String,
+        #[serde(default)]
+        edited_at: u64,
+    },
+    /// reply to a message
+    Reply {
+        /// base58 message id of the replied message
+        reply_to: String,
+        /// base58 user id of the sender of the replied message
+        reply_to_sender_id: String,
+        /// name of the sender of the replied message
+        reply_to_sender_name: String,
+        quote: String,
+        text: String,
+        #[serde(default)]
+        edited_at: u64,
+    },
+    /// file message
+    File {
+        file_id: u64,
+        file_name: String,
+        file_extension: String,
+        file_size: u64,
+        file_description: String,
+        /// path of the file relative to the export folder,
+        /// empty if the file was not available
+        #[serde(default)]
+        path: String,
+    },
+    /// group event information
+    GroupEvent {
+        /// a `GroupEventType` name
+        event: String,
+        /// base58 user id of the concerned user
+        user_id: String,
+        /// name of the concerned user
+        user_name: String,
+    },
+    /// message deleted by its sender
+    Deleted { deleted_at: u64 },
+    /// reaction on a message
+    Reaction {
+        /// base58 id of the reacted message
+        message_id: String,
+        emoji: String,
+        remove: bool,
+    },
+    /// content that could not be decoded
+    Unknown,
+}
+
+/// qaul Chat Export and Import
+pub struct ChatExport {}
+
+impl ChatExport {
+    /// Export conversations of a user account into a folder
+    pub fn export(
+        account_id: PeerId,
+        request: rpc_proto::ChatExportRequest,
+    ) -> rpc_proto::ChatExportResponse {
+        let mut response = rpc_proto::ChatExportResponse {
+            success: false,
+            error: String::new(),
+            files: Vec::new(),
+            conversations: 0,
+            messages: 0,
+        };
+
+        let archive = match Self::write_export(account_id, &request, &mut response.files) {
+            Ok(archive) => archive,
+            Err(error) => {
+                log::error!("chat export error: {}", error);
+                response.error = error;
+                return response;
+            }
+        };
+
+        response.success = true;
+        response.conversations = archive.conversations.len() as u32;
+        response.messages = archive
+            .conversations
+            .iter()
+            .map(|conversation| conversation.messages.len() as u32)
+            .sum();
+        response
+    }
+
+    /// Import conversations from a JSON archive into a user account
+    pub fn import(
+        account_id: PeerId,
+        request: rpc_proto::ChatImportRequest,
+    ) -> rpc_proto::ChatImportResponse {
+        let mut response = rpc_proto::ChatImportResponse {
+            success: false,
+            error: String::new(),
+            conversations: 0,
+            messages: 0,
+        };
+
+        match Self::read_import(account_id, &request.path) {
+            Ok((conversations, messages)) => {
+                response.success = true;
+                response.conversations = conversations;
+                response.messages = messages;
+            }
+            Err(error) => {
+                log::error!("chat import error: {}", error);
+                response.error = error;
+            }
+        }
+
+        response
+    }
+
+    /// create the export folder and write all export files
+    fn write_export(
+        account_id: PeerId,
+        request: &rpc_proto::ChatExportRequest,
+        files: &mut Vec<String>,
+    ) -> Result<ExportArchive, String> {
+        let account = match UserAccounts::get_by_id(account_id) {
+            Some(account) => account,
+            None => return Err("user account not found".to_string()),
+        };
+        if request.path.is_empty() {
+            return Err("no export path".to_string());
+        }
+        let folder = PathBuf::from(&request.path);
+        if let Err(e) = fs::create_dir_all(&folder) {
+            return Err(e.to_string());
+        }
+
+        // get the groups to export
+        let groups = if request.group_id.is_empty() {
+            GroupStorage::get_groups(account_id)
+        } else {
+            match GroupStorage::get_group(account_id, request.group_id.clone()) {
+                Some(group) => vec![group],
+                None => return Err("group not found".to_string()),
+            }
+        };
+
+        let archive = ExportArchive {
+            version: ARCHIVE_VERSION,
+            account_id: account_id.to_base58(),
+            account_name: account.name.clone(),
+            exported_at: Timestamp::get_timestamp(),
+            conversations: groups
+                .into_iter()
+                .map(|group| Self::export_conversation(&account, group, &folder))
+                .collect(),
+        };
+
+        // write JSON archive
+        let json = match serde_json::to_string_pretty(&archive) {
+            Ok(json) => json,
+            Err(e) => return Err(e.to_string()),
+        };
+        files.push(Self::write_file(&folder, ARCHIVE_FILE_NAME, json)?);
+
+        // write human readable document
+        match rpc_proto::ExportFormat::try_from(request.format) {
+            Ok(rpc_proto::ExportFormat::Markdown) => {
+                files.push(Self::write_file(
+                    &folder,
+                    MARKDOWN_FILE_NAME,
+                    Self::to_markdown(&archive),
+                )?);
+            }
+            Ok(rpc_proto::ExportFormat::Html) => {
+                files.push(Self::write_file(
+                    &folder,
+                    HTML_FILE_NAME,
+                    Self::to_html(&archive),
+                )?);
+            }
+            _ => {}
+        }
+
+        Ok(archive)
+    }
+
+    /// write a file into the export folder and return its path
+    fn write_file(folder: &Path, file_name: &str, content: String) -> Result<String, String> {
+        let path = folder.join(file_name);
+        match fs::write(&path, content) {
+            Ok(_) => Ok(path.to_string_lossy().to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// export a group with all its messages
+    fn export_conversation(
+        account: &UserAccount,
+        group: Group,
+        folder: &Path,
+    ) -> ExportConversation {
+        let members = group
+            .members
+            .values()
+            .map(|member| ExportMember {
+                user_id: bs58::encode(&member.user_id).into_string(),
+                name: Self::user_name(account, &member.user_id),
+                role: group_rpc::GroupMemberRole::try_from(member.role)
+                    .unwrap_or(group_rpc::GroupMemberRole::User)
+                    .as_str_name()
+                    .to_string(),
+                state: group_rpc::GroupMemberState::try_from(member.state)
+                    .unwrap_or(group_rpc::GroupMemberState::Invited)
+                    .as_str_name()
+                    .to_string(),
+                joined_at: member.joined_at,
+            })
+            .collect();
+
+        let messages = ChatStorage::get_conversation_messages(account.id, &group.id)
+            .into_iter()
+            .map(|message| Self::export_message(account, message, folder))
+            .collect();
+
+        ExportConversation {
+            group_id: Uuid::from_slice(&group.id)
+                .map(|uuid| uuid.hyphenated().to_string())
+                .unwrap_or_default(),
+            name: group.name,
+            is_direct_chat: group.is_direct_chat,
+            created_at: group.created_at,
+            revision: group.revision,
+            members,
+            messages,
+        }
+    }
+
+    /// export a chat message
+    fn export_message(
+        account: &UserAccount,
+        message: rpc_proto::ChatMessage,
+        folder: &Path,
+    ) -> ExportMessage {
+        let confirmations = message
+            .message_reception_confirmed
+            .iter()
+            .map(|confirmation| ExportConfirmation {
+                user_id: bs58::encode(&confirmation.user_id).into_string(),
+                user_name: Self::user_name(account, &confirmation.user_id),
+                confirmed_at: confirmation.confirmed_at,
+                read_at: confirmation.read_at,
+            })
+            .collect();
+
+        let reactions = message
+            .reactions
+            .iter()
+            .map(|reaction| ExportReaction {
+                emoji: reaction.emoji.clone(),
+                user_ids: reaction
+                    .user_ids
+                    .iter()
+                    .map(|user_id| bs58::encode(user_id).into_string())
+                    .collect(),
+                user_names: reaction
+                    .user_ids
+                    .iter()
+                    .map(|user_id| Self::user_name(account, user_id))
+                    .collect(),
+            })
+            .collect();
+
+        let edits = ChatStorage::get_edit_history(account.id, message.message_id.clone())
+            .edits
+            .into_iter()
+            .map(|entry| ExportEdit {
+                text: entry.text,
+                edited_at: entry.edited_at,
+            })
+            .collect();
+
+        ExportMessage {
+            message_id: bs58::encode(&message.message_id).into_string(),
+            sender_id: bs58::encode(&message.sender_id).into_string(),
+            sender_name: Self::user_name(account, &message.sender_id),
+            sent_at: message.sent_at,
+            received_at: message.received_at,
+            status: rpc_proto::MessageStatus::try_from(message.status)
+                .unwrap_or(rpc_proto::MessageStatus::Sending)
+                .as_str_name()
+                .to_string(),
+            confirmations,
+            reactions,
+            edits,
+            content: Self::export_content(account, &message.content, folder),
+        }
+    }
+
+    /// export the content of a chat message
+    ///
+    /// Attached files are copied into the export folder.
+    fn export_content(account: &UserAccount, content: &Vec<u8>, folder: &Path) -> ExportContent {
+        let message = match rpc_proto::ChatContentMessage::decode(&content[..]) {
+            Ok(content_message) => content_message.message,
+            Err(_) => None,
+        };
+
+        match message {
+            Some(rpc_proto::chat_content_message::Message::ChatContent(chat_content)) => {
+                ExportContent::Text {
+                    text: chat_content.text,
+                    edited_at: chat_content.edited_at,
+                }
+            }
+            Some(rpc_proto::chat_content_message::Message::Reply(reply)) => ExportContent::Reply {
+                reply_to: bs58::encode(&reply.message_id).into_string(),
+                reply_to_sender_id: bs58::encode(&reply.sender_id).into_string(),
+                reply_to_sender_name: Self::user_name(account, &reply.sender_id),
+                quote: reply.quote,
+                text: reply.text,
+                edited_at: reply.edited_at,
+            },
+            Some(rpc_proto::chat_content_message::Message::FileContent(file_content)) => {
+                let path = Self::export_file(account.id, &file_content, folder).unwrap_or_default();
+                ExportContent::File {
+                    file_id: file_content.file_id,
+                    file_name: file_content.file_name,
+                    file_extension: file_content.file_extension,
+                    file_size: file_content.file_size,
+                    file_description: file_content.file_description,
+                    path,
+                }
+            }
+            Some(rpc_proto::chat_content_message::Message::GroupEvent(group_event)) => {
+                ExportContent::GroupEvent {
+                    event: rpc_proto::GroupEventType::try_from(group_event.event_type)
+                        .unwrap_or(rpc_proto::GroupEventType::Default)
+                        .as_str_name()
+                        .to_string(),
+                    user_id: bs58::encode(&group_event.user_id).into_string(),
+                    user_name: Self::user_name(account, &group_event.user_id),
+                }
+            }
+            Some(rpc_proto::chat_content_message::Message::MessageDeleted(deleted)) => {
+                ExportContent::Deleted {
+                    deleted_at: deleted.deleted_at,
+                }
+            }
+            Some(rpc_proto::chat_content_message::Message::Reaction(reaction)) => {
+                ExportContent::Reaction {
+                    message_id: bs58::encode(&reaction.message_id).into_string(),
+                    emoji: reaction.emoji,
+                    remove: reaction.remove,
+                }
+            }
+            None => ExportContent::Unknown,
+        }
+    }
+
+    /// copy an attached file into the export folder
+    ///
+    /// Returns the path of the copy relative to the export folder.
+    fn export_file(
+        account_id: PeerId,
+        file_content: &rpc_proto::FileContent,
+        folder: &Path,
+    ) -> Option<String> {
+        let source = ChatFile::get_file_path(account_id, file_content.file_id)?;
+        let file_name = source.file_name()?.to_string_lossy().to_string();
+
+        let files_folder = folder.join(FILES_FOLDER);
+        if let Err(e) = fs::create_dir_all(&files_folder) {
+            log::error!("creating folder error {}", e);
+            return None;
+        }
+        if let Err(e) = fs::copy(&source, files_folder.join(&file_name)) {
+            log::error!("copying file {} error {}", file_content.file_id, e);
+            return None;
+        }
+
+        Some(format!("{}/{}", FILES_FOLDER, file_name))
+    }
+
+    /// read a JSON archive and import its conversations
+    ///
+    /// Returns the number of imported conversations and messages.
+    fn read_import(account_id: PeerId, path: &str) -> Result<(u32, u32), String> {
+        if UserAccounts::get_by_id(account_id).is_none() {
+            return Err("user account not found".to_string());
+        }
+
+        let path = Path::new(path);
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) => return Err(e.to_string()),
+        };
+        let archive: ExportArchive = match serde_json::from_str(&json) {
+            Ok(archive) => archive,
+            Err(e) => return Err(e.to_string()),
+        };
+        if archive.version > ARCHIVE_VERSION {
+            return Err(format!("unsupported archive version {}", archive.version));
+        }
+
+        // convert all conversations before anything is saved,
+        // an invalid archive is not imported at all
+        let folder = path.parent().unwrap_or(Path::new(""));
+        let mut imported = Vec::new();
+        for conversation in archive.conversations {
+            imported.push(Self::import_conversation(account_id, conversation, folder)?);
+        }
+
+        let mut conversations = 0;
+        let mut messages = 0;
+        for (group, imported_messages) in imported {
+            let count = Self::save_conversation(account_id, group, imported_messages);
+            if count > 0 {
+                conversations += 1;
+                messages += count;
+            }
+        }
+
+        Ok((conversations, messages))
+    }
+
+    /// convert an exported conversation
+    ///
+    /// Returns the group to create, if it doesn't exist yet,
+    /// and the messages to import.
+    /// Messages without a message id can't be recognized
+    /// as already imported, they are only imported into
+    /// new groups.
+    fn import_conversation(
+        account_id: PeerId,
+        conversation: ExportConversation,
+        folder: &Path,
+    ) -> Result<ImportedConversation, String> {
+        let group_id = match Uuid::parse_str(&conversation.group_id) {
+            Ok(uuid) => uuid.as_bytes().to_vec(),
+            Err(e) => return Err(format!("invalid group id {}: {}", conversation.group_id, e)),
+        };
+
+        // create a deactivated group
+        let mut new_group = None;
+        if !GroupStorage::group_exists(account_id, group_id.clone()) {
+            let mut group = Group::new();
+            group.id = group_id.clone();
+            group.name = conversation.name;
+            group.is_direct_chat = conversation.is_direct_chat;
+            group.created_at = conversation.created_at;
+            group.revision = conversation.revision;
+            group.status = group_rpc::GroupStatus::Deactivated as i32;
+            for member in conversation.members {
+                let user_id = Self::decode_id(&member.user_id)?;
+                group.members.insert(
+                    user_id.clone(),
+                    GroupMember {
+                        user_id,
+                        role: group_rpc::GroupMemberRole::from_str_name(&member.role)
+                            .unwrap_or(group_rpc::GroupMemberRole::User)
+                            as i32,
+                        joined_at: member.joined_at,
+                        state: group_rpc::GroupMemberState::from_str_name(&member.state)
+                            .unwrap_or(group_rpc::GroupMemberState::Invited)
+                            as i32,
+                        last_message_index: 0,
+                    },
+                );
+            }
+            new_group = Some(group);
+        }
+
+        // convert messages
+        let mut messages = Vec::new();
+        for export_message in conversation.messages {
+            let imported = Self::import_message(&group_id, export_message, folder)?;
+            if new_group.is_none() && imported.0.message_id.is_empty() {
+                continue;
+            }
+            messages.push(imported);
+        }
+
+        Ok((new_group, messages))
+    }
+
+    /// save an imported conversation
+    ///
+    /// Messages that already exist are skipped.
+    /// Returns the number of imported messages.
+    fn save_conversation(
+        account_id: PeerId,
+        new_group: Option<Group>,
+        messages: Vec<ImportedMessage>,
+    ) -> u32 {
+        let mut count = 0;
+        let mut last_message = None;
+        for (message, edits, file_path) in messages {
+            if !ChatStorage::import_message(&account_id, message.clone(), &edits) {
+                continue;
+            }
+            count += 1;
+
+            // copy attached file into the file storage
+            if let Some(source) = file_path {
+                if let Ok(rpc_proto::ChatContentMessage {
+                    message:
+                        Some(rpc_proto::chat_content_message::Message::FileContent(file_content)),
+                }) = rpc_proto::ChatContentMessage::decode(&message.content[..])
+                {
+                    if let Err(e) =
+                        ChatFile::import_file(account_id, &message, &file_content, &source)
+                    {
+                        log::error!("importing file {} error {}", file_content.file_id, e);
+                    }
+                }
+            }
+
+            last_message = Some(message);
+        }
+
+        // save the new group with its last message
+        if let Some(mut group) = new_group {
+            if let Some(message) = last_message {
+                group.last_message_at = message.received_at;
+                group.last_message_data = message.content;
+                group.last_message_sender_id = message.sender_id;
+            }
+            GroupStorage::save_group(account_id, group);
+        }
+
+        count
+    }
+
+    /// create a chat message from an exported message
+    ///
+    /// Returns the message, its edit history and the path of
+    /// its attached file, if the file is part of the export.
+    fn import_message(
+        group_id: &Vec<u8>,
+        export_message: ExportMessage,
+        folder: &Path,
+    ) -> Result<ImportedMessage, String> {
+        let mut message_reception_confirmed = Vec::new();
+        for confirmation in export_message.confirmations {
+            message_reception_confirmed.push(rpc_proto::MessageReceptionConfirmed {
+                user_id: Self::decode_id(&confirmation.user_id)?,
+                confirmed_at: confirmation.confirmed_at,
+                read_at: confirmation.read_at,
+            });
+        }
+
+        let mut reactions = Vec::new();
+        for reaction in export_message.reactions {
+            let mut user_ids = Vec::new();
+            for user_id in &reaction.user_ids {
+                user_ids.push(Self::decode_id(user_id)?);
+            }
+            reactions.push(rpc_proto::MessageReaction {
+                emoji: reaction.emoji,
+                user_ids,
+            });
+        }
+
+        let edits = export_message
+            .edits
+            .into_iter()
+            .map(|edit| rpc_proto::ChatEditEntry {
+                text: edit.text,
+                edited_at: edit.edited_at,
+            })
+            .collect();
+
+        let mut reply_to = Vec::new();
+        let mut file_path = None;
+        let content = match export_message.content {
+            ExportContent::Text { text, edited_at } => {
+                Some(rpc_proto::chat_content_message::Message::ChatContent(
+                    rpc_proto::ChatContent { text, edited_at },
+                ))
+            }
+            ExportContent::Reply {
+                reply_to: reply_message_id,
+                reply_to_sender_id,
+                quote,
+                text,
+                edited_at,
+                ..
+            } => {
+                reply_to = Self::decode_id(&reply_message_id)?;
+                Some(rpc_proto::chat_content_message::Message::Reply(
+                    rpc_proto::ChatReply {
+                        message_id: reply_to.clone(),
+                        sender_id: Self::decode_id(&reply_to_sender_id)?,
+                        quote,
+                        text,
+                        edited_at,
+                    },
+                ))
+            }
+            ExportContent::File {
+                file_id,
+                file_name,
+                file_extension,
+                file_size,
+                file_description,
+                path,
+            } => {
+                // only accept paths within the export folder
+                let relative = Path::new(&path);
+                if !path.is_empty()
+                    && relative
+                        .components()
+                        .all(|component| matches!(component, Component::Normal(_)))
+                {
+                    file_path = Some(folder.join(relative));
+                }
+                Some(rpc_proto::chat_content_message::Message::FileContent(
+                    rpc_proto::FileContent {
+                        file_id,
+                        file_name,
+                        file_extension,
+                        file_size,
+                        file_description,
+                    },
+                ))
+            }
+            ExportContent::GroupEvent { event, user_id, .. } => Some(
+                rpc_proto::chat_content_message::Message::GroupEvent(rpc_proto::GroupEvent {
+                    event_type: rpc_proto::GroupEventType::from_str_name(&event)
+                        .unwrap_or(rpc_proto::GroupEventType::Default)
+                        as i32,
+                    user_id: Self::decode_id(&user_id)?,
+                }),
+            ),
+            ExportContent::Deleted { deleted_at } => {
+                Some(rpc_proto::chat_content_message::Message::MessageDeleted(
+                    rpc_proto::MessageDeleted { deleted_at },
+                ))
+            }
+            ExportContent::Reaction {
+                message_id,
+                emoji,
+                remove,
+            } => Some(rpc_proto::chat_content_message::Message::Reaction(
+                rpc_proto::ChatReaction {
+                    message_id: Self::decode_id(&message_id)?,
+                    emoji,
+                    remove,
+                },
+            )),
+            ExportContent::Unknown => None,
+        };
+
+        let message = rpc_proto::ChatMessage {
+            index: 0,
+            sender_id: Self::decode_id(&export_message.sender_id)?,
+            message_id: Self::decode_id(&export_message.message_id)?,
+            status: rpc_proto::MessageStatus::from_str_name(&export_message.status)
+                .unwrap_or(rpc_proto::MessageStatus::Received) as i32,
+            message_reception_confirmed,
+            group_id: group_id.clone(),
+            sent_at: export_message.sent_at,
+            received_at: export_message.received_at,
+            content: rpc_proto::ChatContentMessage { message: content }.encode_to_vec(),
+            reply_to,
+            reactions,
+        };
+
+        Ok((message, edits, file_path))
+    }
+
+    /// decode a base58 id
+    fn decode_id(id: &str) -> Result<Vec<u8>, String> {
+        match bs58::decode(id).into_vec() {
+            Ok(bytes) => Ok(bytes),
+            Err(e) => Err(format!("invalid id {}: {}", id, e)),
+        }
+    }
+
+    /// get the name of a user
+    ///
+    /// Returns an empty string for unknown users.
+    fn user_name(account: &UserAccount, user_id: &Vec<u8>) -> String {
+        if *user_id == account.id.to_bytes() {
+            return account.name.clone();
+        }
+        match PeerId::from_bytes(user_id) {
+            Ok(peer_id) => Users::get_name(&peer_id).unwrap_or_default(),
+            Err(_) => String::new(),
+        }
+    }
+
+    /// format a timestamp as UTC date and time
+    ///
+    /// Timestamps after the year 9999 are printed in milliseconds.
+    fn format_time(timestamp: u64) -> String {
+        if timestamp > MAX_RFC3339_TIME {
+            return format!("{} ms", timestamp);
+        }
+        let time = UNIX_EPOCH + Duration::from_millis(timestamp);
+        humantime::format_rfc3339_seconds(time).to_string()
+    }
+
+    /// human readable name of a sender or receiver
+    fn display_name(name: &str, user_id: &str) -> String {
+        if name.is_empty() {
+            user_id.to_string()
+        } else {
+            format!("{} ({})", name, user_id)
+        }
+    }
+
+    /// human readable delivery status of a message
+    fn display_status(message: &ExportMessage) -> String {
+        let mut status = message.status.to_lowercase().replace('_', " ");
+        for confirmation in &message.confirmations {
+            let name = if confirmation.user_name.is_empty() {
+                &confirmation.user_id
+            } else {
+                &confirmation.user_name
+            };
+            status.push_str(&format!(
+                "; received by {} at {}",
+                name,
+                Self::format_time(confirmation.confirmed_at)
+            ));
+            if confirmation.read_at > 0 {
+                status.push_str(&format!(
+                    ", read at {}",
+                    Self::format_time(confirmation.read_at)
+                ));
+            }
+        }
+        status
+    }
+
+    /// human readable content lines of a message
+    ///
+    /// File messages return the relative path of the
+    /// exported file as second value.
+    fn display_content(content: &ExportContent) -> (Vec<String>, Option<String>) {
+        match content {
+            ExportContent::Text { text, edited_at } => {
+                let mut lines: Vec<String> = text.lines().map(|line| line.to_string()).collect();
+                if *edited_at > 0 {
+                    lines.push(format!("(edited at {})", Self::format_time(*edited_at)));
+                }
+                (lines, None)
+            }
+            ExportContent::Reply {
+                reply_to_sender_id,
+                reply_to_sender_name,
+                quote,
+                text,
+                edited_at,
+                ..
+            } => {
+                let mut lines = vec![format!(
+                    "Reply to {}: \"{}\"",
+                    Self::display_name(reply_to_sender_name, reply_to_sender_id),
+                    quote
+                )];
+                lines.extend(text.lines().map(|line| line.to_string()));
+                if *edited_at > 0 {
+                    lines.push(format!("(edited at {})", Self::format_time(*edited_at)));
+                }
+                (lines, None)
+            }
+            ExportContent::File {
+                file_name,
+                file_size,
+                file_description,
+                path,
+                ..
+            } => {
+                let mut lines = vec![format!("File: {} ({} bytes)", file_name, file_size)];
+                if !file_description.is_empty() {
+                    lines.push(file_description.clone());
+                }
+                if path.is_empty() {
+                    lines.push("(file not available)".to_string());
+                    (lines, None)
+                } else {
+                    (lines, Some(path.clone()))
+                }
+            }
+            ExportContent::GroupEvent {
+                event,
+                user_id,
+                user_name,
+            } => (
+                vec![format!(
+                    "Group event {}: {}",
+                    event.to_lowercase().replace('_', " "),
+                    Self::display_name(user_name, user_id)
+                )],
+                None,
+            ),
+            ExportContent::Deleted { deleted_at } => (
+                vec![format!(
+                    "(message deleted at {})",
+                    Self::format_time(*deleted_at)
+                )],
+                None,
+            ),
+            ExportContent::Reaction {
+                message_id,
+                emoji,
+                remove,
+            } => {
+                let action = if *remove { "removed" } else { "added" };
+                (
+                    vec![format!("Reaction {} {} on {}", emoji, action, message_id)],
+                    None,
+                )
+            }
+            ExportContent::Unknown => (vec!["(unknown content)".to_string()], None),
+        }
+    }
+
+    /// human readable reactions of a message
+    fn display_reactions(message: &ExportMessage) -> Option<String> {
+        if message.reactions.is_empty() {
+            return None;
+        }
+        let reactions: Vec<String> = message
+            .reactions
+            .iter()
+            .map(|reaction| {
+                let names: Vec<&String> = reaction
+                    .user_names
+                    .iter()
+                    .zip(reaction.user_ids.iter())
+                    .map(|(name, user_id)| if name.is_empty() { user_id } else { name })
+                    .collect();
+                format!(
+                    "{} {}",
+                    reaction.emoji,
+                    names
+                        .iter()
+                        .map(|name| name.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                )
+            })
+            .collect();
+        Some(format!("Reactions: {}", reactions.join("; ")))
+    }
+
+    /// create the Markdown document of an archive
+    fn to_markdown(archive: &ExportArchive) -> String {
+        let mut md = String::new();
+        md.push_str("# qaul Chat Export\n\n");
+        md.push_str(&format!(
+            "* Account: {}\n* Exported at: {}\n",
+            Self::display_name(&archive.account_name, &archive.account_id),
+            Self::format_time(archive.exported_at)
+        ));
+
+        for conversation in &archive.conversations {
+            md.push_str(&format!("\n## {}\n\n", conversation.name));
+            md.push_str(&format!("* Group ID: {}\n", conversation.group_id));
+            md.push_str(&format!(
+                "* Created at: {}\n",
+                Self::format_time(conversation.created_at)
+            ));
+            md.push_str("* Members:\n");
+            for member in &conversation.members {
+                md.push_str(&format!(
+                    "  * {}, {}, {}\n",
+                    Self::display_name(&member.name, &member.user_id),
+                    member.role.to_lowercase(),
+                    member.state.to_lowercase()
+                ));
+            }
+
+            for message in &conversation.messages {
+                md.push_str(&format!(
+                    "\n**{}** — {}\n\n",
+                    Self::display_name(&message.sender_name, &message.sender_id),
+                    Self::format_time(message.sent_at)
+                ));
+                let (lines, path) = Self::display_content(&message.content);
+                for line in lines {
+                    md.push_str(&format!("> {}\n", line));
+                }
+                if let Some(path) = path {
+                    md.push_str(&format!("> [{}]({})\n", path, path));
+                }
+                md.push('\n');
+                if let Some(reactions) = Self::display_reactions(message) {
+                    md.push_str(&format!("{}  \n", reactions));
+                }
+                md.push_str(&format!(
+                    "*Received at {}, status: {}*\n",
+                    Self::format_time(message.received_at),
+                    Self::display_status(message)
+                ));
+            }
+        }
+
+        md
+    }
+
+    /// create the HTML document of an archive
+    fn to_html(archive: &ExportArchive) -> String {
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<title>qaul Chat Export</title>\n");
+        html.push_str(
+            "<style>\nbody { font-family: sans-serif; max-width: 50em; margin: auto; }\n",
+        );
+        html.push_str(
+            ".message { border-left: 3px solid #ccc; margin: 1em 0; padding-left: 1em; }\n",
+        );
+        html.push_str(".meta { color: #666; font-size: 0.9em; }\n</style>\n");
+        html.push_str("</head>\n<body>\n<h1>qaul Chat Export</h1>\n");
+        html.push_str(&format!(
+            "<ul>\n<li>Account: {}</li>\n<li>Exported at: {}</li>\n</ul>\n",
+            Self::escape_html(&Self::display_name(
+                &archive.account_name,
+                &archive.account_id
+            )),
+            Self::format_time(archive.exported_at)
+        ));
+
+        for conversation in &archive.conversations {
+            html.push_str(&format!(
+                "<section>\n<h2>{}</h2>\n<ul>\n",
+                Self::escape_html(&conversation.name)
+            ));
+            html.push_str(&format!(
+                "<li>Group ID: {}</li>\n<li>Created at: {}</li>\n<li>Members:\n<ul>\n",
+                Self::escape_html(&conversation.group_id),
+                Self::format_time(conversation.created_at)
+            ));
+            for member in &conversation.members {
+                html.push_str(&format!(
+                    "<li>{}, {}, {}</li>\n",
+                    Self::escape_html(&Self::display_name(&member.name, &member.user_id)),
+                    Self::escape_html(&member.role.to_lowercase()),
+                    Self::escape_html(&member.state.to_lowercase())
+                ));
+            }
+            html.push_str("</ul>\n</li>\n</ul>\n");
+
+            for message in &conversation.messages {
+                html.push_str(&format!(
+                    "<div class=\"message\">\n<p><b>{}</b> — {}</p>\n",
+                    Self::escape_html(&Self::display_name(
+                        &message.sender_name,
+                        &message.sender_id
+                    )),
+                    Self::format_time(message.sent_at)
+                ));
+                let (lines, path) = Self::display_content(&message.content);
+                let lines: Vec<String> = lines.iter().map(|line| Self::escape_html(line)).collect();
+                html.push_str(&format!("<p>{}</p>\n", lines.join("<br>\n")));
+                if let Some(path) = path {
+                    let path = Self::escape_html(&path);
+                    html.push_str(&format!("<p><a href=\"{}\">{}</a></p>\n", path, path));
+                }
+                if let Some(reactions) = Self::display_reactions(message) {
+                    html.push_str(&format!("<p>{}</p>\n", Self::escape_html(&reactions)));
+                }
+                html.push_str(&format!(
+                    "<p class=\"meta\">Received at {}, status: {}</p>\n</div>\n",
+                    Self::format_time(message.received_at),
+                    Self::escape_html(&Self::display_status(message))
+                ));
+            }
+            html.push_str("</section>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// escape a text for HTML
+    fn escape_html(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&#39;"),
+                _ => escaped.push(c),
+            }
+        }
+        escaped
+    }
+}
@@ -5,31 +5,102 @@
 //!
 //! Sending files via the chat messenger to other users and groups.
 //! The chat file messages use the messaging service.
+//!
+//! ## Resumable Transfers
+//!
+//! The receiver tracks the reception of each incoming file.
+//! Data chunks arriving before the file info message are kept
+//! until the info message arrives.
+//! If no further chunks arrive for `TRANSFER_REQUEST_TIMEOUT`,
+//! the missing chunks are requested.
+//! The transfer state is saved in the data base and continues
+//! after a restart.
+//!
+//! ## Multi-Source Downloading
+//!
+//! Group members that have received a complete file advertise
+//! its availability to the group, and answer chunk requests.
+//! The receiver requests different ranges of the missing chunks
+//! from the sender and the advertising members in parallel.
+//! The sources are chosen by the quality of their route.
+//! Once the requested chunks have arrived, the next chunks are
+//! requested after `REQUEST_ROUND_TIMEOUT`.
+//! If no source is reachable, the sender is requested first, and
+//! after `SENDER_REQUEST_ATTEMPTS` unanswered requests, the other
+//! group members are requested in turns.
+//! Members that missed the file info message, learn about the file
+//! from the advertisements and request the info message.
+//! The info message is signed by the sender of the file, a resent
+//! info message is only accepted with a valid signature.
+//! Only group members are answered.
+//!
+//! ## Integrity Verification
+//!
+//! The file info message contains the SHA-256 hash of the file
+//! and of each data chunk.
+//! The hashes are only accepted from info messages sent or signed
+//! by the original sender of the file.
+//! Chunks of files without hashes are only accepted from the sender.
+//! Received chunks are verified with their hash, invalid chunks
+//! are dropped and requested again.
+//! Once stored, the whole file is verified with the file hash.
+//!
+//! ## Sending Files
+//!
+//! Files are hashed, copied and read by a worker thread, so that
+//! big files don't block the event loop.
+//! The worker hands the data chunks over to the event loop via a
+//! bounded channel.
+//! The event loop only schedules further chunks for sending, while
+//! the file messages in the messaging send queue are below
+//! `SEND_QUEUE_LIMIT`.
+//! The worker is blocked, once the channel is full.
+//! The progress of the sending is reported to the UI.
+//!
+//! ## Receive Policy and Cancellation
+//!
+//! Incoming files are checked against the file receive policy
+//! of the user account, once their info message arrives.
+//! Files of users that are not verified can be rejected, and
+//! files can be held back for manual acceptance.
+//! The chunks of a held back file are requested from the sender,
+//! once the user accepts it.
+//!
+//! The sender and the receivers can cancel a file transfer.
+//! The other side is informed with a cancel message.
 
+use crossbeam_channel::{bounded, Receiver, Sender};
 use libp2p::PeerId;
 use prost::Message;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sled;
 use state::InitCell;
 use std::{
-    collections::BTreeMap,
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
     convert::TryInto,
     ffi::OsStr,
     fs::{self, File},
-    io::{Read, Write},
+    io::{Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
     sync::RwLock,
+    thread,
 };
 
 use super::ChatStorage;
 use crate::services::messaging::{self, Messaging, MessagingServiceType};
+use crate::storage::configuration::{Configuration, FilePolicy};
 use crate::storage::database::DataBase;
 use crate::utilities::timestamp;
 use crate::utilities::timestamp::Timestamp;
 use crate::{
     node::user_accounts::{UserAccount, UserAccounts},
-    router::users::Users,
-    services::group::{self, group_id::GroupId, Group, GroupStorage},
+    router::{
+        table::{RoutingConnectionEntry, RoutingTable},
+        users::Users,
+    },
+    services::group::{self, group_id::GroupId, proto_rpc::GroupMemberState, Group, GroupStorage},
 };
 use crate::{rpc::Rpc, services::group::GroupManage};
 
@@ -45,6 +116,42 @@ pub mod proto_net {
 /// Size of the biggest file data package
 pub const DEF_PACKAGE_SIZE: u32 = 64000;
 
+/// time without received chunks, after which the missing
+/// chunks of a file are requested, in milliseconds
+const TRANSFER_REQUEST_TIMEOUT: u64 = 60 * 1000;
+
+/// number of requests sent to the sender of a file,
+/// before the other group members are requested
+const SENDER_REQUEST_ATTEMPTS: u32 = 3;
+
+/// maximal number of chunks requested at once from a user
+const MAX_REQUEST_CHUNKS: usize = 128;
+
+/// maximal number of users requested in parallel
+const MAX_REQUEST_SOURCES: usize = 4;
+
+/// time without received chunks after an answered request,
+/// after which the next chunks are requested, in milliseconds
+const REQUEST_ROUND_TIMEOUT: u64 = 10 * 1000;
+
+/// number of data chunks a file sending worker
+/// reads ahead of the event loop
+const SEND_CHANNEL_SIZE: usize = 16;
+
+/// maximal number of file messages in the messaging send queue,
+/// up to which further data chunks are scheduled for sending
+const SEND_QUEUE_LIMIT: u32 = 64;
+
+/// maximal number of file sending events processed per call
+const SEND_BATCH_SIZE: usize = 8;
+
+/// number of data chunks after which the sending
+/// progress is reported to the UI
+const PROGRESS_INTERVAL: u32 = 16;
+
+/// channel from the file sending workers to the event loop
+static SEND_CHANNEL: InitCell<(Sender<FileSendEvent>, Receiver<FileSendEvent>)> = InitCell::new();
+
 /// mutable state of all file
 static ALLFILES: InitCell<RwLock<AllFiles>> = InitCell::new();
 
@@ -68,6 +175,26 @@ pub struct UserFiles {
     /// key: file_id & chunk_index
     /// value: `Vec<u8>`
     pub file_chunks: sled::Tree,
+    /// incoming file transfers
+    ///
+    /// key: file ID
+    /// value: bincode of `FileTransfer`
+    pub transfers: sled::Tree,
+    /// file content hashes
+    ///
+    /// key: file ID
+    /// value: bincode of `FileHashes`
+    pub hashes: sled::Tree,
+    /// receivers that cancelled receiving our files
+    ///
+    /// key: file ID & user ID
+    /// value: empty
+    pub cancels: sled::Tree,
+    /// group members that advertised a complete file
+    ///
+    /// key: file ID & user ID
+    /// value: empty
+    pub sources: sled::Tree,
 }
 
 impl UserFiles {
@@ -75,11 +202,8 @@ impl UserFiles {
     pub fn get_filehistory(&self, file_id: u64) -> Option<FileHistory> {
         // get invite
         match self.histories.get(file_id.to_be_bytes().to_vec()) {
-            Ok(file_history_bytes) => {
-                let file_history: FileHistory =
-                    bincode::deserialize(&file_history_bytes.unwrap()).unwrap();
-                return Some(file_history);
-            }
+            Ok(Some(file_history_bytes)) => return FileHistory::from_bytes(&file_history_bytes),
+            Ok(None) => {}
             Err(e) => log::error!("{}", e),
         }
 
@@ -179,6 +303,168 @@ impl UserFiles {
 
         result
     }
+
+    /// get the indexes of the missing file chunks
+    ///
+    /// Returns maximally `max` indexes.
+    pub fn get_missing_chunks(&self, file_id: u64, chunk_count: u32, max: usize) -> Vec<u32> {
+        // collect the indexes of the received chunks
+        let mut received: BTreeSet<u32> = BTreeSet::new();
+        for key in self.get_file_chunks(&file_id.to_be_bytes().to_vec()).keys() {
+            if let Ok(key) = key {
+                if let Ok(index_bytes) = key[key.len() - 4..].try_into() {
+                    received.insert(u32::from_be_bytes(index_bytes));
+                }
+            }
+        }
+
+        (0..chunk_count)
+            .filter(|index| !received.contains(index))
+            .take(max)
+            .collect()
+    }
+
+    /// remove all file chunks of a file
+    pub fn remove_file_chunks(&self, file_id: u64) {
+        let keys: Vec<sled::IVec> = self
+            .get_file_chunks(&file_id.to_be_bytes().to_vec())
+            .keys()
+            .filter_map(|key| key.ok())
+            .collect();
+        for key in keys {
+            if let Err(e) = self.file_chunks.remove(key) {
+                log::error!("Error removing file chunk: {}", e);
+            }
+        }
+        if let Err(e) = self.file_chunks.flush() {
+            log::error!("Error file chunks flush: {}", e);
+        }
+    }
+
+    /// get incoming file transfer
+    pub fn get_transfer(&self, file_id: u64) -> Option<FileTransfer> {
+        match self.transfers.get(file_id.to_be_bytes().to_vec()) {
+            Ok(Some(transfer_bytes)) => bincode::deserialize(&transfer_bytes).ok(),
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// save incoming file transfer
+    pub fn save_transfer(&self, file_id: u64, transfer: &FileTransfer) {
+        let transfer_bytes = bincode::serialize(transfer).unwrap();
+        if let Err(e) = self
+            .transfers
+            .insert(file_id.to_be_bytes().to_vec(), transfer_bytes)
+        {
+            log::error!("Error saving file transfer to data base: {}", e);
+            return;
+        }
+        if let Err(e) = self.transfers.flush() {
+            log::error!("Error file transfer flush: {}", e);
+        }
+    }
+
+    /// get the content hashes of a file
+    pub fn get_hashes(&self, file_id: u64) -> Option<FileHashes> {
+        match self.hashes.get(file_id.to_be_bytes().to_vec()) {
+            Ok(Some(hashes_bytes)) => bincode::deserialize(&hashes_bytes).ok(),
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// save the content hashes of a file
+    pub fn save_hashes(&self, file_id: u64, hashes: &FileHashes) {
+        let hashes_bytes = bincode::serialize(hashes).unwrap();
+        if let Err(e) = self
+            .hashes
+            .insert(file_id.to_be_bytes().to_vec(), hashes_bytes)
+        {
+            log::error!("Error saving file hashes to data base: {}", e);
+            return;
+        }
+        if let Err(e) = self.hashes.flush() {
+            log::error!("Error file hashes flush: {}", e);
+        }
+    }
+
+    /// save that a receiver cancelled receiving a file
+    pub fn save_cancel(&self, file_id: u64, user_id: &PeerId) {
+        let mut key = file_id.to_be_bytes().to_vec();
+        key.extend(user_id.to_bytes());
+        if let Err(e) = self.cancels.insert(key, Vec::new()) {
+            log::error!("Error saving file cancel to data base: {}", e);
+            return;
+        }
+        if let Err(e) = self.cancels.flush() {
+            log::error!("Error file cancels flush: {}", e);
+        }
+    }
+
+    /// check if a receiver cancelled receiving a file
+    pub fn is_cancelled(&self, file_id: u64, user_id: &PeerId) -> bool {
+        let mut key = file_id.to_be_bytes().to_vec();
+        key.extend(user_id.to_bytes());
+        self.cancels.contains_key(key).unwrap_or(false)
+    }
+
+    /// save a group member that has the complete file
+    pub fn save_source(&self, file_id: u64, user_id: &PeerId) {
+        let mut key = file_id.to_be_bytes().to_vec();
+        key.extend(user_id.to_bytes());
+        if let Err(e) = self.sources.insert(key, Vec::new()) {
+            log::error!("Error saving file source to data base: {}", e);
+            return;
+        }
+        if let Err(e) = self.sources.flush() {
+            log::error!("Error file sources flush: {}", e);
+        }
+    }
+
+    /// get all group members that have the complete file
+    pub fn get_sources(&self, file_id: u64) -> Vec<PeerId> {
+        self.sources
+            .scan_prefix(file_id.to_be_bytes())
+            .keys()
+            .filter_map(|key| key.ok())
+            .filter_map(|key| PeerId::from_bytes(&key[8..]).ok())
+            .collect()
+    }
+
+    /// remove all sources of a file
+    pub fn remove_sources(&self, file_id: u64) {
+        let keys: Vec<sled::IVec> = self
+            .sources
+            .scan_prefix(file_id.to_be_bytes())
+            .keys()
+            .filter_map(|key| key.ok())
+            .collect();
+        for key in keys {
+            if let Err(e) = self.sources.remove(key) {
+                log::error!("Error removing file source: {}", e);
+            }
+        }
+        if let Err(e) = self.sources.flush() {
+            log::error!("Error file sources flush: {}", e);
+        }
+    }
+
+    /// remove a completed file transfer
+    pub fn remove_transfer(&self, file_id: u64) {
+        if let Err(e) = self.transfers.remove(file_id.to_be_bytes().to_vec()) {
+            log::error!("Error removing file transfer: {}", e);
+        }
+        if let Err(e) = self.transfers.flush() {
+            log::error!("Error file transfer flush: {}", e);
+        }
+    }
 }
 
 /// File State
@@ -196,6 +482,26 @@ pub enum FileState {
     Receiving,
     /// File successfully received
     Received,
+    /// Waiting for manual acceptance
+    Pending,
+    /// The transfer has been cancelled
+    Cancelled,
+}
+
+impl FileState {
+    /// get the RPC file state
+    pub fn to_rpc(&self) -> proto_rpc::FileState {
+        match self {
+            FileState::Sending => proto_rpc::FileState::Sending,
+            FileState::Sent => proto_rpc::FileState::Sent,
+            FileState::Confirmed => proto_rpc::FileState::Confirmed,
+            FileState::ConfirmedByAll => proto_rpc::FileState::ConfirmedByAll,
+            FileState::Receiving => proto_rpc::FileState::Receiving,
+            FileState::Received => proto_rpc::FileState::Received,
+            FileState::Pending => proto_rpc::FileState::Pending,
+            FileState::Cancelled => proto_rpc::FileState::Cancelled,
+        }
+    }
 }
 
 /// User Reception Tracking
@@ -233,14 +539,123 @@ pub struct FileHistory {
     /// file extension
     pub file_extension: String,
     /// file size in bytes
-    pub file_size: u32,
+    pub file_size: u64,
     /// file sent
     pub sent_at: u64,
     /// file received
     pub received_at: u64,
 }
 
+/// FileHistory format with a 32 bit file size
+#[derive(Deserialize)]
+struct LegacyFileHistory {
+    group_id: Vec<u8>,
+    sender_id: Vec<u8>,
+    file_id: u64,
+    message_id: Vec<u8>,
+    start_index: u32,
+    message_count: u32,
+    chunk_size: u32,
+    file_state: FileState,
+    reception_tracking: BTreeMap<Vec<u8>, ReceptionTracking>,
+    file_name: String,
+    file_description: String,
+    file_extension: String,
+    file_size: u32,
+    sent_at: u64,
+    received_at: u64,
+}
+
+/// Incoming File Transfer
+///
+/// Tracks the reception of a file, until all
+/// chunks have been received.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FileTransfer {
+    /// group id
+    pub group_id: Vec<u8>,
+    /// qaul id of the sender of the file
+    pub sender_id: Vec<u8>,
+    /// message id
+    pub message_id: Vec<u8>,
+    /// number of file messages, including the info message
+    pub message_count: u32,
+    /// the file info message has been received
+    pub has_info: bool,
+    /// time the last file message was received
+    pub last_received: u64,
+    /// time the last request for missing chunks was sent
+    pub last_request: u64,
+    /// number of requests sent since the last answered request
+    pub request_count: u32,
+}
+
+/// Content Hashes of a File
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FileHashes {
+    /// SHA-256 hash of the file content
+    pub file_hash: Vec<u8>,
+    /// SHA-256 hashes of the data chunks
+    pub chunk_hashes: Vec<Vec<u8>>,
+    /// the stored file has been verified with the file hash
+    pub verified: bool,
+    /// signature of the file info message by the original sender
+    ///
+    /// It is included when the info message is resent.
+    pub signature: Vec<u8>,
+}
+
+impl FileHashes {
+    /// verify a data chunk with its hash
+    ///
+    /// Chunks of files without hashes can't be verified
+    /// and are always accepted.
+    pub fn verify_chunk(&self, index: u32, data: &[u8]) -> bool {
+        if self.chunk_hashes.is_empty() {
+            return true;
+        }
+        match self.chunk_hashes.get(index as usize) {
+            Some(hash) => Sha256::digest(data).as_slice() == hash.as_slice(),
+            None => false,
+        }
+    }
+}
+
 impl FileHistory {
+    /// decode a file history from the data base
+    ///
+    /// File histories saved before the introduction of
+    /// 64 bit file sizes are converted.
+    pub fn from_bytes(file_history_bytes: &[u8]) -> Option<FileHistory> {
+        if let Ok(file_history) = bincode::deserialize::<FileHistory>(file_history_bytes) {
+            return Some(file_history);
+        }
+
+        match bincode::deserialize::<LegacyFileHistory>(file_history_bytes) {
+            Ok(legacy) => Some(FileHistory {
+                group_id: legacy.group_id,
+                sender_id: legacy.sender_id,
+                file_id: legacy.file_id,
+                message_id: legacy.message_id,
+                start_index: legacy.start_index,
+                message_count: legacy.message_count,
+                chunk_size: legacy.chunk_size,
+                file_state: legacy.file_state,
+                reception_tracking: legacy.reception_tracking,
+                file_name: legacy.file_name,
+                file_description: legacy.file_description,
+                file_extension: legacy.file_extension,
+                file_size: legacy.file_size as u64,
+                sent_at: legacy.sent_at,
+                received_at: legacy.received_at,
+            }),
+            Err(e) => {
+                log::error!("file history decoding error: {}", e);
+                None
+            }
+        }
+    }
+
     /// the reception of a file message has successfully been confirmed
     ///
     /// the function returns a boolean that indicates, whether the user finished receiving
@@ -278,6 +693,29 @@ impl FileHistory {
     }
 }
 
+/// Event of a File Sending Worker
+enum FileSendEvent {
+    /// the file has been hashed and copied to the file storage
+    Prepared {
+        account_id: PeerId,
+        file_history: FileHistory,
+        hashes: FileHashes,
+    },
+    /// a data chunk of the file has been read
+    Chunk {
+        account_id: PeerId,
+        file_id: u64,
+        index: u32,
+        data: Vec<u8>,
+    },
+    /// preparing or reading the file failed
+    Failed {
+        account_id: PeerId,
+        file_id: u64,
+        error: String,
+    },
+}
+
 pub struct ChatFile {}
 /// File sharing module to process transfer, receive and RPC commands
 impl ChatFile {
@@ -288,6 +726,9 @@ impl ChatFile {
             db_ref: BTreeMap::new(),
         };
         ALLFILES.set(RwLock::new(all_files));
+
+        // create channel of the file sending workers
+        SEND_CHANNEL.set(bounded(SEND_CHANNEL_SIZE));
     }
 
     /// File history is stored based on the users account id.
@@ -303,6 +744,10 @@ impl ChatFile {
                 return UserFiles {
                     histories: user_files.histories.clone(),
                     file_chunks: user_files.file_chunks.clone(),
+                    transfers: user_files.transfers.clone(),
+                    hashes: user_files.hashes.clone(),
+                    cancels: user_files.cancels.clone(),
+                    sources: user_files.sources.clone(),
                 };
             }
         }
@@ -314,6 +759,10 @@ impl ChatFile {
         UserFiles {
             histories: user_files.histories.clone(),
             file_chunks: user_files.file_chunks.clone(),
+            transfers: user_files.transfers.clone(),
+            hashes: user_files.hashes.clone(),
+            cancels: user_files.cancels.clone(),
+            sources: user_files.sources.clone(),
         }
     }
 
@@ -325,10 +774,18 @@ impl ChatFile {
         // open trees
         let histories: sled::Tree = db.open_tree("chat_file").unwrap();
         let file_chunks: sled::Tree = db.open_tree("file_chunks").unwrap();
+        let transfers: sled::Tree = db.open_tree("file_transfers").unwrap();
+        let hashes: sled::Tree = db.open_tree("file_hashes").unwrap();
+        let cancels: sled::Tree = db.open_tree("file_cancels").unwrap();
+        let sources: sled::Tree = db.open_tree("file_sources").unwrap();
 
         let user_files = UserFiles {
             histories,
             file_chunks,
+            transfers,
+            hashes,
+            cancels,
+            sources,
         };
 
         // get chat state for writing
@@ -373,6 +830,79 @@ impl ChatFile {
         }
     }
 
+    /// Get the path of a completely sent or received file
+    pub fn get_file_path(account_id: PeerId, file_id: u64) -> Option<PathBuf> {
+        let user_files = ChatFile::get_db_ref(&account_id);
+        let file_history = user_files.get_filehistory(file_id)?;
+
+        match file_history.file_state {
+            FileState::Sent
+            | FileState::Confirmed
+            | FileState::ConfirmedByAll
+            | FileState::Received => {}
+            _ => return None,
+        }
+
+        let path = Self::create_file_path(account_id, file_id, &file_history.file_extension);
+        if path.is_file() {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Import a file of an imported chat message
+    ///
+    /// The file is copied into the file storage of the user account
+    /// and added to the file history.
+    /// Files that already exist are skipped.
+    pub fn import_file(
+        account_id: PeerId,
+        chat_message: &super::rpc_proto::ChatMessage,
+        file_content: &super::rpc_proto::FileContent,
+        source: &Path,
+    ) -> Result<(), String> {
+        let user_files = ChatFile::get_db_ref(&account_id);
+        if user_files.get_filehistory(file_content.file_id).is_some() {
+            return Ok(());
+        }
+
+        let path = Self::create_file_path(
+            account_id,
+            file_content.file_id,
+            &file_content.file_extension,
+        );
+        if let Err(e) = fs::copy(source, &path) {
+            return Err(e.to_string());
+        }
+
+        let file_state = if chat_message.sender_id == account_id.to_bytes() {
+            FileState::Sent
+        } else {
+            FileState::Received
+        };
+        let file_history = FileHistory {
+            group_id: chat_message.group_id.clone(),
+            sender_id: chat_message.sender_id.clone(),
+            file_id: file_content.file_id,
+            message_id: chat_message.message_id.clone(),
+            start_index: 0,
+            message_count: 0,
+            chunk_size: 0,
+            file_state,
+            reception_tracking: BTreeMap::new(),
+            file_name: file_content.file_name.clone(),
+            file_description: file_content.file_description.clone(),
+            file_extension: file_content.file_extension.clone(),
+            file_size: file_content.file_size,
+            sent_at: chat_message.sent_at,
+            received_at: chat_message.received_at,
+        };
+        user_files.save_filehistory(file_content.file_id, file_history);
+
+        Ok(())
+    }
+
     /// getting file extension from given filename
     fn get_extension_from_filename(filename: &str) -> Option<&str> {
         Path::new(filename).extension().and_then(OsStr::to_str)
@@ -424,9 +954,10 @@ impl ChatFile {
                     }
 
                     // check if we collect the result
-                    let file_history: FileHistory = bincode::deserialize(&message).unwrap();
                     if counter >= history_req.offset {
-                        histories.push(file_history);
+                        if let Some(file_history) = FileHistory::from_bytes(&message) {
+                            histories.push(file_history);
+                        }
                     }
 
                     counter = counter + 1;
@@ -439,231 +970,468 @@ impl ChatFile {
     }
 
     /// send a file from RPC to users
+    ///
+    /// The group and the file are checked, before the file is
+    /// handed over to a sending worker thread.
     fn send(
         user_account: &UserAccount,
         group_id: &Vec<u8>,
         path_name: String,
         description: String,
-    ) -> Result<bool, String> {
+    ) -> Result<(), String> {
         // get group
-        let group;
-        match GroupStorage::get_group(user_account.id, group_id.to_owned()) {
-            Some(v) => group = v,
-            None => {
-                match GroupId::from_bytes(group_id) {
-                    Ok(direct_group) => {
-                        // check if the group is a direct group
-                        match direct_group.is_direct(user_account.id.clone()) {
-                            Some(remote_q8id) => {
-                                // get remote user
-                                match Users::get_user_id_by_q8id(remote_q8id) {
-                                    Some(remote_id) => {
-                                        // create group
-                                        group = GroupManage::create_new_direct_chat_group(
-                                            &user_account.id,
-                                            &remote_id,
-                                        )
-                                    }
-                                    None => {
-                                        return Err(
-                                            "remote id of direct group not found".to_string()
-                                        )
-                                    }
+        if GroupStorage::get_group(user_account.id, group_id.to_owned()).is_none() {
+            match GroupId::from_bytes(group_id) {
+                Ok(direct_group) => {
+                    // check if the group is a direct group
+                    match direct_group.is_direct(user_account.id.clone()) {
+                        Some(remote_q8id) => {
+                            // get remote user
+                            match Users::get_user_id_by_q8id(remote_q8id) {
+                                Some(remote_id) => {
+                                    // create group
+                                    GroupManage::create_new_direct_chat_group(
+                                        &user_account.id,
+                                        &remote_id,
+                                    );
+                                }
+                                None => {
+                                    return Err("remote id of direct group not found".to_string())
                                 }
-                            }
-                            None => {
-                                return Err("Group does not exist and is not direct".to_string())
                             }
                         }
+                        None => return Err("Group does not exist and is not direct".to_string()),
                     }
-                    Err(_) => return Err("Group does not exist".to_string()),
                 }
+                Err(_) => return Err("Group does not exist".to_string()),
             }
         }
 
-        let timestamp = Timestamp::get_timestamp();
-
-        let mut file: File;
-        match File::open(path_name.clone()) {
-            Ok(f) => Some(file = f),
+        let file = match File::open(path_name.clone()) {
+            Ok(file) => file,
             Err(_e) => {
                 return Err("file open error".to_string());
             }
         };
 
-        let size = file.metadata().unwrap().len() as u32;
+        let size = match file.metadata() {
+            Ok(metadata) => metadata.len(),
+            Err(e) => return Err(e.to_string()),
+        };
         if size == 0 {
             return Err("file size is zero".to_string());
         }
 
+        // hash, copy and read the file in a worker thread
+        let account_id = user_account.id;
+        let group_id = group_id.to_owned();
+        let events = SEND_CHANNEL.get().0.clone();
+        thread::spawn(move || {
+            if let Err((file_id, error)) = Self::send_worker(
+                account_id,
+                group_id,
+                path_name,
+                description,
+                file,
+                size,
+                &events,
+            ) {
+                let _ = events.send(FileSendEvent::Failed {
+                    account_id,
+                    file_id,
+                    error,
+                });
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Prepare and read a file for sending
+    ///
+    /// This function runs in a file sending worker thread.
+    /// It hashes the file, copies it to the file storage of the
+    /// user account and reads the data chunks.
+    /// The results are sent to the event loop.
+    ///
+    /// Returns the file id and an error message on failure.
+    fn send_worker(
+        account_id: PeerId,
+        group_id: Vec<u8>,
+        path_name: String,
+        description: String,
+        mut file: File,
+        size: u64,
+        events: &Sender<FileSendEvent>,
+    ) -> Result<(), (u64, String)> {
         // get file name
         let path = Path::new(path_name.as_str());
-        let mut extension = "".to_string();
-
-        if let Some(ext) =
-            Self::get_extension_from_filename(path.file_name().unwrap().to_str().unwrap())
-        {
-            extension = ext.to_string();
+        let file_name = match path.file_name().and_then(OsStr::to_str) {
+            Some(file_name) => file_name.to_string(),
+            None => return Err((0, "invalid file name".to_string())),
+        };
+        let extension = Self::get_extension_from_filename(&file_name)
+            .unwrap_or("")
+            .to_string();
+
+        // count data chunks
+        let chunk_count = (size + DEF_PACKAGE_SIZE as u64 - 1) / DEF_PACKAGE_SIZE as u64;
+        if chunk_count >= u32::MAX as u64 {
+            return Err((0, "file is too big".to_string()));
         }
+        let chunk_count = chunk_count as u32;
 
-        let file_name = path.file_name().unwrap().to_str().unwrap().to_string();
+        // hash file content
+        let (file_hash, chunk_hashes) = Self::hash_file(&mut file).map_err(|e| (0, e))?;
+        if chunk_hashes.len() != chunk_count as usize {
+            return Err((0, "file changed while reading".to_string()));
+        }
 
         // create file id
         let file_id = Self::generate_file_id(
-            group_id,
-            &user_account.id.to_bytes(),
+            &group_id,
+            &account_id.to_bytes(),
             file_name.clone(),
             size,
+            &file_hash,
         );
 
-        // get file path
-        let file_path = Self::create_file_path(user_account.id, file_id, extension.as_str());
-
-        // TODO: start in new async thread here
-
         // copy file
+        let file_path = Self::create_file_path(account_id, file_id, extension.as_str());
         if let Err(e) = fs::copy(path_name.clone(), file_path) {
             log::error!("copy file error {}", e.to_string());
         }
 
-        // create messages
-        let mut mesage_count = 1 + size / DEF_PACKAGE_SIZE;
-        if size % DEF_PACKAGE_SIZE > 0 {
-            mesage_count = mesage_count + 1;
-        }
-
-        // create message ID
-        let message_id = group::GroupManage::get_new_message_id(&user_account.id, group_id);
-
-        // 1. file info message
-        let file_info = proto_net::ChatFileInfo {
-            file_id,
-            file_name: file_name.clone(),
-            file_extension: extension.clone(),
-            file_size: size,
-            file_description: description.clone(),
-            start_index: 0,
-            message_count: mesage_count,
-            data_chunk_size: DEF_PACKAGE_SIZE,
-        };
-
-        let info = proto_net::ChatFileContainer {
-            message: Some(proto_net::chat_file_container::Message::FileInfo(
-                file_info.clone(),
-            )),
-        };
-
-        // send message to all group members
-        Self::send_filecontainer_to_group(
-            user_account,
-            &group,
-            &message_id,
-            timestamp,
-            info.encode_to_vec(),
-        );
-
-        // create group ID object
-        let groupid = GroupId::from_bytes(group_id).unwrap();
-
-        // save file state to data base
+        // hand the file over to the event loop
         let file_history = FileHistory {
-            group_id: group_id.to_owned(),
-            sender_id: user_account.id.to_bytes(),
+            group_id,
+            sender_id: account_id.to_bytes(),
             file_id,
-            message_id: message_id.clone(),
-            start_index: file_info.start_index,
-            message_count: file_info.message_count,
+            message_id: Vec::new(),
+            start_index: 0,
+            message_count: chunk_count + 1,
             chunk_size: DEF_PACKAGE_SIZE,
             file_state: FileState::Sending,
             reception_tracking: BTreeMap::new(),
-            file_name: file_name.clone(),
-            file_description: description.clone(),
-            file_extension: extension.clone(),
+            file_name,
+            file_description: description,
+            file_extension: extension,
             file_size: size,
-            sent_at: timestamp,
+            sent_at: 0,
             received_at: 0,
         };
-
-        let db_ref = Self::get_db_ref(&user_account.id);
-
-        // save file history to data base
-        let file_history_bytes = bincode::serialize(&file_history).unwrap();
-        if let Err(e) = db_ref
-            .histories
-            .insert(&file_id.to_be_bytes(), file_history_bytes)
+        let hashes = FileHashes {
+            file_hash,
+            chunk_hashes,
+            verified: true,
+            signature: Vec::new(),
+        };
+        if events
+            .send(FileSendEvent::Prepared {
+                account_id,
+                file_history,
+                hashes,
+            })
+            .is_err()
         {
-            log::error!("Error saving file history to data base: {}", e);
-        } else {
-            if let Err(e) = db_ref.histories.flush() {
-                log::error!("Error when flushing data base to disk: {}", e);
-            }
+            return Ok(());
         }
 
-        // save file message to chat conversation
-        Self::save_filemsg_in_chat(
-            user_account,
-            &user_account.id,
-            &groupid,
-            &file_history,
-            super::rpc_proto::MessageStatus::Sending,
-        );
-
-        // 2. file data message
-        // read file contents and create and send FileData messages
-        let mut buffer: [u8; DEF_PACKAGE_SIZE as usize] = [0; DEF_PACKAGE_SIZE as usize];
+        // read the data chunks
+        let user_files = Self::get_db_ref(&account_id);
         let mut left_size = size;
-        let mut chunk_index: u32 = 0;
-
-        while left_size > 0 {
-            let mut read_size = left_size;
-            if left_size > DEF_PACKAGE_SIZE {
-                read_size = DEF_PACKAGE_SIZE;
-            };
-            left_size = left_size - read_size;
-
-            if let Err(e) = file.read(&mut buffer) {
-                return Err(e.to_string());
+        for index in 0..chunk_count {
+            // stop reading, once the sending has been cancelled
+            if let Some(file_history) = user_files.get_filehistory(file_id) {
+                if let FileState::Cancelled = file_history.file_state {
+                    return Ok(());
+                }
             }
 
-            // pack chat file container
-            let data = proto_net::ChatFileContainer {
-                message: Some(proto_net::chat_file_container::Message::FileData(
-                    proto_net::ChatFileData {
-                        file_id,
-                        start_index: chunk_index,
-                        message_count: mesage_count,
-                        data: buffer[0..(read_size as usize)].iter().cloned().collect(),
-                    },
-                )),
-            };
+            let read_size = left_size.min(DEF_PACKAGE_SIZE as u64) as usize;
+            left_size = left_size - read_size as u64;
 
-            // send message to all group members
-            Self::send_filecontainer_to_group(
-                user_account,
-                &group,
-                &message_id,
-                timestamp,
-                data.encode_to_vec(),
-            );
+            let mut data: Vec<u8> = vec![0; read_size];
+            if let Err(e) = file.read_exact(&mut data) {
+                return Err((file_id, e.to_string()));
+            }
 
-            chunk_index = chunk_index + 1;
+            // the channel blocks, until the event loop schedules further chunks
+            if events
+                .send(FileSendEvent::Chunk {
+                    account_id,
+                    file_id,
+                    index,
+                    data,
+                })
+                .is_err()
+            {
+                return Ok(());
+            }
         }
 
-        // set file status to sent
-        ChatStorage::udate_status(
-            &user_account.id,
-            &message_id,
-            super::rpc_proto::MessageStatus::Sent,
-        );
-
-        Ok(true)
+        Ok(())
     }
 
-    /// Save File Message in Chat Conversation
+    /// Process the events of the file sending workers
     ///
-    /// Creates a chat message and saves it to the chat db.
-    fn save_filemsg_in_chat(
-        user_account: &UserAccount,
+    /// This function is called regularly from the event loop.
+    /// Events are only processed, while the file messages in the
+    /// messaging send queue are below `SEND_QUEUE_LIMIT`.
+    pub fn process_send_events() {
+        let events = &SEND_CHANNEL.get().1;
+
+        for _ in 0..SEND_BATCH_SIZE {
+            if Messaging::get_queue_depth().file >= SEND_QUEUE_LIMIT {
+                return;
+            }
+
+            match events.try_recv() {
+                Ok(FileSendEvent::Prepared {
+                    account_id,
+                    file_history,
+                    hashes,
+                }) => Self::on_send_prepared(account_id, file_history, hashes),
+                Ok(FileSendEvent::Chunk {
+                    account_id,
+                    file_id,
+                    index,
+                    data,
+                }) => Self::on_send_chunk(account_id, file_id, index, data),
+                Ok(FileSendEvent::Failed {
+                    account_id,
+                    file_id,
+                    error,
+                }) => {
+                    log::error!("file {} sending failed: {}", file_id, error);
+                    Self::send_file_response(account_id, false, error, file_id);
+                }
+                Err(_) => return,
+            }
+        }
+    }
+
+    /// A file has been prepared for sending
+    ///
+    /// Sends the file info message to all group members, and
+    /// saves the file to the file history and the chat conversation.
+    fn on_send_prepared(account_id: PeerId, mut file_history: FileHistory, mut hashes: FileHashes) {
+        let user_account = match UserAccounts::get_by_id(account_id) {
+            Some(user_account) => user_account,
+            None => return,
+        };
+        let group = match GroupStorage::get_group(account_id, file_history.group_id.clone()) {
+            Some(group) => group,
+            None => {
+                Self::send_file_response(
+                    account_id,
+                    false,
+                    "Group does not exist".to_string(),
+                    file_history.file_id,
+                );
+                return;
+            }
+        };
+        let groupid = GroupId::from_bytes(&file_history.group_id).unwrap();
+
+        // create message ID
+        file_history.message_id =
+            group::GroupManage::get_new_message_id(&account_id, &file_history.group_id);
+        file_history.sent_at = Timestamp::get_timestamp();
+
+        // 1. file info message
+        let mut file_info = proto_net::ChatFileInfo {
+            file_id: file_history.file_id,
+            file_name: file_history.file_name.clone(),
+            file_extension: file_history.file_extension.clone(),
+            file_size: file_history.file_size,
+            file_description: file_history.file_description.clone(),
+            start_index: file_history.start_index,
+            message_count: file_history.message_count,
+            data_chunk_size: file_history.chunk_size,
+            sender_id: Vec::new(),
+            file_hash: hashes.file_hash.clone(),
+            chunk_hashes: hashes.chunk_hashes.clone(),
+            signature: Vec::new(),
+        };
+
+        // sign the info message, so that other group members
+        // can resend it on our behalf
+        match user_account
+            .keys
+            .sign(&Self::info_signature_data(&file_info))
+        {
+            Ok(signature) => {
+                file_info.signature = signature.clone();
+                hashes.signature = signature;
+            }
+            Err(e) => log::error!("file info signing error: {}", e),
+        }
+
+        let info = proto_net::ChatFileContainer {
+            message: Some(proto_net::chat_file_container::Message::FileInfo(file_info)),
+        };
+
+        // send message to all group members
+        Self::send_filecontainer_to_group(
+            &user_account,
+            &group,
+            &file_history.message_id,
+            file_history.sent_at,
+            info.encode_to_vec(),
+        );
+
+        // save file hashes and file history to data base
+        let db_ref = Self::get_db_ref(&account_id);
+        db_ref.save_hashes(file_history.file_id, &hashes);
+        db_ref.save_filehistory(file_history.file_id, file_history.clone());
+
+        // save file message to chat conversation
+        Self::save_filemsg_in_chat(
+            &user_account,
+            &account_id,
+            &groupid,
+            &file_history,
+            super::rpc_proto::MessageStatus::Sending,
+        );
+
+        Self::send_file_response(account_id, true, "".to_string(), file_history.file_id);
+        Self::send_progress(account_id, &file_history, 0);
+    }
+
+    /// A data chunk of a file has been read
+    ///
+    /// 2. file data message
+    /// The chunk is sent to all group members.
+    fn on_send_chunk(account_id: PeerId, file_id: u64, index: u32, data: Vec<u8>) {
+        let user_account = match UserAccounts::get_by_id(account_id) {
+            Some(user_account) => user_account,
+            None => return,
+        };
+        let user_files = Self::get_db_ref(&account_id);
+        let file_history = match user_files.get_filehistory(file_id) {
+            Some(file_history) => file_history,
+            None => return,
+        };
+        if let FileState::Cancelled = file_history.file_state {
+            return;
+        }
+        let group = match GroupStorage::get_group(account_id, file_history.group_id.clone()) {
+            Some(group) => group,
+            None => return,
+        };
+
+        // pack chat file container
+        let container = proto_net::ChatFileContainer {
+            message: Some(proto_net::chat_file_container::Message::FileData(
+                proto_net::ChatFileData {
+                    file_id,
+                    start_index: index,
+                    message_count: file_history.message_count,
+                    data,
+                },
+            )),
+        };
+
+        // send message to all group members,
+        // that didn't cancel receiving the file
+        let data = container.encode_to_vec();
+        for user_id in group.members.keys() {
+            let receiver = PeerId::from_bytes(&user_id.clone()).unwrap();
+            if receiver == account_id || user_files.is_cancelled(file_id, &receiver) {
+                continue;
+            }
+
+            Self::send_filecontainer_to_user(
+                &user_account,
+                &group.id,
+                &receiver,
+                &file_history.message_id,
+                file_history.sent_at,
+                data.clone(),
+            );
+        }
+
+        // report progress
+        let chunks_done = index + 1;
+        let chunks_total = file_history.message_count - 1;
+        if chunks_done % PROGRESS_INTERVAL == 0 || chunks_done == chunks_total {
+            Self::send_progress(account_id, &file_history, chunks_done);
+        }
+
+        // set file status to sent
+        if chunks_done == chunks_total {
+            ChatStorage::udate_status(
+                &account_id,
+                &file_history.message_id,
+                super::rpc_proto::MessageStatus::Sent,
+            );
+        }
+    }
+
+    /// send the result of a send file request to the UI
+    fn send_file_response(account_id: PeerId, success: bool, error: String, file_id: u64) {
+        let proto_message = proto_rpc::ChatFile {
+            message: Some(proto_rpc::chat_file::Message::SendFileResponse(
+                proto_rpc::SendFileResponse {
+                    success,
+                    error,
+                    file_id,
+                },
+            )),
+        };
+
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            crate::rpc::proto::Modules::Chatfile.into(),
+            "".to_string(),
+            account_id.to_bytes(),
+        );
+    }
+
+    /// send the progress of a file being sent or received to the UI
+    fn send_progress(account_id: PeerId, file_history: &FileHistory, chunks_done: u32) {
+        let bytes_done =
+            (chunks_done as u64 * file_history.chunk_size as u64).min(file_history.file_size);
+
+        // calculate the average transfer rate
+        let incoming = file_history.sender_id != account_id.to_bytes();
+        let started_at = if incoming {
+            file_history.received_at
+        } else {
+            file_history.sent_at
+        };
+        let elapsed = Timestamp::get_timestamp().saturating_sub(started_at).max(1);
+        let rate = bytes_done * 1000 / elapsed;
+
+        let proto_message = proto_rpc::ChatFile {
+            message: Some(proto_rpc::chat_file::Message::FileProgress(
+                proto_rpc::FileProgress {
+                    file_id: file_history.file_id,
+                    group_id: file_history.group_id.clone(),
+                    chunks_done,
+                    chunks_total: file_history.message_count - 1,
+                    bytes_done,
+                    file_size: file_history.file_size,
+                    incoming,
+                    rate,
+                },
+            )),
+        };
+
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            crate::rpc::proto::Modules::Chatfile.into(),
+            "".to_string(),
+            account_id.to_bytes(),
+        );
+    }
+
+    /// Save File Message in Chat Conversation
+    ///
+    /// Creates a chat message and saves it to the chat db.
+    fn save_filemsg_in_chat(
+        user_account: &UserAccount,
         sender_id: &PeerId,
         group_id: &GroupId,
         file_history: &FileHistory,
@@ -704,11 +1472,38 @@ impl ChatFile {
         message_id: &Vec<u8>,
         timestamp: u64,
         data: Vec<u8>,
+    ) {
+        // send to all members
+        for user_id in group.members.keys() {
+            let receiver = PeerId::from_bytes(&user_id.clone()).unwrap();
+            if receiver == user_account.id {
+                continue;
+            }
+
+            Self::send_filecontainer_to_user(
+                user_account,
+                &group.id,
+                &receiver,
+                message_id,
+                timestamp,
+                data.clone(),
+            );
+        }
+    }
+
+    /// Pack a FileContainer message and send it to a single user
+    fn send_filecontainer_to_user(
+        user_account: &UserAccount,
+        group_id: &Vec<u8>,
+        receiver: &PeerId,
+        message_id: &Vec<u8>,
+        timestamp: u64,
+        data: Vec<u8>,
     ) {
         // pack file container into common message
         let common_message = messaging::proto::CommonMessage {
             message_id: message_id.clone(),
-            group_id: group.id.clone(),
+            group_id: group_id.clone(),
             sent_at: timestamp,
             payload: Some(messaging::proto::common_message::Payload::FileMessage(
                 messaging::proto::FileMessage { content: data },
@@ -717,32 +1512,74 @@ impl ChatFile {
 
         let message = messaging::proto::Messaging {
             message: Some(messaging::proto::messaging::Message::CommonMessage(
-                common_message.clone(),
+                common_message,
             )),
         };
 
-        // send to all members
-        for user_id in group.members.keys() {
-            let receiver = PeerId::from_bytes(&user_id.clone()).unwrap();
-            if receiver == user_account.id {
-                continue;
+        if let Err(error) = Messaging::pack_and_send_message(
+            user_account,
+            receiver,
+            message.encode_to_vec(),
+            MessagingServiceType::ChatFile,
+            message_id,
+            true,
+        ) {
+            log::error!("sending file message error {}", error);
+        }
+    }
+
+    /// Hash the content of a file
+    ///
+    /// Returns the SHA-256 hash of the file and the
+    /// hashes of all data chunks.
+    /// The file is read from the beginning and rewound afterwards.
+    fn hash_file(file: &mut File) -> Result<(Vec<u8>, Vec<Vec<u8>>), String> {
+        let mut file_hasher = Sha256::new();
+        let mut chunk_hashes: Vec<Vec<u8>> = Vec::new();
+        let mut buffer: Vec<u8> = vec![0; DEF_PACKAGE_SIZE as usize];
+
+        if let Err(e) = file.seek(SeekFrom::Start(0)) {
+            return Err(e.to_string());
+        }
+        loop {
+            // fill the buffer with the next chunk
+            let mut read_size = 0;
+            while read_size < buffer.len() {
+                match file.read(&mut buffer[read_size..]) {
+                    Ok(0) => break,
+                    Ok(n) => read_size += n,
+                    Err(e) => return Err(e.to_string()),
+                }
+            }
+            if read_size == 0 {
+                break;
             }
 
-            if let Err(error) = Messaging::pack_and_send_message(
-                user_account,
-                &receiver,
-                message.encode_to_vec(),
-                MessagingServiceType::ChatFile,
-                message_id,
-                true,
-            ) {
-                log::error!("sending file message error {}", error);
+            file_hasher.update(&buffer[..read_size]);
+            chunk_hashes.push(Sha256::digest(&buffer[..read_size]).to_vec());
+
+            if read_size < buffer.len() {
+                break;
             }
         }
+        if let Err(e) = file.seek(SeekFrom::Start(0)) {
+            return Err(e.to_string());
+        }
+
+        Ok((file_hasher.finalize().to_vec(), chunk_hashes))
     }
 
     /// Generate File id
-    fn generate_file_id(group_id: &Vec<u8>, sender: &Vec<u8>, file_name: String, size: u32) -> u64 {
+    ///
+    /// The file id is derived from the file content hash,
+    /// the group, the sender, the file name and the sending time.
+    fn generate_file_id(
+        group_id: &Vec<u8>,
+        sender: &Vec<u8>,
+        file_name: String,
+        size: u64,
+        file_hash: &Vec<u8>,
+    ) -> u64 {
         let mut name_bytes = file_name.as_bytes().to_vec();
         let mut size_bytes = size.to_be_bytes().to_vec();
         let mut time_bytes = timestamp::Timestamp::get_timestamp().to_be_bytes().to_vec();
@@ -753,6 +1590,7 @@ impl ChatFile {
         key_bytes.append(&mut name_bytes);
         key_bytes.append(&mut size_bytes);
         key_bytes.append(&mut time_bytes);
+        key_bytes.extend_from_slice(file_hash);
         crc::Crc::<u64>::new(&crc::CRC_64_GO_ISO).checksum(&key_bytes)
     }
 
@@ -783,7 +1621,14 @@ impl ChatFile {
     }
 
     /// Store a completely downloaded file
-    fn store_file(user_account: &UserAccount, user_files: UserFiles, file_history: FileHistory) {
+    ///
+    /// The file chunks and the transfer state are
+    /// removed from the data base afterwards.
+    fn store_file(
+        user_account: &UserAccount,
+        user_files: UserFiles,
+        mut file_history: FileHistory,
+    ) {
         // get all chunks from data base
         let iterator = user_files.get_file_chunks(&file_history.file_id.to_be_bytes().to_vec());
 
@@ -805,10 +1650,12 @@ impl ChatFile {
         }
 
         // loop over all chunks and write them to the data base
+        let mut file_hasher = Sha256::new();
         for result in iterator {
             match result {
                 Ok((_key, chunk)) => {
                     // write chunk to file
+                    file_hasher.update(&chunk);
                     if let Err(e) = file.write(&chunk) {
                         log::error!("file storing failed {}", e.to_string());
                     }
@@ -817,58 +1664,251 @@ impl ChatFile {
             }
         }
 
+        // verify file content
+        if let Some(mut hashes) = user_files.get_hashes(file_history.file_id) {
+            if !hashes.file_hash.is_empty() {
+                if file_hasher.finalize().as_slice() != hashes.file_hash.as_slice() {
+                    // the file is received again
+                    log::error!(
+                        "file {} doesn't match its hash, receiving it again",
+                        file_history.file_id
+                    );
+                    drop(file);
+                    if let Err(e) = fs::remove_file(file_path) {
+                        log::error!("removing file failed {}", e);
+                    }
+                    user_files.remove_file_chunks(file_history.file_id);
+                    return;
+                }
+
+                hashes.verified = true;
+                user_files.save_hashes(file_history.file_id, &hashes);
+            }
+        }
+
         // set file status to received
+        file_history.file_state = FileState::Received;
+        user_files.save_filehistory(file_history.file_id, file_history.clone());
+        user_files.remove_transfer(file_history.file_id);
+        user_files.remove_file_chunks(file_history.file_id);
+        user_files.remove_sources(file_history.file_id);
+
         ChatStorage::udate_status(
             &user_account.id,
             &file_history.message_id,
             super::rpc_proto::MessageStatus::Received,
         );
+
+        // advertise the file to the group
+        Self::send_available(user_account, &file_history);
+    }
+
+    /// advertise a complete file to all group members
+    ///
+    /// The sender of the file is not informed.
+    fn send_available(user_account: &UserAccount, file_history: &FileHistory) {
+        let group = match GroupStorage::get_group(user_account.id, file_history.group_id.clone()) {
+            Some(group) => group,
+            None => return,
+        };
+
+        let available = proto_net::ChatFileContainer {
+            message: Some(proto_net::chat_file_container::Message::FileAvailable(
+                proto_net::ChatFileAvailable {
+                    file_id: file_history.file_id,
+                },
+            )),
+        };
+        let data = available.encode_to_vec();
+
+        for user_id in group.members.keys() {
+            if *user_id == user_account.id.to_bytes() || *user_id == file_history.sender_id {
+                continue;
+            }
+            if let Ok(receiver) = PeerId::from_bytes(user_id) {
+                Self::send_filecontainer_to_user(
+                    user_account,
+                    &group.id,
+                    &receiver,
+                    &file_history.message_id,
+                    Timestamp::get_timestamp(),
+                    data.clone(),
+                );
+            }
+        }
+    }
+
+    /// process chat file available message
+    ///
+    /// The advertising group member is saved as a source of
+    /// the file, if we are receiving it.
+    /// Files we don't know yet are requested.
+    /// Advertisements of users that are not active members
+    /// of the group are ignored.
+    fn process_available_message(
+        user_account: &UserAccount,
+        sender_id: PeerId,
+        group_id: Vec<u8>,
+        message_id: Vec<u8>,
+        file_available: proto_net::ChatFileAvailable,
+    ) {
+        let user_files = Self::get_db_ref(&user_account.id);
+        let file_id = file_available.file_id;
+
+        // check if the advertiser is an active group member
+        let active_member = match GroupStorage::get_group(user_account.id, group_id.clone()) {
+            Some(group) => match group.get_member(&sender_id.to_bytes()) {
+                Some(member) => member.state == GroupMemberState::Activated as i32,
+                None => false,
+            },
+            None => false,
+        };
+        if !active_member {
+            log::warn!(
+                "file {} advertised by non member {}",
+                file_id,
+                sender_id.to_base58()
+            );
+            return;
+        }
+
+        if let Some(file_history) = user_files.get_filehistory(file_id) {
+            if file_history.group_id != group_id {
+                return;
+            }
+            match file_history.file_state {
+                FileState::Receiving | FileState::Pending => {}
+                _ => return,
+            }
+        } else if user_files.get_transfer(file_id).is_none() {
+            // we missed the file, request the file info message
+            log::debug!(
+                "unknown file {} advertised by {}",
+                file_id,
+                sender_id.to_base58()
+            );
+            user_files.save_transfer(
+                file_id,
+                &FileTransfer {
+                    group_id,
+                    sender_id: sender_id.to_bytes(),
+                    message_id,
+                    message_count: 0,
+                    has_info: false,
+                    last_received: 0,
+                    last_request: 0,
+                    request_count: 0,
+                },
+            );
+        }
+
+        user_files.save_source(file_id, &sender_id);
     }
 
     /// process chat file data message
     fn process_data_message(
         user_account: &UserAccount,
-        _sender_id: PeerId,
-        _group_id: Vec<u8>,
+        sender_id: PeerId,
+        group_id: Vec<u8>,
+        message_id: Vec<u8>,
         file_data: proto_net::ChatFileData,
     ) {
         // get DB references
         let user_files = Self::get_db_ref(&user_account.id);
 
+        // check if we already received the file info
+        let file_history_option = user_files.get_filehistory(file_data.file_id);
+
+        // ignore chunks of files we already have
+        if let Some(file_history) = &file_history_option {
+            match file_history.file_state {
+                FileState::Receiving => {}
+                _ => return,
+            }
+        }
+
+        // check chunk index
+        if file_data.message_count == 0 || file_data.start_index >= file_data.message_count - 1 {
+            log::error!("invalid file chunk index {}", file_data.start_index);
+            return;
+        }
+
+        // verify chunk
+        let mut valid = true;
+        if let Some(file_history) = &file_history_option {
+            match user_files.get_hashes(file_data.file_id) {
+                Some(hashes) => {
+                    valid = hashes.verify_chunk(file_data.start_index, &file_data.data);
+                }
+                None => {
+                    // chunks of files without hashes can't be
+                    // verified and are only accepted from the sender
+                    if file_history.sender_id != sender_id.to_bytes() {
+                        log::warn!(
+                            "unverifiable chunk of file {} from {}",
+                            file_data.file_id,
+                            sender_id.to_base58()
+                        );
+                        return;
+                    }
+                }
+            }
+        }
+
+        // update transfer state
+        let transfer = match user_files.get_transfer(file_data.file_id) {
+            Some(mut transfer) if !valid => {
+                // request the invalid chunk again on the next check
+                transfer.last_received = 0;
+                transfer.last_request = 0;
+                transfer
+            }
+            Some(mut transfer) => {
+                transfer.last_received = Timestamp::get_timestamp();
+                transfer
+            }
+            None => FileTransfer {
+                group_id,
+                sender_id: sender_id.to_bytes(),
+                message_id,
+                message_count: file_data.message_count,
+                has_info: false,
+                last_received: Timestamp::get_timestamp(),
+                last_request: 0,
+                request_count: 0,
+            },
+        };
+        user_files.save_transfer(file_data.file_id, &transfer);
+
+        if !valid {
+            log::warn!(
+                "invalid chunk {} of file {} from {}",
+                file_data.start_index,
+                file_data.file_id,
+                sender_id.to_base58()
+            );
+            return;
+        }
+
         // save file chunk in DB
         user_files.save_file_chunk(file_data.file_id, file_data.start_index, file_data.data);
 
-        // check if we already received the file info
-        match user_files.get_filehistory(file_data.file_id) {
+        match file_history_option {
             Some(file_history) => {
+                // report progress
+                let chunks_done =
+                    user_files.count_file_chunks(&file_data.file_id.to_be_bytes().to_vec()) as u32;
+                if chunks_done % PROGRESS_INTERVAL == 0
+                    || chunks_done + 1 == file_history.message_count
+                {
+                    Self::send_progress(user_account.id, &file_history, chunks_done);
+                }
+
                 // create file once everything has been received
                 Self::try_store_file(user_account, user_files, file_history);
             }
             None => {
-                log::warn!("haven't received file info message yet");
-                // TODO: create stub file history file
-                /*
-                // create a file history stub if nothing was found
-                let file_history = FileHistory {
-                    group_id: todo!(),
-                    sender_id: todo!(),
-                    file_id: todo!(),
-                    message_id: todo!(),
-                    start_index: todo!(),
-                    message_count: todo!(),
-                    chunk_size: todo!(),
-                    file_state: todo!(),
-                    reception_tracking: todo!(),
-                    file_name: todo!(),
-                    file_description: todo!(),
-                    file_extension: todo!(),
-                    file_size: todo!(),
-                    sent_at: todo!(),
-                    received_at: todo!(),
-                };
-
-                user_files.save_filehistory(file_id, file_history);
-                 */
+                log::trace!("chunk saved until the file info message arrives");
             }
         }
     }
@@ -885,12 +1925,57 @@ impl ChatFile {
         // get db
         let user_files = Self::get_db_ref(&user_account.id);
 
+        // the info message of a file can be resent by
+        // another group member on our request.
+        // The resent message is only accepted, if it is signed
+        // by the original sender.
+        let mut sender_id = sender_id;
+        let mut relay_id: Option<PeerId> = None;
+        if !file_info.sender_id.is_empty() {
+            let original_sender = match PeerId::from_bytes(&file_info.sender_id) {
+                Ok(original_sender) => original_sender,
+                Err(e) => {
+                    log::error!("invalid file sender id: {}", e);
+                    return;
+                }
+            };
+
+            let are_members = match GroupStorage::get_group(user_account.id, group_id.clone()) {
+                Some(group) => group.are_members(&sender_id.to_bytes(), &file_info.sender_id),
+                None => false,
+            };
+            let signed = match Users::get_pub_key(&original_sender) {
+                Some(key) => {
+                    !file_info.signature.is_empty()
+                        && key.verify(&Self::info_signature_data(&file_info), &file_info.signature)
+                }
+                None => false,
+            };
+
+            if !are_members || !signed {
+                log::warn!(
+                    "file {} sender {} relayed by {} not accepted",
+                    file_info.file_id,
+                    original_sender.to_base58(),
+                    sender_id.to_base58()
+                );
+                return;
+            }
+
+            relay_id = Some(sender_id);
+            sender_id = original_sender;
+        }
+
         // check if it already exists in DB
         let file_history;
         if let Some(my_file_history) = user_files.get_filehistory(file_info.file_id) {
             file_history = my_file_history;
 
-            // update fields
+            // the file has already been received
+            match file_history.file_state {
+                FileState::Receiving => {}
+                _ => return,
+            }
         } else {
             file_history = FileHistory {
                 group_id: group_id.clone(),
@@ -900,7 +1985,12 @@ impl ChatFile {
                 start_index: file_info.start_index,
                 message_count: file_info.message_count,
                 chunk_size: file_info.data_chunk_size,
-                file_state: FileState::Receiving,
+                file_state: Self::receive_policy(
+                    user_account,
+                    &sender_id,
+                    relay_id.as_ref(),
+                    file_info.file_size,
+                ),
                 reception_tracking: BTreeMap::new(),
                 file_name: file_info.file_name.clone(),
                 file_description: file_info.file_description.clone(),
@@ -911,9 +2001,6 @@ impl ChatFile {
             };
         }
 
-        // save to file history
-        user_files.save_filehistory(file_info.file_id, file_history.clone());
-
         // create group id
         let groupid;
         match GroupId::from_bytes(&group_id) {
@@ -924,19 +2011,745 @@ impl ChatFile {
             }
         }
 
-        // save message to chat
-        Self::save_filemsg_in_chat(
-            user_account,
-            &sender_id,
-            &groupid,
-            &file_history,
-            super::rpc_proto::MessageStatus::Receiving,
-        );
-
+        // save to file history
+        user_files.save_filehistory(file_info.file_id, file_history.clone());
+
+        // the hashes are only taken from the info message of the
+        // sender of the file
+        let hashes_from_sender = file_history.sender_id == sender_id.to_bytes();
+
+        match file_history.file_state {
+            FileState::Receiving => {}
+            FileState::Pending => {
+                log::debug!("file {} waits for manual acceptance", file_info.file_id);
+
+                // the chunks are requested, once the file is accepted
+                user_files.remove_transfer(file_info.file_id);
+                user_files.remove_file_chunks(file_info.file_id);
+                if hashes_from_sender && !file_info.file_hash.is_empty() {
+                    user_files.save_hashes(
+                        file_info.file_id,
+                        &FileHashes {
+                            file_hash: file_info.file_hash,
+                            chunk_hashes: file_info.chunk_hashes,
+                            verified: false,
+                            signature: file_info.signature,
+                        },
+                    );
+                }
+
+                Self::save_filemsg_in_chat(
+                    user_account,
+                    &sender_id,
+                    &groupid,
+                    &file_history,
+                    super::rpc_proto::MessageStatus::Pending,
+                );
+                return;
+            }
+            _ => {
+                log::info!(
+                    "file {} of untrusted user {} rejected",
+                    file_info.file_id,
+                    sender_id.to_base58()
+                );
+
+                user_files.remove_transfer(file_info.file_id);
+                user_files.remove_file_chunks(file_info.file_id);
+                Self::send_cancel(
+                    user_account,
+                    &group_id,
+                    &sender_id,
+                    &file_history.message_id,
+                    file_info.file_id,
+                );
+                return;
+            }
+        }
+
+        // save file hashes and verify the chunks received
+        // before the info message
+        let mut invalid_chunks = 0;
+        if hashes_from_sender
+            && !file_info.file_hash.is_empty()
+            && user_files.get_hashes(file_info.file_id).is_none()
+        {
+            let hashes = FileHashes {
+                file_hash: file_info.file_hash,
+                chunk_hashes: file_info.chunk_hashes,
+                verified: false,
+                signature: file_info.signature,
+            };
+            user_files.save_hashes(file_info.file_id, &hashes);
+            invalid_chunks = Self::remove_invalid_chunks(&user_files, file_info.file_id, &hashes);
+        }
+
+        // update transfer state
+        let mut transfer = user_files
+            .get_transfer(file_info.file_id)
+            .unwrap_or(FileTransfer {
+                group_id: group_id.clone(),
+                sender_id: sender_id.to_bytes(),
+                message_id: message_id.clone(),
+                message_count: file_history.message_count,
+                has_info: true,
+                last_received: 0,
+                last_request: 0,
+                request_count: 0,
+            });
+        transfer.sender_id = sender_id.to_bytes();
+        transfer.message_id = file_history.message_id.clone();
+        transfer.message_count = file_history.message_count;
+        transfer.has_info = true;
+        transfer.last_received = Timestamp::get_timestamp();
+        if invalid_chunks > 0 {
+            // request the invalid chunks again on the next check
+            transfer.last_received = 0;
+            transfer.last_request = 0;
+        }
+        user_files.save_transfer(file_info.file_id, &transfer);
+
+        // save message to chat
+        Self::save_filemsg_in_chat(
+            user_account,
+            &sender_id,
+            &groupid,
+            &file_history,
+            super::rpc_proto::MessageStatus::Receiving,
+        );
+
         // create file once everything has been received
         Self::try_store_file(user_account, user_files, file_history);
     }
 
+    /// decide how an incoming file is received
+    ///
+    /// Returns the state of the new file according to the
+    /// file receive policy of the user account.
+    /// A relayed file needs to pass the policy for the
+    /// original sender and for the relaying group member.
+    fn receive_policy(
+        user_account: &UserAccount,
+        sender_id: &PeerId,
+        relay_id: Option<&PeerId>,
+        file_size: u64,
+    ) -> FileState {
+        let policy = match Configuration::get_user(user_account.id.to_string()) {
+            Some(user) => user.file_policy,
+            None => FilePolicy::default(),
+        };
+
+        if policy.trusted_only {
+            if !Users::is_verified(sender_id) {
+                return FileState::Cancelled;
+            }
+            if let Some(relay_id) = relay_id {
+                if !Users::is_verified(relay_id) {
+                    return FileState::Cancelled;
+                }
+            }
+        }
+        if policy.manual_accept
+            || (policy.max_auto_accept_size > 0 && file_size > policy.max_auto_accept_size)
+        {
+            return FileState::Pending;
+        }
+
+        FileState::Receiving
+    }
+
+    /// data of a file info message, that is signed by the sender
+    ///
+    /// The message is signed without the fields, that are
+    /// set when resending it.
+    fn info_signature_data(file_info: &proto_net::ChatFileInfo) -> Vec<u8> {
+        let mut unsigned = file_info.clone();
+        unsigned.sender_id = Vec::new();
+        unsigned.signature = Vec::new();
+        unsigned.encode_to_vec()
+    }
+
+    /// remove the saved chunks of a file, that don't match their hash
+    ///
+    /// Returns the number of removed chunks.
+    fn remove_invalid_chunks(user_files: &UserFiles, file_id: u64, hashes: &FileHashes) -> usize {
+        let mut invalid: Vec<sled::IVec> = Vec::new();
+        for result in user_files.get_file_chunks(&file_id.to_be_bytes().to_vec()) {
+            if let Ok((key, chunk)) = result {
+                if let Ok(index_bytes) = key[key.len() - 4..].try_into() {
+                    if !hashes.verify_chunk(u32::from_be_bytes(index_bytes), &chunk) {
+                        invalid.push(key);
+                    }
+                }
+            }
+        }
+
+        for key in invalid.iter() {
+            log::warn!("invalid chunk of file {} removed", file_id);
+            if let Err(e) = user_files.file_chunks.remove(key) {
+                log::error!("Error removing file chunk: {}", e);
+            }
+        }
+
+        invalid.len()
+    }
+
+    /// process chat file request message
+    ///
+    /// The requested parts of the file are sent to the requester,
+    /// if we have the complete file.
+    fn process_request_message(
+        user_account: &UserAccount,
+        requester_id: PeerId,
+        group_id: Vec<u8>,
+        file_request: proto_net::ChatFileRequest,
+    ) {
+        let user_files = Self::get_db_ref(&user_account.id);
+
+        // check if we have the complete file
+        let file_history = match user_files.get_filehistory(file_request.file_id) {
+            Some(file_history) => file_history,
+            None => return,
+        };
+        if file_history.group_id != group_id {
+            log::warn!("file request from {} for wrong group", requester_id);
+            return;
+        }
+        match GroupStorage::get_group(user_account.id, group_id.clone()) {
+            Some(group) if group.is_member(&requester_id.to_bytes()) => {}
+            _ => {
+                log::warn!("file request from non member {}", requester_id);
+                return;
+            }
+        }
+        match file_history.file_state {
+            FileState::Receiving | FileState::Pending | FileState::Cancelled => return,
+            _ => {}
+        }
+        if user_files.is_cancelled(file_request.file_id, &requester_id) {
+            return;
+        }
+
+        log::trace!(
+            "file {} request from {}: {} chunks",
+            file_request.file_id,
+            requester_id.to_base58(),
+            file_request.missing_chunks.len()
+        );
+
+        // resend file info message
+        if file_request.info {
+            let mut sender_id = Vec::new();
+            if file_history.sender_id != user_account.id.to_bytes() {
+                sender_id = file_history.sender_id.clone();
+            }
+            let hashes = user_files.get_hashes(file_history.file_id);
+            let info = proto_net::ChatFileContainer {
+                message: Some(proto_net::chat_file_container::Message::FileInfo(
+                    proto_net::ChatFileInfo {
+                        file_id: file_history.file_id,
+                        file_name: file_history.file_name.clone(),
+                        file_extension: file_history.file_extension.clone(),
+                        file_size: file_history.file_size,
+                        file_description: file_history.file_description.clone(),
+                        start_index: file_history.start_index,
+                        message_count: file_history.message_count,
+                        data_chunk_size: file_history.chunk_size,
+                        sender_id,
+                        file_hash: hashes
+                            .as_ref()
+                            .map(|hashes| hashes.file_hash.clone())
+                            .unwrap_or_default(),
+                        chunk_hashes: hashes
+                            .as_ref()
+                            .map(|hashes| hashes.chunk_hashes.clone())
+                            .unwrap_or_default(),
+                        signature: hashes.map(|hashes| hashes.signature).unwrap_or_default(),
+                    },
+                )),
+            };
+            Self::send_filecontainer_to_user(
+                user_account,
+                &group_id,
+                &requester_id,
+                &file_history.message_id,
+                file_history.sent_at,
+                info.encode_to_vec(),
+            );
+        }
+
+        if file_request.missing_chunks.is_empty() {
+            return;
+        }
+
+        // open file
+        let file_path = Self::create_file_path(
+            user_account.id,
+            file_history.file_id,
+            &file_history.file_extension,
+        );
+        let mut file = match File::open(file_path) {
+            Ok(file) => file,
+            Err(e) => {
+                log::error!("file open error {}", e);
+                return;
+            }
+        };
+
+        // resend requested chunks
+        let chunk_count = file_history.message_count.saturating_sub(1);
+        let mut buffer: Vec<u8> = vec![0; file_history.chunk_size as usize];
+        for index in file_request
+            .missing_chunks
+            .into_iter()
+            .filter(|index| *index < chunk_count)
+            .take(MAX_REQUEST_CHUNKS)
+        {
+            let offset = index as u64 * file_history.chunk_size as u64;
+            let read_size = file_history
+                .file_size
+                .saturating_sub(offset)
+                .min(file_history.chunk_size as u64) as usize;
+
+            if let Err(e) = file.seek(SeekFrom::Start(offset)) {
+                log::error!("file seek error {}", e);
+                return;
+            }
+            if let Err(e) = file.read_exact(&mut buffer[..read_size]) {
+                log::error!("file read error {}", e);
+                return;
+            }
+
+            let data = proto_net::ChatFileContainer {
+                message: Some(proto_net::chat_file_container::Message::FileData(
+                    proto_net::ChatFileData {
+                        file_id: file_history.file_id,
+                        start_index: index,
+                        message_count: file_history.message_count,
+                        data: buffer[..read_size].to_vec(),
+                    },
+                )),
+            };
+            Self::send_filecontainer_to_user(
+                user_account,
+                &group_id,
+                &requester_id,
+                &file_history.message_id,
+                file_history.sent_at,
+                data.encode_to_vec(),
+            );
+        }
+    }
+
+    /// Cancel sending or receiving a file
+    ///
+    /// When sending, all group members are informed.
+    /// When receiving, the sender of the file is informed.
+    fn cancel(user_account: &UserAccount, file_id: u64) -> Result<(), String> {
+        let user_files = Self::get_db_ref(&user_account.id);
+        let mut file_history = match user_files.get_filehistory(file_id) {
+            Some(file_history) => file_history,
+            None => return Err("file not found".to_string()),
+        };
+        match file_history.file_state {
+            FileState::Received | FileState::ConfirmedByAll | FileState::Cancelled => {
+                return Err("file transfer already finished".to_string())
+            }
+            _ => {}
+        }
+
+        file_history.file_state = FileState::Cancelled;
+        user_files.save_filehistory(file_id, file_history.clone());
+
+        if file_history.sender_id == user_account.id.to_bytes() {
+            // inform all group members
+            if let Some(group) =
+                GroupStorage::get_group(user_account.id, file_history.group_id.clone())
+            {
+                let cancel = proto_net::ChatFileContainer {
+                    message: Some(proto_net::chat_file_container::Message::FileCancel(
+                        proto_net::ChatFileCancel { file_id },
+                    )),
+                };
+                Self::send_filecontainer_to_group(
+                    user_account,
+                    &group,
+                    &file_history.message_id,
+                    Timestamp::get_timestamp(),
+                    cancel.encode_to_vec(),
+                );
+            }
+        } else {
+            // inform the sender of the file
+            user_files.remove_transfer(file_id);
+            user_files.remove_file_chunks(file_id);
+            user_files.remove_sources(file_id);
+            if let Ok(sender_id) = PeerId::from_bytes(&file_history.sender_id) {
+                Self::send_cancel(
+                    user_account,
+                    &file_history.group_id,
+                    &sender_id,
+                    &file_history.message_id,
+                    file_id,
+                );
+            }
+        }
+
+        ChatStorage::udate_status(
+            &user_account.id,
+            &file_history.message_id,
+            super::rpc_proto::MessageStatus::Cancelled,
+        );
+
+        Ok(())
+    }
+
+    /// Accept a file waiting for manual acceptance
+    ///
+    /// The chunks of the file are requested from the
+    /// sender on the next transfer check.
+    fn accept(user_account: &UserAccount, file_id: u64) -> Result<(), String> {
+        let user_files = Self::get_db_ref(&user_account.id);
+        let mut file_history = match user_files.get_filehistory(file_id) {
+            Some(file_history) => file_history,
+            None => return Err("file not found".to_string()),
+        };
+        match file_history.file_state {
+            FileState::Pending => {}
+            _ => return Err("file is not waiting for acceptance".to_string()),
+        }
+
+        file_history.file_state = FileState::Receiving;
+        file_history.received_at = Timestamp::get_timestamp();
+        user_files.save_filehistory(file_id, file_history.clone());
+
+        user_files.save_transfer(
+            file_id,
+            &FileTransfer {
+                group_id: file_history.group_id.clone(),
+                sender_id: file_history.sender_id.clone(),
+                message_id: file_history.message_id.clone(),
+                message_count: file_history.message_count,
+                has_info: true,
+                last_received: 0,
+                last_request: 0,
+                request_count: 0,
+            },
+        );
+
+        ChatStorage::udate_status(
+            &user_account.id,
+            &file_history.message_id,
+            super::rpc_proto::MessageStatus::Receiving,
+        );
+
+        Ok(())
+    }
+
+    /// send a file cancel message to a single user
+    fn send_cancel(
+        user_account: &UserAccount,
+        group_id: &Vec<u8>,
+        receiver: &PeerId,
+        message_id: &Vec<u8>,
+        file_id: u64,
+    ) {
+        let cancel = proto_net::ChatFileContainer {
+            message: Some(proto_net::chat_file_container::Message::FileCancel(
+                proto_net::ChatFileCancel { file_id },
+            )),
+        };
+        Self::send_filecontainer_to_user(
+            user_account,
+            group_id,
+            receiver,
+            message_id,
+            Timestamp::get_timestamp(),
+            cancel.encode_to_vec(),
+        );
+    }
+
+    /// process chat file cancel message
+    ///
+    /// The sender of a file cancelled sending it,
+    /// or a receiver cancelled receiving our file.
+    fn process_cancel_message(
+        user_account: &UserAccount,
+        sender_id: PeerId,
+        file_cancel: proto_net::ChatFileCancel,
+    ) {
+        let user_files = Self::get_db_ref(&user_account.id);
+        let file_id = file_cancel.file_id;
+
+        let mut file_history = match user_files.get_filehistory(file_id) {
+            Some(file_history) => file_history,
+            None => {
+                // remove the chunks received before the info message
+                if let Some(transfer) = user_files.get_transfer(file_id) {
+                    if transfer.sender_id == sender_id.to_bytes() {
+                        user_files.remove_transfer(file_id);
+                        user_files.remove_file_chunks(file_id);
+                    }
+                }
+                return;
+            }
+        };
+
+        // a receiver cancelled receiving our file
+        if file_history.sender_id == user_account.id.to_bytes() {
+            log::debug!(
+                "{} cancelled receiving file {}",
+                sender_id.to_base58(),
+                file_id
+            );
+            user_files.save_cancel(file_id, &sender_id);
+            return;
+        }
+
+        // only the sender of the file can cancel sending it
+        if file_history.sender_id != sender_id.to_bytes() {
+            return;
+        }
+        match file_history.file_state {
+            FileState::Receiving | FileState::Pending => {}
+            _ => return,
+        }
+
+        log::debug!(
+            "{} cancelled sending file {}",
+            sender_id.to_base58(),
+            file_id
+        );
+
+        file_history.file_state = FileState::Cancelled;
+        user_files.save_filehistory(file_id, file_history.clone());
+        user_files.remove_transfer(file_id);
+        user_files.remove_file_chunks(file_id);
+        user_files.remove_sources(file_id);
+
+        ChatStorage::udate_status(
+            &user_account.id,
+            &file_history.message_id,
+            super::rpc_proto::MessageStatus::Cancelled,
+        );
+    }
+
+    /// send the file receive policy of a user account to the UI
+    fn send_policy(account_id: PeerId) {
+        let policy = match Configuration::get_user(account_id.to_string()) {
+            Some(user) => user.file_policy,
+            None => FilePolicy::default(),
+        };
+
+        let proto_message = proto_rpc::ChatFile {
+            message: Some(proto_rpc::chat_file::Message::FilePolicy(
+                proto_rpc::FilePolicy {
+                    max_auto_accept_size: policy.max_auto_accept_size,
+                    trusted_only: policy.trusted_only,
+                    manual_accept: policy.manual_accept,
+                },
+            )),
+        };
+
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            crate::rpc::proto::Modules::Chatfile.into(),
+            "".to_string(),
+            account_id.to_bytes(),
+        );
+    }
+
+    /// Request the missing chunks of stalled file transfers
+    ///
+    /// This function is called regularly from the event loop.
+    pub fn check_transfers() {
+        let now = Timestamp::get_timestamp();
+
+        for user in UserAccounts::get_user_info() {
+            let user_account = match UserAccounts::get_by_id(user.id) {
+                Some(user_account) => user_account,
+                None => continue,
+            };
+            let user_files = Self::get_db_ref(&user_account.id);
+
+            for result in user_files.transfers.iter() {
+                let (key, transfer_bytes) = match result {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        log::error!("{}", e);
+                        continue;
+                    }
+                };
+                let file_id = match key.as_ref().try_into() {
+                    Ok(key_bytes) => u64::from_be_bytes(key_bytes),
+                    Err(_) => continue,
+                };
+                let mut transfer: FileTransfer = match bincode::deserialize(&transfer_bytes) {
+                    Ok(transfer) => transfer,
+                    Err(_) => continue,
+                };
+
+                // check if the transfer stalled, or if the
+                // answers to the last request arrived
+                let answered =
+                    transfer.last_request > 0 && transfer.last_received > transfer.last_request;
+                let stalled = now.saturating_sub(transfer.last_received.max(transfer.last_request))
+                    >= TRANSFER_REQUEST_TIMEOUT;
+                let next_round =
+                    answered && now.saturating_sub(transfer.last_received) >= REQUEST_ROUND_TIMEOUT;
+                if !stalled && !next_round {
+                    continue;
+                }
+                if answered {
+                    transfer.request_count = 0;
+                }
+
+                let sources = Self::request_sources(&user_account, &user_files, file_id, &transfer);
+                let missing_chunks = user_files.get_missing_chunks(
+                    file_id,
+                    transfer.message_count.saturating_sub(1),
+                    MAX_REQUEST_CHUNKS * sources.len().max(1),
+                );
+
+                // a complete file is stored once the info message arrives
+                if transfer.has_info && missing_chunks.is_empty() {
+                    if let Some(file_history) = user_files.get_filehistory(file_id) {
+                        Self::try_store_file(&user_account, user_files.clone(), file_history);
+                    }
+                    continue;
+                }
+
+                // request a different range of the missing chunks from each source
+                if !sources.is_empty() {
+                    let range_size = ((missing_chunks.len() + sources.len() - 1) / sources.len())
+                        .clamp(1, MAX_REQUEST_CHUNKS);
+                    let mut ranges = missing_chunks.chunks(range_size);
+
+                    for (i, source) in sources.iter().enumerate() {
+                        let range = ranges
+                            .next()
+                            .map(|range| range.to_vec())
+                            .unwrap_or_default();
+                        let info = i == 0 && !transfer.has_info;
+                        if range.is_empty() && !info {
+                            break;
+                        }
+
+                        log::debug!(
+                            "request {} missing chunks of file {} from {}",
+                            range.len(),
+                            file_id,
+                            source.to_base58()
+                        );
+
+                        let request = proto_net::ChatFileContainer {
+                            message: Some(proto_net::chat_file_container::Message::FileRequest(
+                                proto_net::ChatFileRequest {
+                                    file_id,
+                                    info,
+                                    missing_chunks: range,
+                                },
+                            )),
+                        };
+                        Self::send_filecontainer_to_user(
+                            &user_account,
+                            &transfer.group_id,
+                            source,
+                            &transfer.message_id,
+                            now,
+                            request.encode_to_vec(),
+                        );
+                    }
+                }
+
+                transfer.last_request = now;
+                transfer.request_count += 1;
+                user_files.save_transfer(file_id, &transfer);
+            }
+        }
+    }
+
+    /// choose the users to request the missing chunks from
+    ///
+    /// The sender of the file and the group members that advertised
+    /// the file are ordered by the quality of their route.
+    /// Unreachable users are skipped.
+    /// If none of them is reachable, or after `SENDER_REQUEST_ATTEMPTS`
+    /// unanswered requests, the user chosen by `request_receiver`
+    /// is requested additionally.
+    fn request_sources(
+        user_account: &UserAccount,
+        user_files: &UserFiles,
+        file_id: u64,
+        transfer: &FileTransfer,
+    ) -> Vec<PeerId> {
+        let mut candidates = user_files.get_sources(file_id);
+        if let Ok(sender_id) = PeerId::from_bytes(&transfer.sender_id) {
+            if !candidates.contains(&sender_id) {
+                candidates.insert(0, sender_id);
+            }
+        }
+
+        // order the reachable candidates by route quality
+        let mut routes: Vec<(PeerId, RoutingConnectionEntry)> = candidates
+            .into_iter()
+            .filter(|user_id| *user_id != user_account.id)
+            .filter_map(|user_id| {
+                RoutingTable::get_route_to_user(user_id.clone()).map(|route| (user_id, route))
+            })
+            .collect();
+        routes.sort_by_key(|(_, route)| {
+            (
+                Reverse(RoutingTable::rank_routing_connection(route)),
+                route.hc,
+                route.rtt,
+            )
+        });
+        let mut sources: Vec<PeerId> = routes
+            .into_iter()
+            .map(|(user_id, _)| user_id)
+            .take(MAX_REQUEST_SOURCES)
+            .collect();
+
+        if sources.is_empty() || transfer.request_count >= SENDER_REQUEST_ATTEMPTS {
+            if let Some(receiver) = Self::request_receiver(user_account, transfer) {
+                if !sources.contains(&receiver) {
+                    sources.push(receiver);
+                }
+            }
+        }
+
+        sources
+    }
+
+    /// choose the user to request the missing chunks from
+    ///
+    /// The sender of the file is requested first.
+    /// Afterwards the group members are requested in turns.
+    fn request_receiver(user_account: &UserAccount, transfer: &FileTransfer) -> Option<PeerId> {
+        let sender_id = PeerId::from_bytes(&transfer.sender_id).ok()?;
+        if transfer.request_count < SENDER_REQUEST_ATTEMPTS {
+            return Some(sender_id);
+        }
+
+        let group = match GroupStorage::get_group(user_account.id, transfer.group_id.clone()) {
+            Some(group) => group,
+            None => return Some(sender_id),
+        };
+        let members: Vec<PeerId> = group
+            .members
+            .keys()
+            .filter_map(|id| PeerId::from_bytes(id).ok())
+            .filter(|id| *id != user_account.id)
+            .collect();
+        if members.is_empty() {
+            return Some(sender_id);
+        }
+
+        let turn = (transfer.request_count - SENDER_REQUEST_ATTEMPTS) as usize % members.len();
+        Some(members[turn])
+    }
+
     /// process chat file container message from network
     pub fn process_net_chatfilecontainer(
         sender_id: PeerId,
@@ -960,7 +2773,28 @@ impl ChatFile {
                     );
                 }
                 Some(proto_net::chat_file_container::Message::FileData(file_data)) => {
-                    Self::process_data_message(&user_account, sender_id, group_id, file_data);
+                    Self::process_data_message(
+                        &user_account,
+                        sender_id,
+                        group_id,
+                        message_id,
+                        file_data,
+                    );
+                }
+                Some(proto_net::chat_file_container::Message::FileRequest(file_request)) => {
+                    Self::process_request_message(&user_account, sender_id, group_id, file_request);
+                }
+                Some(proto_net::chat_file_container::Message::FileCancel(file_cancel)) => {
+                    Self::process_cancel_message(&user_account, sender_id, file_cancel);
+                }
+                Some(proto_net::chat_file_container::Message::FileAvailable(file_available)) => {
+                    Self::process_available_message(
+                        &user_account,
+                        sender_id,
+                        group_id,
+                        message_id,
+                        file_available,
+                    );
                 }
                 None => {
                     log::error!(
@@ -997,6 +2831,7 @@ impl ChatFile {
                             send_req.description,
                         ) {
                             log::error!("file rpc send file failed {}", e.to_string());
+                            Self::send_file_response(account_id, false, e, 0);
                         }
                     }
                     Some(proto_rpc::chat_file::Message::FileHistory(history_req)) => {
@@ -1013,9 +2848,14 @@ impl ChatFile {
                         }
 
                         let list = Self::file_history(&user_account, &history_req);
+                        let user_files = Self::get_db_ref(&user_account.id);
 
                         let mut histories: Vec<proto_rpc::FileHistoryEntry> = vec![];
                         for entry in list {
+                            let verified = user_files
+                                .get_hashes(entry.file_id)
+                                .map(|hashes| hashes.verified)
+                                .unwrap_or(false);
                             let file_entry = proto_rpc::FileHistoryEntry {
                                 file_id: entry.file_id,
                                 file_name: entry.file_name.clone(),
@@ -1028,6 +2868,8 @@ impl ChatFile {
                                     entry.group_id.try_into().unwrap(),
                                 )
                                 .to_string(),
+                                verified,
+                                state: entry.file_state.to_rpc() as i32,
                             };
                             histories.push(file_entry);
                         }
@@ -1058,6 +2900,46 @@ impl ChatFile {
                             Vec::new(),
                         );
                     }
+                    Some(proto_rpc::chat_file::Message::FileCancel(cancel_req)) => {
+                        let user_account = match UserAccounts::get_by_id(account_id) {
+                            Some(account) => account,
+                            None => {
+                                log::error!("user account not found");
+                                return;
+                            }
+                        };
+                        if let Err(e) = Self::cancel(&user_account, cancel_req.file_id) {
+                            log::error!("file rpc cancel file failed {}", e);
+                        }
+                    }
+                    Some(proto_rpc::chat_file::Message::FileAccept(accept_req)) => {
+                        let user_account = match UserAccounts::get_by_id(account_id) {
+                            Some(account) => account,
+                            None => {
+                                log::error!("user account not found");
+                                return;
+                            }
+                        };
+                        if let Err(e) = Self::accept(&user_account, accept_req.file_id) {
+                            log::error!("file rpc accept file failed {}", e);
+                        }
+                    }
+                    Some(proto_rpc::chat_file::Message::FilePolicyRequest(_)) => {
+                        Self::send_policy(account_id);
+                    }
+                    Some(proto_rpc::chat_file::Message::FilePolicy(policy)) => {
+                        Configuration::update_file_policy(
+                            account_id.to_string(),
+                            &FilePolicy {
+                                max_auto_accept_size: policy.max_auto_accept_size,
+                                trusted_only: policy.trusted_only,
+                                manual_accept: policy.manual_accept,
+                            },
+                        );
+                        Configuration::save();
+
+                        Self::send_policy(account_id);
+                    }
                     _ => {
                         log::error!("Unhandled Protobuf File Message");
                     }
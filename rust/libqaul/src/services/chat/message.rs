@@ -7,15 +7,29 @@
 //! in one single message.
 //!
 //! The sending of the files via the chat messaging is handled in the file.rs file.
+//!
+//! A message can reply to another message of the group, quoting an
+//! excerpt of it. Group members can react on messages with emojis.
+//!
+//! When a conversation is displayed, read receipts are sent to the
+//! senders of the newly read messages, if enabled in the chat privacy
+//! settings of the user account.
+//!
+//! Sent messages can be edited and deleted for everyone by their sender.
+//! Edits and deletions reference the original message id and are
+//! propagated to all group members as group messages.
 
 use libp2p::PeerId;
 use prost::Message;
 
 use super::rpc_proto;
 use super::{Chat, ChatStorage};
-use crate::node::user_accounts::{UserAccount, UserAccounts};
-use crate::services::group::{Group, GroupId, GroupManage, GroupStorage};
-use crate::services::messaging::{proto, Messaging, MessagingServiceType};
+use crate::node::user_accounts::UserAccounts;
+use crate::services::group::{
+    proto_net as group_proto_net, Group, GroupId, GroupManage, GroupSenderKey, GroupStorage,
+};
+use crate::services::messaging::{proto, MessagingServiceType};
+use crate::storage::configuration::Configuration;
 use crate::utilities::timestamp::Timestamp;
 
 /// Incoming and outgoing chat message handling
@@ -23,31 +37,40 @@ pub struct ChatMessage {}
 
 impl ChatMessage {
     /// send message
-    pub fn send(
-        user_account: &UserAccount,
-        receiver: &PeerId,
-        common_message: &proto::CommonMessage,
-    ) -> Result<Vec<u8>, String> {
-        let send_message = proto::Messaging {
-            message: Some(proto::messaging::Message::CommonMessage(
-                common_message.clone(),
-            )),
+    pub fn send_chat_message(
+        account_id: &PeerId,
+        group_id: &Vec<u8>,
+        message: String,
+    ) -> Result<bool, String> {
+        Self::send(account_id, group_id, message, None)
+    }
+
+    /// send a reply to a message
+    ///
+    /// The reply contains a quoted excerpt of the message replied to.
+    pub fn send_reply(
+        account_id: &PeerId,
+        group_id: &Vec<u8>,
+        reply_to: &Vec<u8>,
+        message: String,
+    ) -> Result<bool, String> {
+        let (sender_id, quote) = ChatStorage::get_quote(account_id, group_id, reply_to)?;
+        let reply = rpc_proto::ChatReply {
+            message_id: reply_to.clone(),
+            sender_id,
+            quote,
+            text: message.clone(),
+            edited_at: 0,
         };
-        Messaging::pack_and_send_message(
-            user_account,
-            &receiver,
-            send_message.encode_to_vec(),
-            MessagingServiceType::Chat,
-            &common_message.message_id,
-            true,
-        )
+        Self::send(account_id, group_id, message, Some(reply))
     }
 
-    /// send message
-    pub fn send_chat_message(
+    /// send a text message or a reply to all group members
+    fn send(
         account_id: &PeerId,
         group_id: &Vec<u8>,
         message: String,
+        reply: Option<rpc_proto::ChatReply>,
     ) -> Result<bool, String> {
         let groupid: GroupId;
         let group;
@@ -103,16 +126,34 @@ impl ChatMessage {
             payload: Some(proto::common_message::Payload::ChatMessage(
                 proto::ChatMessage {
                     content: message.clone(),
+                    reply_to: reply
+                        .as_ref()
+                        .map(|reply| reply.message_id.clone())
+                        .unwrap_or_default(),
+                    quote: reply
+                        .as_ref()
+                        .map(|reply| reply.quote.clone())
+                        .unwrap_or_default(),
                 },
             )),
         };
 
-        let message_content = super::rpc_proto::ChatContentMessage {
-            message: Some(
-                super::rpc_proto::chat_content_message::Message::ChatContent(
-                    super::rpc_proto::ChatContent { text: message },
+        let message_content = match reply {
+            Some(reply) => super::rpc_proto::ChatContentMessage {
+                message: Some(super::rpc_proto::chat_content_message::Message::Reply(
+                    reply,
+                )),
+            },
+            None => super::rpc_proto::ChatContentMessage {
+                message: Some(
+                    super::rpc_proto::chat_content_message::Message::ChatContent(
+                        super::rpc_proto::ChatContent {
+                            text: message,
+                            edited_at: 0,
+                        },
+                    ),
                 ),
-            ),
+            },
         };
 
         // save outgoing message
@@ -128,15 +169,16 @@ impl ChatMessage {
 
         // send to all group members
         if let Some(user_account) = UserAccounts::get_by_id(account_id.clone()) {
-            for user_id in group.members.keys() {
-                let receiver = PeerId::from_bytes(&user_id.clone()).unwrap();
-                if receiver != *account_id {
-                    log::trace!("send message to {}", receiver.to_base58());
-                    if let Err(error) = Self::send(&user_account, &receiver, &common_message) {
-                        log::error!("chat message send error {}", error);
-                    }
-                }
-            }
+            let send_message = proto::Messaging {
+                message: Some(proto::messaging::Message::CommonMessage(common_message)),
+            };
+            GroupSenderKey::send_to_members(
+                &user_account,
+                &group,
+                send_message.encode_to_vec(),
+                MessagingServiceType::Chat,
+                &message_id,
+            );
         }
 
         // update member state
@@ -145,4 +187,205 @@ impl ChatMessage {
 
         Ok(true)
     }
+
+    /// edit a sent chat message
+    ///
+    /// The message is updated locally and the edit
+    /// is sent to all group members.
+    pub fn edit_chat_message(
+        account_id: &PeerId,
+        message_id: &Vec<u8>,
+        text: String,
+    ) -> Result<(), String> {
+        let user_account = match UserAccounts::get_by_id(account_id.to_owned()) {
+            Some(user_account) => user_account,
+            None => return Err("user account not found".to_string()),
+        };
+        let edited_at = Timestamp::get_timestamp();
+
+        // update local message
+        let group_id =
+            ChatStorage::edit_message(account_id, account_id, message_id, text.clone(), edited_at)?;
+
+        // send edit to all group members
+        let container = group_proto_net::GroupContainer {
+            message: Some(group_proto_net::group_container::Message::ChatEdit(
+                group_proto_net::ChatMessageEdit {
+                    group_id: group_id.clone(),
+                    message_id: message_id.clone(),
+                    text,
+                    edited_at,
+                },
+            )),
+        };
+        Group::send_group_message(&user_account, group_id, &container.encode_to_vec());
+
+        Ok(())
+    }
+
+    /// delete a sent chat message for everyone
+    ///
+    /// The message is deleted locally and the deletion
+    /// is sent to all group members.
+    pub fn delete_chat_message(account_id: &PeerId, message_id: &Vec<u8>) -> Result<(), String> {
+        let user_account = match UserAccounts::get_by_id(account_id.to_owned()) {
+            Some(user_account) => user_account,
+            None => return Err("user account not found".to_string()),
+        };
+        let deleted_at = Timestamp::get_timestamp();
+
+        // delete local message
+        let group_id = ChatStorage::delete_message(account_id, account_id, message_id, deleted_at)?;
+
+        // send deletion to all group members
+        let container = group_proto_net::GroupContainer {
+            message: Some(group_proto_net::group_container::Message::ChatDelete(
+                group_proto_net::ChatMessageDelete {
+                    group_id: group_id.clone(),
+                    message_id: message_id.clone(),
+                    deleted_at,
+                },
+            )),
+        };
+        Group::send_group_message(&user_account, group_id, &container.encode_to_vec());
+
+        Ok(())
+    }
+
+    /// add or remove an emoji reaction on a message
+    ///
+    /// The reaction is saved locally and sent to all group members.
+    pub fn send_reaction(
+        account_id: &PeerId,
+        message_id: &Vec<u8>,
+        emoji: String,
+        remove: bool,
+    ) -> Result<(), String> {
+        let user_account = match UserAccounts::get_by_id(account_id.to_owned()) {
+            Some(user_account) => user_account,
+            None => return Err("user account not found".to_string()),
+        };
+        let group_id = ChatStorage::get_message(account_id, message_id)?.group_id;
+
+        // update local reactions
+        ChatStorage::update_reaction(
+            account_id, &group_id, account_id, message_id, &emoji, remove,
+        )?;
+
+        // send reaction to all group members
+        let container = group_proto_net::GroupContainer {
+            message: Some(group_proto_net::group_container::Message::ChatReaction(
+                group_proto_net::ChatMessageReaction {
+                    group_id: group_id.clone(),
+                    message_id: message_id.clone(),
+                    emoji,
+                    remove,
+                },
+            )),
+        };
+        Group::send_group_message(&user_account, group_id, &container.encode_to_vec());
+
+        Ok(())
+    }
+
+    /// send read receipts for the newly read messages of a conversation
+    ///
+    /// The messages are marked as read in any case.
+    /// The receipts are only sent, if read receipts are enabled
+    /// in the chat privacy settings of the user account.
+    pub fn send_read_receipts(account_id: &PeerId, conversation: &rpc_proto::ChatConversationList) {
+        let unread = ChatStorage::mark_read(account_id, &conversation.message_list);
+        if unread.is_empty() {
+            return;
+        }
+
+        match Configuration::get_user(account_id.to_string()) {
+            Some(user) if user.chat_privacy.read_receipts => {}
+            _ => return,
+        }
+        let user_account = match UserAccounts::get_by_id(account_id.to_owned()) {
+            Some(user_account) => user_account,
+            None => return,
+        };
+
+        let read_at = Timestamp::get_timestamp();
+        for (sender_id, message_ids) in unread {
+            let sender = match PeerId::from_bytes(&sender_id) {
+                Ok(sender) => sender,
+                Err(_) => continue,
+            };
+            let container = group_proto_net::GroupContainer {
+                message: Some(group_proto_net::group_container::Message::ChatRead(
+                    group_proto_net::ChatReadReceipt {
+                        group_id: conversation.group_id.clone(),
+                        message_ids,
+                        read_at,
+                    },
+                )),
+            };
+            Group::send_member_message(
+                &user_account,
+                conversation.group_id.clone(),
+                &sender,
+                &container.encode_to_vec(),
+            );
+        }
+    }
+
+    /// process a received chat message edit
+    pub fn on_edit(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        edit: &group_proto_net::ChatMessageEdit,
+    ) -> Result<(), String> {
+        ChatStorage::edit_message(
+            account_id,
+            sender_id,
+            &edit.message_id,
+            edit.text.clone(),
+            edit.edited_at,
+        )?;
+        Ok(())
+    }
+
+    /// process a received chat message deletion
+    pub fn on_delete(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        delete: &group_proto_net::ChatMessageDelete,
+    ) -> Result<(), String> {
+        ChatStorage::delete_message(account_id, sender_id, &delete.message_id, delete.deleted_at)?;
+        Ok(())
+    }
+
+    /// process a received reaction on a chat message
+    pub fn on_reaction(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        reaction: &group_proto_net::ChatMessageReaction,
+    ) -> Result<(), String> {
+        ChatStorage::update_reaction(
+            account_id,
+            &reaction.group_id,
+            sender_id,
+            &reaction.message_id,
+            &reaction.emoji,
+            reaction.remove,
+        )
+    }
+
+    /// process received read receipts
+    pub fn on_read_receipt(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        receipt: &group_proto_net::ChatReadReceipt,
+    ) {
+        for message_id in &receipt.message_ids {
+            if let Err(error) =
+                ChatStorage::update_read(account_id, sender_id, message_id, receipt.read_at)
+            {
+                log::error!("chat read receipt error: {}", error);
+            }
+        }
+    }
 }
@@ -8,15 +8,21 @@
 use libp2p::PeerId;
 use prost::Message;
 
+pub mod export;
 pub mod file;
 pub mod message;
+pub mod search;
 pub mod storage;
 
 use crate::connections::{internet::Internet, lan::Lan};
 use crate::node::user_accounts::UserAccounts;
 use crate::rpc::Rpc;
+use crate::services::messaging::{failed::MessagingFailed, MessagingServiceType};
+use crate::storage::configuration::{ChatPrivacy, Configuration};
+pub use export::ChatExport;
 pub use file::ChatFile;
 pub use message::ChatMessage;
+pub use search::ChatSearch;
 pub use storage::ChatStorage;
 
 /// Import protobuf message definition generated by
@@ -34,6 +40,9 @@ impl Chat {
         // initialize the chat storage
         ChatStorage::init();
 
+        // initialize the search indexes
+        ChatSearch::init();
+
         // initialize the chat file management
         ChatFile::init();
     }
@@ -59,6 +68,29 @@ impl Chat {
         buff0
     }
 
+    /// send the chat privacy settings of a user account via rpc
+    fn send_privacy(account_id: PeerId) {
+        let privacy = match Configuration::get_user(account_id.to_string()) {
+            Some(user) => user.chat_privacy,
+            None => ChatPrivacy::default(),
+        };
+
+        // pack message
+        let proto_message = rpc_proto::Chat {
+            message: Some(rpc_proto::chat::Message::Privacy(rpc_proto::ChatPrivacy {
+                read_receipts: privacy.read_receipts,
+            })),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            crate::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
     /// Process incoming RPC request messages for chat module
     pub fn rpc(
         data: Vec<u8>,
@@ -76,6 +108,9 @@ impl Chat {
                         let conversation_list =
                             ChatStorage::get_messages(account_id, conversation_request.group_id);
 
+                        // confirm the newly read messages to their senders
+                        ChatMessage::send_read_receipts(&account_id, &conversation_list);
+
                         // pack message
                         let proto_message = rpc_proto::Chat {
                             message: Some(rpc_proto::chat::Message::ConversationList(
@@ -130,6 +165,170 @@ impl Chat {
                             log::error!("Outgoing chat message error: {}", error)
                         }
                     }
+                    Some(rpc_proto::chat::Message::FailedRequest(_failed_request)) => {
+                        // get failed chat messages from data base
+                        let mut messages: Vec<rpc_proto::ChatFailedMessage> = Vec::new();
+                        for failed in MessagingFailed::get_failed_messages(account_id) {
+                            match failed.message_type {
+                                MessagingServiceType::Chat | MessagingServiceType::ChatFile => {
+                                    messages.push(MessagingFailed::to_chat_rpc(&failed));
+                                }
+                                _ => {}
+                            }
+                        }
+
+                        // pack message
+                        let proto_message = rpc_proto::Chat {
+                            message: Some(rpc_proto::chat::Message::FailedList(
+                                rpc_proto::ChatFailedList { messages },
+                            )),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Chat.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(rpc_proto::chat::Message::ResendRequest(resend_request)) => {
+                        // resend failed message
+                        let count = MessagingFailed::resend(account_id, &resend_request.message_id);
+                        if count == 0 {
+                            log::error!(
+                                "no failed message {} found",
+                                bs58::encode(resend_request.message_id).into_string()
+                            );
+                        }
+                    }
+                    Some(rpc_proto::chat::Message::Edit(edit)) => {
+                        // edit a sent message
+                        if let Err(error) = ChatMessage::edit_chat_message(
+                            &account_id,
+                            &edit.message_id,
+                            edit.content,
+                        ) {
+                            log::error!("chat message edit error: {}", error);
+                        }
+                    }
+                    Some(rpc_proto::chat::Message::Delete(delete)) => {
+                        // delete a sent message for everyone
+                        if let Err(error) =
+                            ChatMessage::delete_chat_message(&account_id, &delete.message_id)
+                        {
+                            log::error!("chat message delete error: {}", error);
+                        }
+                    }
+                    Some(rpc_proto::chat::Message::Reply(reply)) => {
+                        // send a reply to a message
+                        if let Err(error) = ChatMessage::send_reply(
+                            &account_id,
+                            &reply.group_id,
+                            &reply.message_id,
+                            reply.content,
+                        ) {
+                            log::error!("Outgoing chat reply error: {}", error);
+                        }
+                    }
+                    Some(rpc_proto::chat::Message::Reaction(reaction)) => {
+                        // add or remove a reaction on a message
+                        if let Err(error) = ChatMessage::send_reaction(
+                            &account_id,
+                            &reaction.message_id,
+                            reaction.emoji,
+                            reaction.remove,
+                        ) {
+                            log::error!("chat reaction error: {}", error);
+                        }
+                    }
+                    Some(rpc_proto::chat::Message::EditHistoryRequest(request)) => {
+                        // get edit history of a message from data base
+                        let edit_history =
+                            ChatStorage::get_edit_history(account_id, request.message_id);
+
+                        // pack message
+                        let proto_message = rpc_proto::Chat {
+                            message: Some(rpc_proto::chat::Message::EditHistory(edit_history)),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Chat.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(rpc_proto::chat::Message::PrivacyRequest(_)) => {
+                        Self::send_privacy(account_id);
+                    }
+                    Some(rpc_proto::chat::Message::Privacy(privacy)) => {
+                        Configuration::update_chat_privacy(
+                            account_id.to_string(),
+                            &ChatPrivacy {
+                                read_receipts: privacy.read_receipts,
+                            },
+                        );
+                        Configuration::save();
+
+                        Self::send_privacy(account_id);
+                    }
+                    Some(rpc_proto::chat::Message::SearchRequest(search_request)) => {
+                        // search the messages
+                        let search_result = ChatSearch::search(account_id, search_request);
+
+                        // pack message
+                        let proto_message = rpc_proto::Chat {
+                            message: Some(rpc_proto::chat::Message::SearchResult(search_result)),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Chat.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(rpc_proto::chat::Message::ExportRequest(export_request)) => {
+                        // export the conversations
+                        let export_response = ChatExport::export(account_id, export_request);
+
+                        // pack message
+                        let proto_message = rpc_proto::Chat {
+                            message: Some(rpc_proto::chat::Message::ExportResponse(
+                                export_response,
+                            )),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Chat.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(rpc_proto::chat::Message::ImportRequest(import_request)) => {
+                        // import the conversations
+                        let import_response = ChatExport::import(account_id, import_request);
+
+                        // pack message
+                        let proto_message = rpc_proto::Chat {
+                            message: Some(rpc_proto::chat::Message::ImportResponse(
+                                import_response,
+                            )),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Chat.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
                     _ => {
                         log::error!("Unhandled Protobuf Chat Message");
                     }
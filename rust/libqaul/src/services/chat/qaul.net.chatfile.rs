@@ -2,7 +2,7 @@
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ChatFileContainer {
-    #[prost(oneof = "chat_file_container::Message", tags = "1, 2")]
+    #[prost(oneof = "chat_file_container::Message", tags = "1, 2, 3, 4, 5")]
     pub message: ::core::option::Option<chat_file_container::Message>,
 }
 /// Nested message and enum types in `ChatFileContainer`.
@@ -16,6 +16,15 @@ pub mod chat_file_container {
         /// Chat File Data Message
         #[prost(message, tag = "2")]
         FileData(super::ChatFileData),
+        /// Chat File Request Message
+        #[prost(message, tag = "3")]
+        FileRequest(super::ChatFileRequest),
+        /// Chat File Cancel Message
+        #[prost(message, tag = "4")]
+        FileCancel(super::ChatFileCancel),
+        /// Chat File Available Message
+        #[prost(message, tag = "5")]
+        FileAvailable(super::ChatFileAvailable),
     }
 }
 /// Chat File Info Message
@@ -31,9 +40,9 @@ pub struct ChatFileInfo {
     /// file extension
     #[prost(string, tag = "3")]
     pub file_extension: ::prost::alloc::string::String,
-    /// file size
-    #[prost(uint32, tag = "4")]
-    pub file_size: u32,
+    /// file size in bytes
+    #[prost(uint64, tag = "4")]
+    pub file_size: u64,
     /// file description
     #[prost(string, tag = "5")]
     pub file_description: ::prost::alloc::string::String,
@@ -47,6 +56,31 @@ pub struct ChatFileInfo {
     /// file data chunk size
     #[prost(uint32, tag = "8")]
     pub data_chunk_size: u32,
+    /// qaul id of the original sender of the file
+    ///
+    /// This field is only set, when the info message
+    /// is resent by another group member.
+    #[prost(bytes = "vec", tag = "9")]
+    pub sender_id: ::prost::alloc::vec::Vec<u8>,
+    /// SHA-256 hash of the file content
+    ///
+    /// Older versions of qaul don't send any hashes.
+    #[prost(bytes = "vec", tag = "10")]
+    pub file_hash: ::prost::alloc::vec::Vec<u8>,
+    /// SHA-256 hashes of all file data chunks,
+    /// ordered by chunk index
+    #[prost(bytes = "vec", repeated, tag = "11")]
+    pub chunk_hashes: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// signature of the original sender
+    ///
+    /// The sender signs this message with empty
+    /// sender_id and signature fields.
+    /// Group members resending the info message include
+    /// the signature, so that the receiver can verify
+    /// the original sender and the hashes.
+    /// Older versions of qaul don't sign the message.
+    #[prost(bytes = "vec", tag = "12")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
 }
 /// Chat File Data Message
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -65,3 +99,44 @@ pub struct ChatFileData {
     #[prost(bytes = "vec", tag = "4")]
     pub data: ::prost::alloc::vec::Vec<u8>,
 }
+/// Chat File Request Message
+///
+/// Requests the missing parts of a file.
+/// The request is sent to the sender of the file,
+/// or to another group member, that has received the file.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatFileRequest {
+    /// file id
+    #[prost(uint64, tag = "1")]
+    pub file_id: u64,
+    /// request the file info message
+    #[prost(bool, tag = "2")]
+    pub info: bool,
+    /// indexes of the missing data chunks
+    #[prost(uint32, repeated, tag = "3")]
+    pub missing_chunks: ::prost::alloc::vec::Vec<u32>,
+}
+/// Chat File Cancel Message
+///
+/// The sender of a file cancels sending the file,
+/// or a receiver cancels receiving it.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatFileCancel {
+    /// file id
+    #[prost(uint64, tag = "1")]
+    pub file_id: u64,
+}
+/// Chat File Available Message
+///
+/// A group member advertises, that it has received
+/// the complete file.
+/// The member can be requested for the file.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatFileAvailable {
+    /// file id
+    #[prost(uint64, tag = "1")]
+    pub file_id: u64,
+}
@@ -3,7 +3,10 @@
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Chat {
     /// message type
-    #[prost(oneof = "chat::Message", tags = "3, 4, 5")]
+    #[prost(
+        oneof = "chat::Message",
+        tags = "3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23"
+    )]
     pub message: ::core::option::Option<chat::Message>,
 }
 /// Nested message and enum types in `Chat`.
@@ -21,6 +24,66 @@ pub mod chat {
         /// send a new chat message
         #[prost(message, tag = "5")]
         Send(super::ChatMessageSend),
+        /// the delivery of a message failed
+        ///
+        /// This message is sent by libqaul, whenever
+        /// a message could not be delivered to a receiver.
+        #[prost(message, tag = "6")]
+        DeliveryFailed(super::ChatDeliveryFailed),
+        /// request the list of failed messages
+        #[prost(message, tag = "7")]
+        FailedRequest(super::ChatFailedRequest),
+        /// list of failed messages
+        #[prost(message, tag = "8")]
+        FailedList(super::ChatFailedList),
+        /// resend a failed message
+        #[prost(message, tag = "9")]
+        ResendRequest(super::ChatResendRequest),
+        /// edit a sent chat message
+        #[prost(message, tag = "10")]
+        Edit(super::ChatEditRequest),
+        /// delete a sent chat message for everyone
+        #[prost(message, tag = "11")]
+        Delete(super::ChatDeleteRequest),
+        /// request the edit history of a chat message
+        #[prost(message, tag = "12")]
+        EditHistoryRequest(super::ChatEditHistoryRequest),
+        /// edit history of a chat message
+        #[prost(message, tag = "13")]
+        EditHistory(super::ChatEditHistory),
+        /// reply to a chat message
+        #[prost(message, tag = "14")]
+        Reply(super::ChatReplySend),
+        /// add or remove an emoji reaction on a chat message
+        #[prost(message, tag = "15")]
+        Reaction(super::ChatReactionSend),
+        /// request the chat privacy settings
+        #[prost(message, tag = "16")]
+        PrivacyRequest(super::ChatPrivacyRequest),
+        /// chat privacy settings
+        ///
+        /// This message is sent by libqaul as a response
+        /// to a request, and by the client to update the settings.
+        #[prost(message, tag = "17")]
+        Privacy(super::ChatPrivacy),
+        /// search the chat history and the feed
+        #[prost(message, tag = "18")]
+        SearchRequest(super::ChatSearchRequest),
+        /// search results
+        #[prost(message, tag = "19")]
+        SearchResult(super::ChatSearchResult),
+        /// export conversations to files
+        #[prost(message, tag = "20")]
+        ExportRequest(super::ChatExportRequest),
+        /// result of an export
+        #[prost(message, tag = "21")]
+        ExportResponse(super::ChatExportResponse),
+        /// import conversations from a JSON export
+        #[prost(message, tag = "22")]
+        ImportRequest(super::ChatImportRequest),
+        /// result of an import
+        #[prost(message, tag = "23")]
+        ImportResponse(super::ChatImportResponse),
     }
 }
 /// request messages of a specific chat conversation
@@ -82,6 +145,15 @@ pub struct ChatMessage {
     /// chat content message
     #[prost(bytes = "vec", tag = "8")]
     pub content: ::prost::alloc::vec::Vec<u8>,
+    /// id of the message this message replies to,
+    /// empty if it is not a reply
+    #[prost(bytes = "vec", tag = "11")]
+    #[serde(skip)]
+    pub reply_to: ::prost::alloc::vec::Vec<u8>,
+    /// emoji reactions on this message
+    #[prost(message, repeated, tag = "12")]
+    #[serde(skip)]
+    pub reactions: ::prost::alloc::vec::Vec<MessageReaction>,
 }
 /// message reception confirmed
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -94,12 +166,28 @@ pub struct MessageReceptionConfirmed {
     /// time of confirmation
     #[prost(uint64, tag = "2")]
     pub confirmed_at: u64,
+    /// time the user read the message,
+    /// 0 if no read receipt was received
+    #[prost(uint64, tag = "3")]
+    #[serde(skip)]
+    pub read_at: u64,
+}
+/// emoji reaction on a message
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MessageReaction {
+    /// reaction emoji
+    #[prost(string, tag = "1")]
+    pub emoji: ::prost::alloc::string::String,
+    /// ids of the users who reacted with this emoji
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub user_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
 /// chat content message
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ChatContentMessage {
-    #[prost(oneof = "chat_content_message::Message", tags = "1, 2, 3")]
+    #[prost(oneof = "chat_content_message::Message", tags = "1, 2, 3, 4, 5, 6")]
     pub message: ::core::option::Option<chat_content_message::Message>,
 }
 /// Nested message and enum types in `ChatContentMessage`.
@@ -116,6 +204,15 @@ pub mod chat_content_message {
         /// a group event information
         #[prost(message, tag = "3")]
         GroupEvent(super::GroupEvent),
+        /// a deleted message
+        #[prost(message, tag = "4")]
+        MessageDeleted(super::MessageDeleted),
+        /// a reply to a message
+        #[prost(message, tag = "5")]
+        Reply(super::ChatReply),
+        /// a reaction on a message
+        #[prost(message, tag = "6")]
+        Reaction(super::ChatReaction),
     }
 }
 /// chat content
@@ -125,6 +222,56 @@ pub struct ChatContent {
     /// message text
     #[prost(string, tag = "1")]
     pub text: ::prost::alloc::string::String,
+    /// time of the last edit, 0 if never edited
+    #[prost(uint64, tag = "2")]
+    pub edited_at: u64,
+}
+/// reply to a message
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatReply {
+    /// id of the message replied to
+    #[prost(bytes = "vec", tag = "1")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// id of the sender of the message replied to
+    #[prost(bytes = "vec", tag = "2")]
+    pub sender_id: ::prost::alloc::vec::Vec<u8>,
+    /// quoted excerpt of the message replied to
+    #[prost(string, tag = "3")]
+    pub quote: ::prost::alloc::string::String,
+    /// message text
+    #[prost(string, tag = "4")]
+    pub text: ::prost::alloc::string::String,
+    /// time of the last edit, 0 if never edited
+    #[prost(uint64, tag = "5")]
+    pub edited_at: u64,
+}
+/// reaction on a message
+///
+/// Reactions are not saved as messages of their own,
+/// they are aggregated on the message they react to.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatReaction {
+    /// id of the message reacted to
+    #[prost(bytes = "vec", tag = "1")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// reaction emoji
+    #[prost(string, tag = "2")]
+    pub emoji: ::prost::alloc::string::String,
+    /// true if the reaction was removed
+    #[prost(bool, tag = "3")]
+    pub remove: bool,
+}
+/// deleted message
+///
+/// The content of a message, that was deleted by its sender.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MessageDeleted {
+    /// time of the deletion
+    #[prost(uint64, tag = "1")]
+    pub deleted_at: u64,
 }
 /// file content
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -139,9 +286,9 @@ pub struct FileContent {
     /// file extension
     #[prost(string, tag = "3")]
     pub file_extension: ::prost::alloc::string::String,
-    /// file size
-    #[prost(uint32, tag = "4")]
-    pub file_size: u32,
+    /// file size in bytes
+    #[prost(uint64, tag = "4")]
+    pub file_size: u64,
     /// file description
     #[prost(string, tag = "5")]
     pub file_description: ::prost::alloc::string::String,
@@ -169,6 +316,308 @@ pub struct ChatMessageSend {
     #[prost(string, tag = "2")]
     pub content: ::prost::alloc::string::String,
 }
+/// delivery of a message failed
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatDeliveryFailed {
+    /// failed message
+    #[prost(message, optional, tag = "1")]
+    pub message: ::core::option::Option<ChatFailedMessage>,
+}
+/// request the failed messages of this user account
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatFailedRequest {}
+/// list of failed messages
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatFailedList {
+    /// failed messages
+    #[prost(message, repeated, tag = "1")]
+    pub messages: ::prost::alloc::vec::Vec<ChatFailedMessage>,
+}
+/// a message that could not be delivered to a receiver
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatFailedMessage {
+    /// message id
+    #[prost(bytes = "vec", tag = "1")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// group id, empty if unknown
+    #[prost(bytes = "vec", tag = "2")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// id of the receiving user
+    #[prost(bytes = "vec", tag = "3")]
+    pub receiver_id: ::prost::alloc::vec::Vec<u8>,
+    /// time when the message was created
+    #[prost(uint64, tag = "4")]
+    pub created_at: u64,
+    /// time of the last sending attempt
+    #[prost(uint64, tag = "5")]
+    pub last_try: u64,
+    /// number of sending attempts
+    #[prost(uint32, tag = "6")]
+    pub try_count: u32,
+    /// reason of the failure
+    #[prost(string, tag = "7")]
+    pub reason: ::prost::alloc::string::String,
+}
+/// resend a failed message
+///
+/// The message is sent again to all receivers
+/// to which its delivery failed.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatResendRequest {
+    /// message id
+    #[prost(bytes = "vec", tag = "1")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// edit a sent chat message
+///
+/// Only messages sent by this user account can be edited.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatEditRequest {
+    /// message id
+    #[prost(bytes = "vec", tag = "1")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// new message text
+    #[prost(string, tag = "2")]
+    pub content: ::prost::alloc::string::String,
+}
+/// delete a sent chat message for everyone
+///
+/// Only messages sent by this user account can be deleted.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatDeleteRequest {
+    /// message id
+    #[prost(bytes = "vec", tag = "1")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// request the edit history of a chat message
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatEditHistoryRequest {
+    /// message id
+    #[prost(bytes = "vec", tag = "1")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// edit history of a chat message
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatEditHistory {
+    /// message id
+    #[prost(bytes = "vec", tag = "1")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// previous versions of the message, the oldest first
+    #[prost(message, repeated, tag = "2")]
+    pub edits: ::prost::alloc::vec::Vec<ChatEditEntry>,
+}
+/// a previous version of an edited chat message
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatEditEntry {
+    /// message text
+    #[prost(string, tag = "1")]
+    pub text: ::prost::alloc::string::String,
+    /// time of the edit that created this version,
+    /// 0 for the original message
+    #[prost(uint64, tag = "2")]
+    pub edited_at: u64,
+}
+/// reply to a chat message
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatReplySend {
+    /// group id to which this message is sent
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// id of the message replied to
+    #[prost(bytes = "vec", tag = "2")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// content of the message
+    #[prost(string, tag = "3")]
+    pub content: ::prost::alloc::string::String,
+}
+/// add or remove an emoji reaction on a chat message
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatReactionSend {
+    /// id of the message reacted to
+    #[prost(bytes = "vec", tag = "1")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// reaction emoji
+    #[prost(string, tag = "2")]
+    pub emoji: ::prost::alloc::string::String,
+    /// true to remove the reaction
+    #[prost(bool, tag = "3")]
+    pub remove: bool,
+}
+/// request the chat privacy settings
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatPrivacyRequest {}
+/// chat privacy settings of the user account
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatPrivacy {
+    /// send read receipts to the senders of messages,
+    /// when their messages are displayed
+    #[prost(bool, tag = "1")]
+    pub read_receipts: bool,
+}
+/// search the chat history and the feed
+///
+/// All words of the query need to be found in a message.
+/// A word of the query matches all words starting with it.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatSearchRequest {
+    /// search query
+    #[prost(string, tag = "1")]
+    pub query: ::prost::alloc::string::String,
+    /// only search the conversation of this group,
+    /// empty to search all conversations
+    #[prost(bytes = "vec", tag = "2")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// only search the messages of this sender,
+    /// empty to search the messages of all senders
+    #[prost(bytes = "vec", tag = "3")]
+    pub sender_id: ::prost::alloc::vec::Vec<u8>,
+    /// only search messages sent at or after this time,
+    /// 0 for no limit
+    #[prost(uint64, tag = "4")]
+    pub sent_after: u64,
+    /// only search messages sent at or before this time,
+    /// 0 for no limit
+    #[prost(uint64, tag = "5")]
+    pub sent_before: u64,
+    /// search the feed messages too
+    ///
+    /// Feed messages are not searched, when a group id is set.
+    #[prost(bool, tag = "6")]
+    pub include_feed: bool,
+    /// number of results to skip
+    #[prost(uint32, tag = "7")]
+    pub offset: u32,
+    /// maximal number of results, 0 for the default of 20
+    #[prost(uint32, tag = "8")]
+    pub limit: u32,
+}
+/// search results
+///
+/// The matching messages are ordered by their sending time,
+/// the newest first.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatSearchResult {
+    /// search query
+    #[prost(string, tag = "1")]
+    pub query: ::prost::alloc::string::String,
+    /// total number of matching messages
+    #[prost(uint32, tag = "2")]
+    pub total: u32,
+    /// number of skipped results
+    #[prost(uint32, tag = "3")]
+    pub offset: u32,
+    /// matching messages
+    #[prost(message, repeated, tag = "4")]
+    pub hits: ::prost::alloc::vec::Vec<ChatSearchHit>,
+}
+/// a matching message
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatSearchHit {
+    /// source of the message
+    #[prost(enumeration = "SearchSource", tag = "1")]
+    pub source: i32,
+    /// name of the group of the message,
+    /// empty for feed messages
+    #[prost(string, tag = "2")]
+    pub group_name: ::prost::alloc::string::String,
+    /// the message
+    ///
+    /// Feed messages have no group id, their content
+    /// is a chat content message and their index is the
+    /// index of the feed message.
+    #[prost(message, optional, tag = "3")]
+    pub message: ::core::option::Option<ChatMessage>,
+}
+/// export conversations to files
+///
+/// A JSON archive `qaul-chat-export.json` is always written,
+/// as it can be imported again.
+/// Attached files are copied to the `files` folder.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatExportRequest {
+    /// group id of the conversation to export,
+    /// empty to export all conversations
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// additional human readable format
+    #[prost(enumeration = "ExportFormat", tag = "2")]
+    pub format: i32,
+    /// path of the folder to export to
+    ///
+    /// The folder is created if it does not exist.
+    #[prost(string, tag = "3")]
+    pub path: ::prost::alloc::string::String,
+}
+/// result of an export
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatExportResponse {
+    /// export was successful
+    #[prost(bool, tag = "1")]
+    pub success: bool,
+    /// error message, if the export failed
+    #[prost(string, tag = "2")]
+    pub error: ::prost::alloc::string::String,
+    /// paths of the written documents
+    #[prost(string, repeated, tag = "3")]
+    pub files: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// number of exported conversations
+    #[prost(uint32, tag = "4")]
+    pub conversations: u32,
+    /// number of exported messages
+    #[prost(uint32, tag = "5")]
+    pub messages: u32,
+}
+/// import conversations from a JSON export
+///
+/// Conversations that do not exist in the user account
+/// are created as deactivated groups.
+/// Messages that already exist are skipped.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatImportRequest {
+    /// path of the JSON archive
+    ///
+    /// Attached files are read from the `files` folder
+    /// next to it.
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+}
+/// result of an import
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatImportResponse {
+    /// import was successful
+    #[prost(bool, tag = "1")]
+    pub success: bool,
+    /// error message, if the import failed
+    #[prost(string, tag = "2")]
+    pub error: ::prost::alloc::string::String,
+    /// number of imported conversations
+    #[prost(uint32, tag = "3")]
+    pub conversations: u32,
+    /// number of imported messages
+    #[prost(uint32, tag = "4")]
+    pub messages: u32,
+}
 /// Sending status of sent messages
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -188,6 +637,17 @@ pub enum MessageStatus {
     Receiving = 4,
     /// message received
     Received = 5,
+    /// message is waiting for the crypto handshake
+    /// with the receiver to complete
+    WaitingForKeyExchange = 6,
+    /// message could not be delivered
+    ///
+    /// The message can be resent manually.
+    Failed = 7,
+    /// the file is waiting for manual acceptance
+    Pending = 8,
+    /// the file transfer has been cancelled
+    Cancelled = 9,
 }
 impl MessageStatus {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -202,6 +662,10 @@ impl MessageStatus {
             MessageStatus::ConfirmedByAll => "CONFIRMED_BY_ALL",
             MessageStatus::Receiving => "RECEIVING",
             MessageStatus::Received => "RECEIVED",
+            MessageStatus::WaitingForKeyExchange => "WAITING_FOR_KEY_EXCHANGE",
+            MessageStatus::Failed => "FAILED",
+            MessageStatus::Pending => "PENDING",
+            MessageStatus::Cancelled => "CANCELLED",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -213,6 +677,10 @@ impl MessageStatus {
             "CONFIRMED_BY_ALL" => Some(Self::ConfirmedByAll),
             "RECEIVING" => Some(Self::Receiving),
             "RECEIVED" => Some(Self::Received),
+            "WAITING_FOR_KEY_EXCHANGE" => Some(Self::WaitingForKeyExchange),
+            "FAILED" => Some(Self::Failed),
+            "PENDING" => Some(Self::Pending),
+            "CANCELLED" => Some(Self::Cancelled),
             _ => None,
         }
     }
@@ -276,3 +744,65 @@ impl GroupEventType {
         }
     }
 }
+/// source of a search result
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SearchSource {
+    /// chat conversation
+    ChatMessage = 0,
+    /// feed
+    FeedMessage = 1,
+}
+impl SearchSource {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SearchSource::ChatMessage => "CHAT_MESSAGE",
+            SearchSource::FeedMessage => "FEED_MESSAGE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "CHAT_MESSAGE" => Some(Self::ChatMessage),
+            "FEED_MESSAGE" => Some(Self::FeedMessage),
+            _ => None,
+        }
+    }
+}
+/// human readable export format
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ExportFormat {
+    /// only the JSON archive
+    Json = 0,
+    /// additional Markdown document
+    Markdown = 1,
+    /// additional HTML document
+    Html = 2,
+}
+impl ExportFormat {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Markdown => "MARKDOWN",
+            ExportFormat::Html => "HTML",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "JSON" => Some(Self::Json),
+            "MARKDOWN" => Some(Self::Markdown),
+            "HTML" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
@@ -3,7 +3,7 @@
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ChatFile {
     /// message type
-    #[prost(oneof = "chat_file::Message", tags = "1, 2, 3, 4")]
+    #[prost(oneof = "chat_file::Message", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9")]
     pub message: ::core::option::Option<chat_file::Message>,
 }
 /// Nested message and enum types in `ChatFile`.
@@ -33,6 +33,33 @@ pub mod chat_file {
         /// delivers the requested list of
         #[prost(message, tag = "4")]
         FileHistoryResponse(super::FileHistoryResponse),
+        /// file progress
+        ///
+        /// libqaul informs the UI about the progress
+        /// of a file that is being sent or received
+        #[prost(message, tag = "5")]
+        FileProgress(super::FileProgress),
+        /// cancel file request
+        ///
+        /// cancel sending or receiving a file
+        #[prost(message, tag = "6")]
+        FileCancel(super::FileCancelRequest),
+        /// accept file request
+        ///
+        /// accept a file that is waiting for manual acceptance
+        #[prost(message, tag = "7")]
+        FileAccept(super::FileAcceptRequest),
+        /// file receive policy request
+        ///
+        /// request the file receive policy of the user account
+        #[prost(message, tag = "8")]
+        FilePolicyRequest(super::FilePolicyRequest),
+        /// file receive policy
+        ///
+        /// sent by the UI to change the file receive policy,
+        /// and by libqaul as response to the policy request
+        #[prost(message, tag = "9")]
+        FilePolicy(super::FilePolicy),
     }
 }
 /// Send File Request
@@ -70,6 +97,87 @@ pub struct SendFileResponse {
     #[prost(uint64, tag = "3")]
     pub file_id: u64,
 }
+/// File Progress
+///
+/// Progress of a file that is being sent or received.
+/// An outgoing data chunk is done, once it has been
+/// scheduled for sending to all group members.
+/// An incoming data chunk is done, once it has been
+/// received.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FileProgress {
+    /// file id
+    #[prost(uint64, tag = "1")]
+    pub file_id: u64,
+    /// group id
+    #[prost(bytes = "vec", tag = "2")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// number of done file data chunks
+    #[prost(uint32, tag = "3")]
+    pub chunks_done: u32,
+    /// total number of file data chunks
+    #[prost(uint32, tag = "4")]
+    pub chunks_total: u32,
+    /// number of done bytes
+    #[prost(uint64, tag = "5")]
+    pub bytes_done: u64,
+    /// file size in bytes
+    #[prost(uint64, tag = "6")]
+    pub file_size: u64,
+    /// the file is received
+    #[prost(bool, tag = "7")]
+    pub incoming: bool,
+    /// average transfer rate in bytes per second
+    #[prost(uint64, tag = "8")]
+    pub rate: u64,
+}
+/// Cancel File Request
+///
+/// Cancels sending or receiving a file.
+/// The other side of the transfer is informed.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FileCancelRequest {
+    /// file id
+    #[prost(uint64, tag = "1")]
+    pub file_id: u64,
+}
+/// Accept File Request
+///
+/// Accepts a file that is waiting for manual acceptance.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FileAcceptRequest {
+    /// file id
+    #[prost(uint64, tag = "1")]
+    pub file_id: u64,
+}
+/// File Receive Policy Request
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FilePolicyRequest {}
+/// File Receive Policy
+///
+/// Decides which incoming files are received automatically.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FilePolicy {
+    /// maximal size in bytes of automatically accepted files
+    ///
+    /// bigger files need to be accepted manually,
+    /// 0 means no limit
+    #[prost(uint64, tag = "1")]
+    pub max_auto_accept_size: u64,
+    /// only receive files from verified users
+    ///
+    /// files of all other users are rejected
+    #[prost(bool, tag = "2")]
+    pub trusted_only: bool,
+    /// all files need to be accepted manually
+    #[prost(bool, tag = "3")]
+    pub manual_accept: bool,
+}
 /// File History Request
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -94,9 +202,9 @@ pub struct FileHistoryEntry {
     /// file extension
     #[prost(string, tag = "3")]
     pub file_extension: ::prost::alloc::string::String,
-    /// file size
-    #[prost(uint32, tag = "4")]
-    pub file_size: u32,
+    /// file size in bytes
+    #[prost(uint64, tag = "4")]
+    pub file_size: u64,
     /// file description
     #[prost(string, tag = "5")]
     pub file_description: ::prost::alloc::string::String,
@@ -109,6 +217,12 @@ pub struct FileHistoryEntry {
     /// group id
     #[prost(string, tag = "8")]
     pub group_id: ::prost::alloc::string::String,
+    /// the file content has been verified with its hash
+    #[prost(bool, tag = "9")]
+    pub verified: bool,
+    /// state of the file transfer
+    #[prost(enumeration = "FileState", tag = "10")]
+    pub state: i32,
 }
 /// File History Response
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -127,3 +241,56 @@ pub struct FileHistoryResponse {
     #[prost(message, repeated, tag = "4")]
     pub histories: ::prost::alloc::vec::Vec<FileHistoryEntry>,
 }
+/// File State
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum FileState {
+    /// the file is being sent
+    Sending = 0,
+    /// the file has been sent
+    Sent = 1,
+    /// the reception has been confirmed
+    Confirmed = 2,
+    /// all group members confirmed the reception
+    ConfirmedByAll = 3,
+    /// the file is being received
+    Receiving = 4,
+    /// the file has been received
+    Received = 5,
+    /// the file is waiting for manual acceptance
+    Pending = 6,
+    /// the transfer has been cancelled
+    Cancelled = 7,
+}
+impl FileState {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            FileState::Sending => "SENDING",
+            FileState::Sent => "SENT",
+            FileState::Confirmed => "CONFIRMED",
+            FileState::ConfirmedByAll => "CONFIRMED_BY_ALL",
+            FileState::Receiving => "RECEIVING",
+            FileState::Received => "RECEIVED",
+            FileState::Pending => "PENDING",
+            FileState::Cancelled => "CANCELLED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SENDING" => Some(Self::Sending),
+            "SENT" => Some(Self::Sent),
+            "CONFIRMED" => Some(Self::Confirmed),
+            "CONFIRMED_BY_ALL" => Some(Self::ConfirmedByAll),
+            "RECEIVING" => Some(Self::Receiving),
+            "RECEIVED" => Some(Self::Received),
+            "PENDING" => Some(Self::Pending),
+            "CANCELLED" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
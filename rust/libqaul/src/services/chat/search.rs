@@ -0,0 +1,398 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Chat and Feed Search
+//!
+//! Every user account has an inverted index of the words of its
+//! chat messages and of the feed messages.
+//! The index is updated whenever a message is saved, edited or deleted.
+//! Messages that were saved before the index existed, are indexed
+//! in a background thread, when the index of a user account is
+//! opened for the first time.
+//!
+//! All words of a query need to be found in a message.
+//! A word of the query matches all indexed words starting with it.
+
+use libp2p::PeerId;
+use prost::Message;
+use sled;
+use state::InitCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::RwLock;
+use std::thread;
+
+use super::rpc_proto;
+use super::ChatStorage;
+use crate::node::user_accounts::UserAccounts;
+use crate::services::feed::{Feed, FeedMessageData};
+use crate::services::group::GroupStorage;
+use crate::storage::database::DataBase;
+
+/// minimal length of an indexed word in characters
+const MIN_WORD_LENGTH: usize = 2;
+
+/// maximal length of an indexed word in characters
+///
+/// Longer words are indexed by their beginning.
+const MAX_WORD_LENGTH: usize = 32;
+
+/// default number of search results
+const DEFAULT_LIMIT: u32 = 20;
+
+/// maximal number of search results
+const MAX_LIMIT: u32 = 100;
+
+/// key marking an index, to which all existing messages were added
+///
+/// Indexed words never start with a zero byte.
+const INDEXED_KEY: &[u8] = b"\0indexed";
+
+/// document prefix of chat messages
+const CHAT_DOCUMENT: u8 = b'c';
+
+/// document prefix of feed messages
+const FEED_DOCUMENT: u8 = b'f';
+
+/// mutable state of the search indexes
+static SEARCH: InitCell<RwLock<ChatSearch>> = InitCell::new();
+
+/// Chat and Feed Search Structure
+pub struct ChatSearch {
+    /// index data base trees per user account
+    ///
+    /// key: {word}\0{document}
+    ///
+    /// document: 'c'{chat message db key} or 'f'{feed message index}
+    ///
+    /// value: empty
+    db_ref: BTreeMap<Vec<u8>, sled::Tree>,
+}
+
+impl ChatSearch {
+    /// initialize search indexes
+    pub fn init() {
+        let search = ChatSearch {
+            db_ref: BTreeMap::new(),
+        };
+        SEARCH.set(RwLock::new(search));
+    }
+
+    /// Add a chat message to the index of a user account
+    pub fn index_chat_message(account_id: &PeerId, db_key: &Vec<u8>, content: &Vec<u8>) {
+        if let Some(text) = Self::content_text(content) {
+            let tree = Self::get_db_ref(account_id.to_owned());
+            Self::index(&tree, &Self::chat_document(db_key), &text);
+        }
+    }
+
+    /// Remove a chat message from the index of a user account
+    ///
+    /// The content is the content the message was indexed with.
+    pub fn unindex_chat_message(account_id: &PeerId, db_key: &Vec<u8>, content: &Vec<u8>) {
+        if let Some(text) = Self::content_text(content) {
+            let tree = Self::get_db_ref(account_id.to_owned());
+            Self::unindex(&tree, &Self::chat_document(db_key), &text);
+        }
+    }
+
+    /// Add a feed message to the indexes of all user accounts
+    pub fn index_feed_message(index: u64, content: &str) {
+        for account_id in UserAccounts::get_ids() {
+            let tree = Self::get_db_ref(account_id);
+            Self::index(&tree, &Self::feed_document(index), content);
+        }
+    }
+
+    /// Search the chat messages and the feed messages of a user account
+    pub fn search(
+        account_id: PeerId,
+        request: rpc_proto::ChatSearchRequest,
+    ) -> rpc_proto::ChatSearchResult {
+        let mut result = rpc_proto::ChatSearchResult {
+            query: request.query.clone(),
+            total: 0,
+            offset: request.offset,
+            hits: Vec::new(),
+        };
+
+        let words = Self::words(&request.query);
+        if words.is_empty() {
+            return result;
+        }
+
+        let tree = Self::get_db_ref(account_id);
+        let mut group_names: BTreeMap<Vec<u8>, String> = BTreeMap::new();
+        let mut hits: Vec<rpc_proto::ChatSearchHit> = Vec::new();
+
+        for document in Self::find(&tree, &words) {
+            let hit = match document.split_first() {
+                Some((&CHAT_DOCUMENT, db_key)) => {
+                    match ChatStorage::get_message_by_key(&account_id, db_key) {
+                        Some(message) => {
+                            if !request.group_id.is_empty() && message.group_id != request.group_id
+                            {
+                                continue;
+                            }
+                            let group_name = group_names
+                                .entry(message.group_id.clone())
+                                .or_insert_with(|| {
+                                    GroupStorage::get_group(account_id, message.group_id.clone())
+                                        .map(|group| group.name)
+                                        .unwrap_or_default()
+                                })
+                                .clone();
+                            rpc_proto::ChatSearchHit {
+                                source: rpc_proto::SearchSource::ChatMessage as i32,
+                                group_name,
+                                message: Some(message),
+                            }
+                        }
+                        None => continue,
+                    }
+                }
+                Some((&FEED_DOCUMENT, index_bytes)) => {
+                    if !request.include_feed || !request.group_id.is_empty() {
+                        continue;
+                    }
+                    let index = match index_bytes.try_into() {
+                        Ok(arr) => u64::from_be_bytes(arr),
+                        Err(_) => continue,
+                    };
+                    match Feed::get_message_data(index) {
+                        Some(message_data) => rpc_proto::ChatSearchHit {
+                            source: rpc_proto::SearchSource::FeedMessage as i32,
+                            group_name: String::new(),
+                            message: Some(Self::feed_to_chat_message(message_data)),
+                        },
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            // filter by sender and sending time
+            if let Some(message) = &hit.message {
+                if !request.sender_id.is_empty() && message.sender_id != request.sender_id {
+                    continue;
+                }
+                if request.sent_after > 0 && message.sent_at < request.sent_after {
+                    continue;
+                }
+                if request.sent_before > 0 && message.sent_at > request.sent_before {
+                    continue;
+                }
+            }
+
+            hits.push(hit);
+        }
+
+        // newest messages first
+        hits.sort_by_key(|hit| {
+            std::cmp::Reverse(hit.message.as_ref().map(|message| message.sent_at))
+        });
+
+        let limit = match request.limit {
+            0 => DEFAULT_LIMIT,
+            limit => limit.min(MAX_LIMIT),
+        };
+        result.total = hits.len() as u32;
+        result.hits = hits
+            .into_iter()
+            .skip(request.offset as usize)
+            .take(limit as usize)
+            .collect();
+
+        result
+    }
+
+    /// split a text into its distinct lower case words
+    fn words(text: &str) -> BTreeSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.chars().count() >= MIN_WORD_LENGTH)
+            .map(|word| word.to_lowercase().chars().take(MAX_WORD_LENGTH).collect())
+            .collect()
+    }
+
+    /// get the searchable text of a chat content message
+    fn content_text(content: &Vec<u8>) -> Option<String> {
+        match rpc_proto::ChatContentMessage::decode(&content[..]) {
+            Ok(content_message) => match content_message.message {
+                Some(rpc_proto::chat_content_message::Message::ChatContent(chat_content)) => {
+                    Some(chat_content.text)
+                }
+                Some(rpc_proto::chat_content_message::Message::Reply(reply)) => Some(reply.text),
+                Some(rpc_proto::chat_content_message::Message::FileContent(file_content)) => {
+                    Some(file_content.file_name + " " + file_content.file_description.as_str())
+                }
+                _ => None,
+            },
+            Err(_) => None,
+        }
+    }
+
+    /// create the index document of a chat message
+    fn chat_document(db_key: &Vec<u8>) -> Vec<u8> {
+        let mut document = vec![CHAT_DOCUMENT];
+        document.extend_from_slice(db_key);
+        document
+    }
+
+    /// create the index document of a feed message
+    fn feed_document(index: u64) -> Vec<u8> {
+        let mut document = vec![FEED_DOCUMENT];
+        document.extend_from_slice(&index.to_be_bytes());
+        document
+    }
+
+    /// create the index key of a word of a document
+    fn index_key(word: &str, document: &Vec<u8>) -> Vec<u8> {
+        let mut key = word.as_bytes().to_vec();
+        key.push(0);
+        key.extend_from_slice(document);
+        key
+    }
+
+    /// add the words of a text to the index
+    fn index(tree: &sled::Tree, document: &Vec<u8>, text: &str) {
+        Self::insert_words(tree, document, text);
+        if let Err(e) = tree.flush() {
+            log::error!("Error search index flush: {}", e);
+        }
+    }
+
+    /// add the words of a text to the index without flushing it
+    fn insert_words(tree: &sled::Tree, document: &Vec<u8>, text: &str) {
+        for word in Self::words(text) {
+            if let Err(e) = tree.insert(Self::index_key(&word, document), Vec::new()) {
+                log::error!("Error saving search index: {}", e);
+            }
+        }
+    }
+
+    /// remove the words of a text from the index
+    fn unindex(tree: &sled::Tree, document: &Vec<u8>, text: &str) {
+        for word in Self::words(text) {
+            if let Err(e) = tree.remove(Self::index_key(&word, document)) {
+                log::error!("Error removing from search index: {}", e);
+            }
+        }
+        if let Err(e) = tree.flush() {
+            log::error!("Error search index flush: {}", e);
+        }
+    }
+
+    /// find the documents containing all words
+    fn find(tree: &sled::Tree, words: &BTreeSet<String>) -> BTreeSet<Vec<u8>> {
+        let mut found: Option<BTreeSet<Vec<u8>>> = None;
+
+        for word in words {
+            let mut documents: BTreeSet<Vec<u8>> = BTreeSet::new();
+            for key in tree
+                .scan_prefix(word.as_bytes())
+                .keys()
+                .filter_map(|key| key.ok())
+            {
+                if let Some(position) = key.iter().position(|byte| *byte == 0) {
+                    documents.insert(key[position + 1..].to_vec());
+                }
+            }
+
+            let documents = match found {
+                Some(found) => found.intersection(&documents).cloned().collect(),
+                None => documents,
+            };
+            if documents.is_empty() {
+                return documents;
+            }
+            found = Some(documents);
+        }
+
+        found.unwrap_or_default()
+    }
+
+    /// convert a feed message to a chat message for the search results
+    fn feed_to_chat_message(message_data: FeedMessageData) -> rpc_proto::ChatMessage {
+        let content = rpc_proto::ChatContentMessage {
+            message: Some(rpc_proto::chat_content_message::Message::ChatContent(
+                rpc_proto::ChatContent {
+                    text: message_data.content,
+                    edited_at: 0,
+                },
+            )),
+        };
+
+        rpc_proto::ChatMessage {
+            index: message_data.index,
+            sender_id: message_data.sender_id,
+            message_id: message_data.message_id,
+            status: rpc_proto::MessageStatus::Received as i32,
+            message_reception_confirmed: Vec::new(),
+            group_id: Vec::new(),
+            sent_at: message_data.timestamp_sent,
+            received_at: message_data.timestamp_received,
+            content: content.encode_to_vec(),
+            reply_to: Vec::new(),
+            reactions: Vec::new(),
+        }
+    }
+
+    /// add all existing chat and feed messages to the index
+    ///
+    /// The index is flushed once, after all messages were added.
+    fn index_all(account_id: PeerId, tree: &sled::Tree) {
+        log::trace!("indexing messages of {}", account_id.to_base58());
+
+        ChatStorage::for_each_message(account_id, |db_key, message| {
+            if let Some(text) = Self::content_text(&message.content) {
+                Self::insert_words(tree, &Self::chat_document(&db_key.to_vec()), &text);
+            }
+        });
+        Feed::for_each_message(|message_data| {
+            Self::insert_words(
+                tree,
+                &Self::feed_document(message_data.index),
+                &message_data.content,
+            );
+        });
+
+        if let Err(e) = tree.insert(INDEXED_KEY, Vec::new()) {
+            log::error!("Error saving search index: {}", e);
+        }
+        if let Err(e) = tree.flush() {
+            log::error!("Error search index flush: {}", e);
+        }
+    }
+
+    /// get search index data base tree of a user account
+    fn get_db_ref(account_id: PeerId) -> sled::Tree {
+        // check if user account tree is already open
+        {
+            let state = SEARCH.get().read().unwrap();
+            if let Some(tree) = state.db_ref.get(&account_id.to_bytes()) {
+                return tree.clone();
+            }
+        }
+
+        // open tree
+        let db = DataBase::get_user_db(account_id);
+        let tree: sled::Tree = db.open_tree("search_index").unwrap();
+
+        // add tree to state
+        {
+            let mut state = SEARCH.get().write().unwrap();
+            if let Some(tree) = state.db_ref.get(&account_id.to_bytes()) {
+                return tree.clone();
+            }
+            state.db_ref.insert(account_id.to_bytes(), tree.clone());
+        }
+
+        // index the existing messages in the background,
+        // new messages are indexed in the meantime
+        if !tree.contains_key(INDEXED_KEY).unwrap_or(false) {
+            let backfill_tree = tree.clone();
+            thread::spawn(move || Self::index_all(account_id, &backfill_tree));
+        }
+
+        tree
+    }
+}
@@ -13,11 +13,18 @@ use std::collections::BTreeMap;
 use std::sync::RwLock;
 
 use super::rpc_proto;
-use crate::services::group::{group_id::GroupId, GroupStorage};
+use super::ChatSearch;
+use crate::services::group::{group_id::GroupId, proto_rpc::GroupMemberState, GroupStorage};
 use crate::storage::database::DataBase;
 use crate::utilities::timestamp::Timestamp;
 use prost::Message;
 
+/// maximal length of the quoted excerpt of a reply in characters
+const QUOTE_LENGTH: usize = 100;
+
+/// maximal length of a reaction emoji in bytes
+const MAX_REACTION_LENGTH: usize = 32;
+
 /// mutable state of chat messages
 static CHAT: InitCell<RwLock<ChatStorage>> = InitCell::new();
 
@@ -32,6 +39,31 @@ pub struct ChatAccountDb {
     ///
     /// value: Vec<u8> of db key
     pub message_ids: sled::Tree,
+    /// edit history of chat messages
+    ///
+    /// key: {message_id}{edited_at}
+    ///
+    /// value: Vec<u8> protobuf of rpc_proto::ChatEditEntry
+    pub edits: sled::Tree,
+    /// emoji reactions on chat messages
+    ///
+    /// key: {message_id}
+    ///
+    /// value: Vec<u8> bincode of `BTreeMap<String, Vec<Vec<u8>>>`,
+    /// emoji => user ids
+    pub reactions: sled::Tree,
+    /// received messages that were read by this user account
+    ///
+    /// key: {message_id}
+    ///
+    /// value: read time as big endian u64
+    pub read: sled::Tree,
+    /// read receipts of the messages sent by this user account
+    ///
+    /// key: {message_id}{user_id}
+    ///
+    /// value: read time as big endian u64
+    pub read_receipts: sled::Tree,
 }
 
 /// qaul Chat Conversation Storage
@@ -152,6 +184,8 @@ impl ChatStorage {
             sent_at,
             received_at,
             content: content.encode_to_vec(),
+            reply_to: Vec::new(),
+            reactions: Vec::new(),
         };
 
         // save message in data base
@@ -177,6 +211,126 @@ impl ChatStorage {
                 log::error!("Error chat message_ids flush: {}", e);
             }
         }
+
+        // add message to the search index
+        ChatSearch::index_chat_message(account_id, &db_key, &chat_message.content);
+    }
+
+    /// Import a chat message from an export
+    ///
+    /// The message keeps its status, timestamps, confirmations,
+    /// reactions and edit history, and is appended to its conversation.
+    /// Returns false if a message with this message id already exists.
+    pub fn import_message(
+        account_id: &PeerId,
+        mut chat_message: rpc_proto::ChatMessage,
+        edits: &Vec<rpc_proto::ChatEditEntry>,
+    ) -> bool {
+        // get data base of user account
+        let db_ref = Self::get_db_ref(account_id.clone());
+
+        // check if message_id already exists
+        if chat_message.message_id.len() > 0 {
+            if db_ref
+                .message_ids
+                .contains_key(&chat_message.message_id)
+                .unwrap()
+            {
+                return false;
+            }
+        }
+
+        // get next index
+        chat_message.index = Self::get_next_db_index(db_ref.clone(), &chat_message.group_id);
+
+        // create data base key
+        let db_key = Self::get_db_key_from_vec(&chat_message.group_id, chat_message.index);
+
+        // save edit history
+        if !edits.is_empty() {
+            for entry in edits {
+                let mut edit_key = chat_message.message_id.clone();
+                edit_key.extend_from_slice(&entry.edited_at.to_be_bytes());
+                if let Err(e) = db_ref.edits.insert(edit_key, entry.encode_to_vec()) {
+                    log::error!("Error saving chat edit to data base: {}", e);
+                }
+            }
+            if let Err(e) = db_ref.edits.flush() {
+                log::error!("Error chat edits flush: {}", e);
+            }
+        }
+
+        // save reactions
+        if !chat_message.reactions.is_empty() {
+            let reactions: BTreeMap<String, Vec<Vec<u8>>> = chat_message
+                .reactions
+                .iter()
+                .map(|reaction| (reaction.emoji.clone(), reaction.user_ids.clone()))
+                .collect();
+            let reactions_bytes = bincode::serialize(&reactions).unwrap();
+            if let Err(e) = db_ref
+                .reactions
+                .insert(chat_message.message_id.clone(), reactions_bytes)
+            {
+                log::error!("Error saving chat reactions to data base: {}", e);
+            }
+            if let Err(e) = db_ref.reactions.flush() {
+                log::error!("Error chat reactions flush: {}", e);
+            }
+        }
+
+        // save read receipts
+        for confirmation in &chat_message.message_reception_confirmed {
+            if confirmation.read_at > 0 {
+                let mut key = chat_message.message_id.clone();
+                key.extend(&confirmation.user_id);
+                if let Err(e) = db_ref
+                    .read_receipts
+                    .insert(key, &confirmation.read_at.to_be_bytes())
+                {
+                    log::error!("Error saving read receipt to data base: {}", e);
+                }
+            }
+        }
+        if let Err(e) = db_ref.read_receipts.flush() {
+            log::error!("Error chat read receipts flush: {}", e);
+        }
+
+        // mark received messages as read,
+        // no read receipts are sent for archived messages
+        if chat_message.sender_id != account_id.to_bytes() && chat_message.message_id.len() > 0 {
+            if let Err(e) = db_ref.read.insert(
+                chat_message.message_id.clone(),
+                chat_message.received_at.to_be_bytes().to_vec(),
+            ) {
+                log::error!("Error saving chat read state: {}", e);
+            }
+            if let Err(e) = db_ref.read.flush() {
+                log::error!("Error chat read flush: {}", e);
+            }
+        }
+
+        // save message in data base
+        Self::save_message_by_key(&db_ref, db_key.clone().into(), &chat_message);
+
+        // save message id in data base
+        if chat_message.message_id.len() > 0 {
+            if let Err(e) = db_ref
+                .message_ids
+                .insert(chat_message.message_id.clone(), db_key.clone())
+            {
+                log::error!("Error saving chat messageid to data base: {}", e);
+            }
+            // flush trees to disk
+            if let Err(e) = db_ref.message_ids.flush() {
+                log::error!("Error chat message_ids flush: {}", e);
+            }
+        }
+
+        // add message to the search index
+        ChatSearch::index_chat_message(account_id, &db_key, &chat_message.content);
+
+        true
     }
 
     /// updating chat message status as confirmed
@@ -192,20 +346,31 @@ impl ChatStorage {
             if let Some(chat_msg_bytes) = db_ref.messages.get(&key).unwrap() {
                 let mut chat_msg: rpc_proto::ChatMessage =
                     bincode::deserialize(&chat_msg_bytes).unwrap();
-                chat_msg.status = rpc_proto::MessageStatus::Confirmed as i32;
-                chat_msg.received_at = received_at;
 
-                // TODO: check if receiver already exists
+                // ignore duplicate confirmations
+                if chat_msg
+                    .message_reception_confirmed
+                    .iter()
+                    .any(|confirmation| confirmation.user_id == receiver_id.to_bytes())
+                {
+                    return;
+                }
+                chat_msg.received_at = received_at;
 
                 // receiving user
                 let confirmation = rpc_proto::MessageReceptionConfirmed {
                     user_id: receiver_id.to_bytes(),
                     confirmed_at: received_at,
+                    read_at: 0,
                 };
                 chat_msg.message_reception_confirmed.push(confirmation);
 
-                // TODO: check if it was received by everyone
-                //       set received_by_all flag if yes
+                // check if it was received by everyone
+                if Self::confirmed_by_all(&account_id, &chat_msg) {
+                    chat_msg.status = rpc_proto::MessageStatus::ConfirmedByAll as i32;
+                } else {
+                    chat_msg.status = rpc_proto::MessageStatus::Confirmed as i32;
+                }
 
                 // save message in data base
                 let chat_msg_bytes = bincode::serialize(&chat_msg).unwrap();
@@ -220,6 +385,111 @@ impl ChatStorage {
         }
     }
 
+    /// check if all active group members confirmed a message
+    fn confirmed_by_all(account_id: &PeerId, chat_msg: &rpc_proto::ChatMessage) -> bool {
+        let group = match GroupStorage::get_group(account_id.to_owned(), chat_msg.group_id.clone())
+        {
+            Some(group) => group,
+            None => return false,
+        };
+
+        group
+            .members
+            .values()
+            .filter(|member| {
+                member.state == GroupMemberState::Activated as i32
+                    && member.user_id != account_id.to_bytes()
+            })
+            .all(|member| {
+                chat_msg
+                    .message_reception_confirmed
+                    .iter()
+                    .any(|confirmation| confirmation.user_id == member.user_id)
+            })
+    }
+
+    /// Mark the received messages of a conversation as read
+    ///
+    /// Returns the ids of the messages, that were not read before,
+    /// by their sender.
+    pub fn mark_read(
+        account_id: &PeerId,
+        messages: &Vec<rpc_proto::ChatMessage>,
+    ) -> BTreeMap<Vec<u8>, Vec<Vec<u8>>> {
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+        let read_at = Timestamp::get_timestamp();
+
+        let mut unread: BTreeMap<Vec<u8>, Vec<Vec<u8>>> = BTreeMap::new();
+        for message in messages {
+            if message.sender_id == account_id.to_bytes() || message.message_id.is_empty() {
+                continue;
+            }
+            match db_ref.read.contains_key(&message.message_id) {
+                Ok(false) => {}
+                _ => continue,
+            }
+            if let Err(e) = db_ref
+                .read
+                .insert(message.message_id.clone(), read_at.to_be_bytes().to_vec())
+            {
+                log::error!("Error saving chat read state: {}", e);
+                continue;
+            }
+            unread
+                .entry(message.sender_id.clone())
+                .or_insert(Vec::new())
+                .push(message.message_id.clone());
+        }
+        if let Err(e) = db_ref.read.flush() {
+            log::error!("Error chat read flush: {}", e);
+        }
+
+        unread
+    }
+
+    /// Save the read receipt of a group member for a message we sent
+    ///
+    /// A read message is confirmed as received too,
+    /// if its reception confirmation is missing.
+    pub fn update_read(
+        account_id: &PeerId,
+        reader_id: &PeerId,
+        message_id: &Vec<u8>,
+        read_at: u64,
+    ) -> Result<(), String> {
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+        let (_key, chat_msg) = Self::get_message_by_id(&db_ref, message_id)?;
+
+        if chat_msg.sender_id != account_id.to_bytes() {
+            return Err("read receipt for a message we didn't send".to_string());
+        }
+        match GroupStorage::get_group(account_id.to_owned(), chat_msg.group_id.clone()) {
+            Some(group) if group.is_member(&reader_id.to_bytes()) => {}
+            _ => return Err("read receipt from non member".to_string()),
+        }
+
+        let mut key = message_id.clone();
+        key.extend(reader_id.to_bytes());
+        if let Err(e) = db_ref
+            .read_receipts
+            .insert(key, read_at.to_be_bytes().to_vec())
+        {
+            log::error!("Error saving chat read receipt: {}", e);
+        }
+        if let Err(e) = db_ref.read_receipts.flush() {
+            log::error!("Error chat read receipts flush: {}", e);
+        }
+
+        Self::update_confirmation(
+            account_id.to_owned(),
+            reader_id.to_owned(),
+            message_id,
+            read_at,
+        );
+
+        Ok(())
+    }
+
     /// update message status
     pub fn udate_status(
         account_id: &PeerId,
@@ -247,8 +517,401 @@ impl ChatStorage {
         }
     }
 
+    /// update message status, if it currently has a specific status
+    ///
+    /// This prevents the status of a group message from being reset,
+    /// when it was already confirmed by other group members.
+    pub fn update_status_from(
+        account_id: &PeerId,
+        message_id: &Vec<u8>,
+        from: rpc_proto::MessageStatus,
+        to: rpc_proto::MessageStatus,
+    ) {
+        // get data base of user account
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+        if let Some(key) = db_ref.message_ids.get(message_id).unwrap() {
+            if let Some(chat_msg_fromdb) = db_ref.messages.get(&key).unwrap() {
+                let mut chat_msg: rpc_proto::ChatMessage =
+                    bincode::deserialize(&chat_msg_fromdb).unwrap();
+                if chat_msg.status != from as i32 {
+                    return;
+                }
+                chat_msg.status = to as i32;
+
+                // save message in data base
+                let chat_msg_todb = bincode::serialize(&chat_msg).unwrap();
+                if let Err(e) = db_ref.messages.insert(key.clone(), chat_msg_todb) {
+                    log::error!("Error saving chat message to data base: {}", e);
+                }
+                // flush trees to disk
+                if let Err(e) = db_ref.messages.flush() {
+                    log::error!("Error chat messages flush: {}", e);
+                }
+            }
+        }
+    }
+
+    /// set message status to failed, after its delivery failed
+    ///
+    /// Messages that were already confirmed by a group member
+    /// keep their status.
+    /// Returns the group id of the message.
+    pub fn on_delivery_failed(account_id: &PeerId, message_id: &Vec<u8>) -> Option<Vec<u8>> {
+        // get data base of user account
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+        let key = db_ref.message_ids.get(message_id).unwrap()?;
+        let chat_msg_fromdb = db_ref.messages.get(&key).unwrap()?;
+        let mut chat_msg: rpc_proto::ChatMessage = bincode::deserialize(&chat_msg_fromdb).unwrap();
+
+        if chat_msg.status == rpc_proto::MessageStatus::Confirmed as i32
+            || chat_msg.status == rpc_proto::MessageStatus::ConfirmedByAll as i32
+        {
+            return Some(chat_msg.group_id);
+        }
+        chat_msg.status = rpc_proto::MessageStatus::Failed as i32;
+
+        // save message in data base
+        let chat_msg_todb = bincode::serialize(&chat_msg).unwrap();
+        if let Err(e) = db_ref.messages.insert(key.clone(), chat_msg_todb) {
+            log::error!("Error saving chat message to data base: {}", e);
+        }
+        // flush trees to disk
+        if let Err(e) = db_ref.messages.flush() {
+            log::error!("Error chat messages flush: {}", e);
+        }
+
+        Some(chat_msg.group_id)
+    }
+
+    /// Edit the text of a chat message
+    ///
+    /// Only the original sender of a message can edit it.
+    /// The previous version of the message is saved to the edit history.
+    /// Edits that are older than the last applied edit are ignored.
+    ///
+    /// Returns the group id of the message.
+    pub fn edit_message(
+        account_id: &PeerId,
+        sender_id: &PeerId,
+        message_id: &Vec<u8>,
+        text: String,
+        edited_at: u64,
+    ) -> Result<Vec<u8>, String> {
+        // get data base of user account
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+        let (key, mut chat_msg) = Self::get_message_by_id(&db_ref, message_id)?;
+
+        if chat_msg.sender_id != sender_id.to_bytes() {
+            return Err("only the sender can edit a message".to_string());
+        }
+
+        // get current text
+        let mut content_message = match rpc_proto::ChatContentMessage::decode(&chat_msg.content[..])
+        {
+            Ok(content_message) => content_message,
+            Err(e) => return Err(e.to_string()),
+        };
+        let (current_text, current_edited_at) = match content_message.message {
+            Some(rpc_proto::chat_content_message::Message::ChatContent(ref mut content)) => {
+                (&mut content.text, &mut content.edited_at)
+            }
+            Some(rpc_proto::chat_content_message::Message::Reply(ref mut reply)) => {
+                (&mut reply.text, &mut reply.edited_at)
+            }
+            _ => return Err("only text messages can be edited".to_string()),
+        };
+        if edited_at <= *current_edited_at {
+            return Err("message was already edited later".to_string());
+        }
+
+        // save previous version to the edit history
+        let entry = rpc_proto::ChatEditEntry {
+            text: current_text.clone(),
+            edited_at: *current_edited_at,
+        };
+        let mut edit_key = message_id.clone();
+        edit_key.extend_from_slice(&current_edited_at.to_be_bytes());
+        if let Err(e) = db_ref.edits.insert(edit_key, entry.encode_to_vec()) {
+            log::error!("Error saving chat edit to data base: {}", e);
+        }
+        if let Err(e) = db_ref.edits.flush() {
+            log::error!("Error chat edits flush: {}", e);
+        }
+
+        // update message
+        *current_text = text;
+        *current_edited_at = edited_at;
+        let previous_content = chat_msg.content;
+        chat_msg.content = content_message.encode_to_vec();
+        Self::save_message_by_key(&db_ref, key.clone(), &chat_msg);
+
+        // update search index
+        ChatSearch::unindex_chat_message(account_id, &key.to_vec(), &previous_content);
+        ChatSearch::index_chat_message(account_id, &key.to_vec(), &chat_msg.content);
+
+        Ok(chat_msg.group_id)
+    }
+
+    /// Delete a chat message for everyone
+    ///
+    /// Only the original sender of a message can delete it.
+    /// The content of the message is replaced by a deletion
+    /// notice and its edit history and reactions are removed.
+    ///
+    /// Returns the group id of the message.
+    pub fn delete_message(
+        account_id: &PeerId,
+        sender_id: &PeerId,
+        message_id: &Vec<u8>,
+        deleted_at: u64,
+    ) -> Result<Vec<u8>, String> {
+        // get data base of user account
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+        let (key, mut chat_msg) = Self::get_message_by_id(&db_ref, message_id)?;
+
+        if chat_msg.sender_id != sender_id.to_bytes() {
+            return Err("only the sender can delete a message".to_string());
+        }
+
+        match rpc_proto::ChatContentMessage::decode(&chat_msg.content[..]) {
+            Ok(rpc_proto::ChatContentMessage {
+                message: Some(rpc_proto::chat_content_message::Message::ChatContent(_)),
+            })
+            | Ok(rpc_proto::ChatContentMessage {
+                message: Some(rpc_proto::chat_content_message::Message::Reply(_)),
+            })
+            | Ok(rpc_proto::ChatContentMessage {
+                message: Some(rpc_proto::chat_content_message::Message::FileContent(_)),
+            }) => {}
+            Ok(rpc_proto::ChatContentMessage {
+                message: Some(rpc_proto::chat_content_message::Message::MessageDeleted(_)),
+            }) => return Err("message is already deleted".to_string()),
+            _ => return Err("message can't be deleted".to_string()),
+        }
+
+        // remove edit history
+        let edit_keys: Vec<sled::IVec> = db_ref
+            .edits
+            .scan_prefix(message_id)
+            .keys()
+            .filter_map(|key| key.ok())
+            .collect();
+        for edit_key in edit_keys {
+            if let Err(e) = db_ref.edits.remove(edit_key) {
+                log::error!("Error removing chat edit: {}", e);
+            }
+        }
+        if let Err(e) = db_ref.edits.flush() {
+            log::error!("Error chat edits flush: {}", e);
+        }
+
+        // remove reactions
+        if let Err(e) = db_ref.reactions.remove(message_id) {
+            log::error!("Error removing chat reactions: {}", e);
+        }
+
+        // remove message from the search index
+        ChatSearch::unindex_chat_message(account_id, &key.to_vec(), &chat_msg.content);
+
+        // replace content
+        chat_msg.content = rpc_proto::ChatContentMessage {
+            message: Some(rpc_proto::chat_content_message::Message::MessageDeleted(
+                rpc_proto::MessageDeleted { deleted_at },
+            )),
+        }
+        .encode_to_vec();
+        Self::save_message_by_key(&db_ref, key, &chat_msg);
+
+        Ok(chat_msg.group_id)
+    }
+
+    /// Get the edit history of a chat message
+    pub fn get_edit_history(account_id: PeerId, message_id: Vec<u8>) -> rpc_proto::ChatEditHistory {
+        let db_ref = Self::get_db_ref(account_id);
+
+        let mut edits: Vec<rpc_proto::ChatEditEntry> = Vec::new();
+        for res in db_ref.edits.scan_prefix(&message_id) {
+            match res {
+                Ok((_key, entry_bytes)) => {
+                    match rpc_proto::ChatEditEntry::decode(&entry_bytes[..]) {
+                        Ok(entry) => edits.push(entry),
+                        Err(e) => log::error!("chat edit decoding error: {}", e),
+                    }
+                }
+                Err(e) => {
+                    log::error!("get_edit_history error: {}", e);
+                }
+            }
+        }
+
+        rpc_proto::ChatEditHistory { message_id, edits }
+    }
+
+    /// Get a chat message by its message id
+    pub fn get_message(
+        account_id: &PeerId,
+        message_id: &Vec<u8>,
+    ) -> Result<rpc_proto::ChatMessage, String> {
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+        let (_key, chat_msg) = Self::get_message_by_id(&db_ref, message_id)?;
+        Ok(chat_msg)
+    }
+
+    /// Get the quote of a message for a reply
+    ///
+    /// Returns the sender id of the message and a quoted excerpt
+    /// of its content.
+    pub fn get_quote(
+        account_id: &PeerId,
+        group_id: &Vec<u8>,
+        message_id: &Vec<u8>,
+    ) -> Result<(Vec<u8>, String), String> {
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+        let (_key, chat_msg) = Self::get_message_by_id(&db_ref, message_id)?;
+
+        if chat_msg.group_id != *group_id {
+            return Err("message is not in this group".to_string());
+        }
+
+        let quote = match rpc_proto::ChatContentMessage::decode(&chat_msg.content[..]) {
+            Ok(rpc_proto::ChatContentMessage {
+                message: Some(rpc_proto::chat_content_message::Message::ChatContent(content)),
+            }) => content.text,
+            Ok(rpc_proto::ChatContentMessage {
+                message: Some(rpc_proto::chat_content_message::Message::Reply(reply)),
+            }) => reply.text,
+            Ok(rpc_proto::ChatContentMessage {
+                message: Some(rpc_proto::chat_content_message::Message::FileContent(file)),
+            }) => file.file_name,
+            _ => return Err("message can't be replied to".to_string()),
+        };
+
+        Ok((
+            chat_msg.sender_id,
+            quote.chars().take(QUOTE_LENGTH).collect(),
+        ))
+    }
+
+    /// Add or remove the emoji reaction of a user on a message
+    ///
+    /// The reactions are aggregated per emoji on the message.
+    /// Added reactions are set as the last message of the group.
+    pub fn update_reaction(
+        account_id: &PeerId,
+        group_id: &Vec<u8>,
+        user_id: &PeerId,
+        message_id: &Vec<u8>,
+        emoji: &str,
+        remove: bool,
+    ) -> Result<(), String> {
+        if emoji.is_empty() || emoji.len() > MAX_REACTION_LENGTH {
+            return Err("invalid reaction".to_string());
+        }
+
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+        let (_key, chat_msg) = Self::get_message_by_id(&db_ref, message_id)?;
+        if chat_msg.group_id != *group_id {
+            return Err("message is not in this group".to_string());
+        }
+        match GroupStorage::get_group(account_id.to_owned(), group_id.clone()) {
+            Some(group) if group.is_member(&user_id.to_bytes()) => {}
+            _ => return Err("reaction from non member".to_string()),
+        }
+
+        // update reactions
+        let mut reactions = Self::get_reactions(&db_ref, message_id);
+        let users = reactions.entry(emoji.to_string()).or_insert(Vec::new());
+        users.retain(|id| *id != user_id.to_bytes());
+        if !remove {
+            users.push(user_id.to_bytes());
+        }
+        reactions.retain(|_emoji, users| !users.is_empty());
+
+        if let Err(e) = db_ref
+            .reactions
+            .insert(message_id.clone(), bincode::serialize(&reactions).unwrap())
+        {
+            log::error!("Error saving chat reactions to data base: {}", e);
+        }
+        if let Err(e) = db_ref.reactions.flush() {
+            log::error!("Error chat reactions flush: {}", e);
+        }
+
+        // update last message
+        if !remove {
+            let content = rpc_proto::ChatContentMessage {
+                message: Some(rpc_proto::chat_content_message::Message::Reaction(
+                    rpc_proto::ChatReaction {
+                        message_id: message_id.clone(),
+                        emoji: emoji.to_string(),
+                        remove,
+                    },
+                )),
+            };
+            GroupStorage::group_update_last_chat_message(
+                account_id.to_owned(),
+                group_id.clone(),
+                user_id.to_owned(),
+                content.encode_to_vec(),
+                Timestamp::get_timestamp(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Get a chat message by its data base key
+    pub fn get_message_by_key(
+        account_id: &PeerId,
+        db_key: &[u8],
+    ) -> Option<rpc_proto::ChatMessage> {
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+        let message_bytes = db_ref.messages.get(db_key).ok()??;
+        let mut message: rpc_proto::ChatMessage = bincode::deserialize(&message_bytes).ok()?;
+        Self::add_message_details(&db_ref, &mut message);
+        Some(message)
+    }
+
+    /// Call a function for every chat message of a user account
+    pub fn for_each_message<F: FnMut(&[u8], rpc_proto::ChatMessage)>(account_id: PeerId, mut f: F) {
+        let db_ref = Self::get_db_ref(account_id);
+        for res in db_ref.messages.iter() {
+            match res {
+                Ok((db_key, message_bytes)) => {
+                    if let Ok(message) = bincode::deserialize(&message_bytes) {
+                        f(&db_key, message);
+                    }
+                }
+                Err(e) => {
+                    log::error!("for_each_message error: {}", e);
+                }
+            }
+        }
+    }
+
     /// Get chat messages of a specific conversation from data base
     pub fn get_messages(account_id: PeerId, group_id: Vec<u8>) -> rpc_proto::ChatConversationList {
+        // get messages of the conversation
+        let message_list = Self::get_conversation_messages(account_id, &group_id);
+
+        // clear unread messages from group
+        if group_id.len() == 16 {
+            GroupStorage::group_clear_unread(account_id, group_id.clone());
+        }
+
+        rpc_proto::ChatConversationList {
+            group_id,
+            message_list,
+        }
+    }
+
+    /// Get all chat messages of a conversation
+    ///
+    /// In contrast to `get_messages`, the messages are not marked as read.
+    pub fn get_conversation_messages(
+        account_id: PeerId,
+        group_id: &Vec<u8>,
+    ) -> Vec<rpc_proto::ChatMessage> {
         // create empty messages list
         let mut message_list: Vec<rpc_proto::ChatMessage> = Vec::new();
 
@@ -257,7 +920,7 @@ impl ChatStorage {
             let db_ref = Self::get_db_ref(account_id);
 
             // create message keys
-            let (first_key, last_key) = Self::get_db_key_range(&group_id.clone());
+            let (first_key, last_key) = Self::get_db_key_range(group_id);
 
             // iterate over all values in chat_messages db
             for res in db_ref
@@ -266,8 +929,9 @@ impl ChatStorage {
             {
                 match res {
                     Ok((_id, message_bytes)) => {
-                        let message: rpc_proto::ChatMessage =
+                        let mut message: rpc_proto::ChatMessage =
                             bincode::deserialize(&message_bytes).unwrap();
+                        Self::add_message_details(&db_ref, &mut message);
                         message_list.push(message);
                     }
                     Err(e) => {
@@ -275,14 +939,79 @@ impl ChatStorage {
                     }
                 }
             }
+        }
 
-            // clear unread messages from group
-            GroupStorage::group_clear_unread(account_id, group_id.clone());
+        message_list
+    }
+
+    /// get a chat message and its db key by message id
+    fn get_message_by_id(
+        db_ref: &ChatAccountDb,
+        message_id: &Vec<u8>,
+    ) -> Result<(sled::IVec, rpc_proto::ChatMessage), String> {
+        let key = match db_ref.message_ids.get(message_id) {
+            Ok(Some(key)) => key,
+            _ => return Err("message not found".to_string()),
+        };
+        match db_ref.messages.get(&key) {
+            Ok(Some(chat_msg_bytes)) => match bincode::deserialize(&chat_msg_bytes) {
+                Ok(chat_msg) => Ok((key, chat_msg)),
+                Err(e) => Err(e.to_string()),
+            },
+            _ => Err("message not found".to_string()),
         }
+    }
 
-        rpc_proto::ChatConversationList {
-            group_id,
-            message_list,
+    /// get the reactions on a message
+    fn get_reactions(
+        db_ref: &ChatAccountDb,
+        message_id: &Vec<u8>,
+    ) -> BTreeMap<String, Vec<Vec<u8>>> {
+        match db_ref.reactions.get(message_id) {
+            Ok(Some(reactions_bytes)) => bincode::deserialize(&reactions_bytes).unwrap_or_default(),
+            _ => BTreeMap::new(),
+        }
+    }
+
+    /// fill in the reply reference, the reactions and
+    /// the read receipts of a message
+    fn add_message_details(db_ref: &ChatAccountDb, message: &mut rpc_proto::ChatMessage) {
+        if let Ok(rpc_proto::ChatContentMessage {
+            message: Some(rpc_proto::chat_content_message::Message::Reply(reply)),
+        }) = rpc_proto::ChatContentMessage::decode(&message.content[..])
+        {
+            message.reply_to = reply.message_id;
+        }
+
+        message.reactions = Self::get_reactions(db_ref, &message.message_id)
+            .into_iter()
+            .map(|(emoji, user_ids)| rpc_proto::MessageReaction { emoji, user_ids })
+            .collect();
+
+        for confirmation in message.message_reception_confirmed.iter_mut() {
+            let mut key = message.message_id.clone();
+            key.extend(&confirmation.user_id);
+            if let Ok(Some(read_bytes)) = db_ref.read_receipts.get(key) {
+                if let Ok(arr) = read_bytes.as_ref().try_into() {
+                    confirmation.read_at = u64::from_be_bytes(arr);
+                }
+            }
+        }
+    }
+
+    /// save a chat message under an existing db key
+    fn save_message_by_key(
+        db_ref: &ChatAccountDb,
+        key: sled::IVec,
+        chat_msg: &rpc_proto::ChatMessage,
+    ) {
+        let chat_msg_todb = bincode::serialize(chat_msg).unwrap();
+        if let Err(e) = db_ref.messages.insert(key, chat_msg_todb) {
+            log::error!("Error saving chat message to data base: {}", e);
+        }
+        // flush trees to disk
+        if let Err(e) = db_ref.messages.flush() {
+            log::error!("Error chat messages flush: {}", e);
         }
     }
 
@@ -334,6 +1063,10 @@ impl ChatStorage {
                 return ChatAccountDb {
                     messages: chat_user.messages.clone(),
                     message_ids: chat_user.message_ids.clone(),
+                    edits: chat_user.edits.clone(),
+                    reactions: chat_user.reactions.clone(),
+                    read: chat_user.read.clone(),
+                    read_receipts: chat_user.read_receipts.clone(),
                 };
             }
         }
@@ -345,6 +1078,10 @@ impl ChatStorage {
         ChatAccountDb {
             messages: chat_user.messages.clone(),
             message_ids: chat_user.message_ids.clone(),
+            edits: chat_user.edits.clone(),
+            reactions: chat_user.reactions.clone(),
+            read: chat_user.read.clone(),
+            read_receipts: chat_user.read_receipts.clone(),
         }
     }
 
@@ -356,10 +1093,18 @@ impl ChatStorage {
         // open trees
         let messages: sled::Tree = db.open_tree("chat_messages").unwrap();
         let message_ids: sled::Tree = db.open_tree("chat_message_ids").unwrap();
+        let edits: sled::Tree = db.open_tree("chat_edits").unwrap();
+        let reactions: sled::Tree = db.open_tree("chat_reactions").unwrap();
+        let read: sled::Tree = db.open_tree("chat_read").unwrap();
+        let read_receipts: sled::Tree = db.open_tree("chat_read_receipts").unwrap();
 
         let chat_user = ChatAccountDb {
             messages,
             message_ids,
+            edits,
+            reactions,
+            read,
+            read_receipts,
         };
 
         // get chat state for writing
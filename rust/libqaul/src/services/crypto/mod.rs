@@ -10,9 +10,33 @@
 //! qaul uses the `Noise_KK_25519_ChaChaPoly_SHA256` pattern.
 //!
 //! This file manages the encryption session.
+//!
+//! ## Forward Secrecy
+//!
+//! Once a session is in transport state, the transport keys are
+//! ratcheted forward every `RATCHET_INTERVAL` messages, if both
+//! users announced support for it in the handshake.
+//! Old keys are deleted, except for a bounded window of skipped
+//! keys, which are needed to decrypt delayed messages.
+//!
+//! After a configurable amount of sent messages or session age,
+//! a new session is negotiated via a new handshake.
+//!
+//! ## Session Lifecycle
+//!
+//! Unanswered handshakes are removed after the configured
+//! `handshake_timeout`, queued messages then start a new handshake.
+//! Sessions, which were replaced by a newer session, are kept
+//! to decrypt delayed messages until they have not been used
+//! for the configured `session_expiry`.
+//!
+//! If both users start a handshake simultaneously, both sessions
+//! are established, and both users send via the session that was
+//! initiated by the user with the lower user ID.
 
 use libp2p::PeerId;
 use noise_rust_crypto::{ChaCha20Poly1305, Sha256, X25519};
+use prost::Message;
 use serde::{Deserialize, Serialize};
 
 mod crypto25519;
@@ -21,13 +45,25 @@ pub mod sessionmanager;
 mod storage;
 
 use super::messaging;
-use crate::node::user_accounts::UserAccount;
+use super::messaging::compression::MessagingCompression;
+use super::messaging::failed::MessagingFailed;
+use crate::node::user_accounts::{UserAccount, UserAccounts};
+use crate::rpc::Rpc;
 use crate::services::crypto::sessionmanager::CryptoSessionManager;
+use crate::storage::configuration::Configuration;
+use crate::utilities::timestamp::Timestamp;
 pub use crypto25519::Crypto25519;
 pub use noise::CryptoNoise;
 pub use storage::CryptoAccount;
+pub use storage::CryptoPendingMessage;
 pub use storage::CryptoStorage;
 
+/// Import protobuf message definition generated by
+/// the rust module prost-build.
+pub mod proto_rpc {
+    include!("qaul.rpc.crypto.rs");
+}
+
 /// The State Data of the Noise Protocol
 #[derive(Clone, Serialize, Deserialize)]
 pub struct CryptoState {
@@ -69,6 +105,25 @@ pub struct CryptoState {
     /// can synchronize all messages and actively query for
     /// all missing messages.
     pub out_of_order_indexes: bool,
+    /// ratchet epoch of the outgoing cipher key
+    ///
+    /// The cipher keys are ratcheted forward every
+    /// `RATCHET_INTERVAL` messages.
+    pub epoch_out: u64,
+    /// ratchet epoch of the incoming cipher key
+    pub epoch_in: u64,
+    /// time when the session was created
+    pub created_at: u64,
+    /// time when the session reached the transport state
+    pub established_at: u64,
+    /// time when the session was last used to en- or decrypt a message
+    pub last_used: u64,
+    /// negotiated session version
+    ///
+    /// The lower of the `SESSION_VERSION`s announced by both sides
+    /// in the handshake. The transport keys are only ratcheted,
+    /// if both sides support it.
+    pub version: u32,
 }
 
 /// The State of Noise Protocol Handshake
@@ -95,6 +150,107 @@ impl Crypto {
         CryptoStorage::init();
     }
 
+    /// Remove expired crypto sessions of all user accounts
+    ///
+    /// This function is called regularly from the event loop.
+    /// The queued messages of expired handshakes are sent again,
+    /// which starts a new handshake.
+    /// Queued messages older than the maximal message age are
+    /// marked as failed.
+    pub fn cleanup() {
+        let config = Configuration::get().crypto.clone();
+
+        for user in UserAccounts::get_user_info() {
+            let user_account = match UserAccounts::get_by_id(user.id) {
+                Some(user_account) => user_account,
+                None => continue,
+            };
+            let crypto_account = CryptoStorage::get_db_ref(user_account.id.clone());
+
+            for remote_id in crypto_account.cleanup(
+                config.handshake_timeout * 1000,
+                config.session_expiry * 1000,
+            ) {
+                log::debug!("crypto handshake with {} expired", remote_id.to_base58());
+                messaging::Messaging::send_pending_messages(&user_account, &remote_id);
+            }
+
+            // messages waiting too long for a handshake are marked as failed
+            for (remote_id, message) in crypto_account
+                .take_expired_pending_messages(Configuration::get().messaging.retry_max_age * 1000)
+            {
+                MessagingFailed::on_pending_failed(
+                    user_account.id,
+                    &remote_id,
+                    message,
+                    "maximal message age reached",
+                );
+            }
+        }
+    }
+
+    /// Check if a handshake with a remote user is pending
+    ///
+    /// Returns true if we sent a first handshake message to the
+    /// remote user and are still waiting for the second handshake
+    /// message.
+    /// No further messages can be encrypted for this user until
+    /// the session is established.
+    pub fn handshake_pending(user_account: &UserAccount, remote_id: PeerId) -> bool {
+        let crypto_account = CryptoStorage::get_db_ref(user_account.id.clone());
+
+        match crypto_account.get_state(remote_id) {
+            Some(CryptoState {
+                state: CryptoProcessState::HalfOutgoing,
+                ..
+            }) => true,
+            _ => false,
+        }
+    }
+
+    /// Get the id of the established crypto session with a remote user
+    ///
+    /// Returns the id of the session, with which the next
+    /// message to the remote user will be encrypted,
+    /// or None if there is no established session.
+    pub fn session_id(user_account: &UserAccount, remote_id: PeerId) -> Option<u32> {
+        let crypto_account = CryptoStorage::get_db_ref(user_account.id.clone());
+
+        match crypto_account.get_state(remote_id) {
+            Some(CryptoState {
+                state: CryptoProcessState::Transport,
+                session_id,
+                ..
+            }) => Some(session_id),
+            _ => None,
+        }
+    }
+
+    /// Queue an outgoing message until the handshake is completed
+    ///
+    /// Returns false if the queue of the remote user is full.
+    pub fn save_pending_message(
+        user_account: &UserAccount,
+        remote_id: PeerId,
+        message: CryptoPendingMessage,
+    ) -> bool {
+        let crypto_account = CryptoStorage::get_db_ref(user_account.id.clone());
+        crypto_account.save_pending_message(
+            remote_id,
+            message,
+            Configuration::get().crypto.pending_max_messages,
+        )
+    }
+
+    /// Remove and return all queued messages for a remote user
+    pub fn take_pending_messages(
+        user_account: &UserAccount,
+        remote_id: PeerId,
+    ) -> Vec<CryptoPendingMessage> {
+        let crypto_account = CryptoStorage::get_db_ref(user_account.id.clone());
+        crypto_account.take_pending_messages(remote_id)
+    }
+
     /// Encrypt an Outgoing Message
     ///
     /// This uses the `Noise_KK_X25519_ChaChaPoly_Sha256`
@@ -122,8 +278,18 @@ impl Crypto {
         let crypto_account = CryptoStorage::get_db_ref(user_account.id.clone());
 
         // check if there is a handshake state?
-        match crypto_account.get_state(remote_id) {
+        let state_option = crypto_account.get_state(remote_id);
+
+        // check if a new session needs to be negotiated
+        let rekey = match &state_option {
             Some(session) => {
+                Self::rekey_needed(session) && !crypto_account.handshake_in_progress(remote_id)
+            }
+            None => false,
+        };
+
+        match state_option {
+            Some(session) if !rekey => {
                 // encrypt with existing crypto state
                 if let Some((my_encrypted_option, my_nonce, my_session_id, my_process_state)) =
                     Self::encrypt_with_state(data, remote_id, crypto_account, session)
@@ -136,7 +302,11 @@ impl Crypto {
                     return None;
                 }
             }
-            None => {
+            _ => {
+                if rekey {
+                    log::debug!("rekey crypto session with {}", remote_id.to_base58());
+                }
+
                 // create new session and start handshake
                 (encrypted_option, nonce, session_id) =
                     CryptoNoise::encrypt_noise_kk_handshake_1::<
@@ -165,6 +335,22 @@ impl Crypto {
         None
     }
 
+    /// Check if a session needs to be replaced by a new one
+    ///
+    /// A new handshake is started after the configured amount of
+    /// sent messages or session age.
+    fn rekey_needed(crypto_state: &CryptoState) -> bool {
+        match crypto_state.state {
+            CryptoProcessState::Transport => {}
+            _ => return false,
+        }
+
+        let config = Configuration::get().crypto.clone();
+        let age = Timestamp::get_timestamp().saturating_sub(crypto_state.created_at);
+
+        crypto_state.index_nonce_out >= config.rekey_messages || age >= config.rekey_age * 1000
+    }
+
     /// Encrypt a message with a specific crypto state
     fn encrypt_with_state(
         data: Vec<u8>,
@@ -191,7 +377,8 @@ impl Crypto {
                 log::trace!("session state HalfOutgoing");
                 // we cannot send more messages at the moment, before we haven't
                 // received the handshake confirmation.
-                // TODO: build functionality to send further asymmetrically encrypted messages
+                // The messaging service queues these messages via
+                // `Crypto::save_pending_message` until the session is established.
                 log::error!("Can't send further messages after handshake");
                 return None;
             }
@@ -240,10 +427,17 @@ impl Crypto {
             data: encrypted_data,
         });
 
+        // announce our session version in the handshake
+        let session_version = match process_state {
+            messaging::proto::CryptoState::Handshake => noise::SESSION_VERSION,
+            _ => 0,
+        };
+
         return messaging::proto::Encrypted {
             state: process_state.into(),
             session_id,
             data: data_messages,
+            session_version,
         };
     }
 
@@ -291,6 +485,10 @@ impl Crypto {
                     ) => {
                         log::trace!("decrypt {}: second handshake", session.session_id);
 
+                        // use the session version both sides support
+                        let mut session = session;
+                        session.version = message.session_version.min(noise::SESSION_VERSION);
+
                         // decrypt second handshake message
                         for data in message.data {
                             let message = CryptoNoise::decrypt_noise_kk_handshake_2::<
@@ -302,6 +500,16 @@ impl Crypto {
                                 data.data, session, crypto_account, remote_id
                             );
 
+                            // announce our capabilities for the new session and
+                            // send the messages that waited for the handshake
+                            if message.is_some() {
+                                MessagingCompression::announce(&user_account, &remote_id);
+                                messaging::Messaging::send_pending_messages(
+                                    &user_account,
+                                    &remote_id,
+                                );
+                            }
+
                             // return second handshake confirmation message
 
                             return message;
@@ -369,9 +577,12 @@ impl Crypto {
                     Ok(messaging::proto::CryptoState::Handshake) => {
                         log::trace!("decrypt incoming first handshake");
 
+                        // use the session version both sides support
+                        let session_version = message.session_version.min(noise::SESSION_VERSION);
+
                         // decrypt new handshake
                         for data in message.data {
-                            if let Some((decrypted_data, crypto_state)) =
+                            if let Some((decrypted_data, mut crypto_state)) =
                                 CryptoNoise::decrypt_noise_kk_handshake_1::<
                                     X25519,
                                     ChaCha20Poly1305,
@@ -385,6 +596,8 @@ impl Crypto {
                                     message.session_id,
                                 )
                             {
+                                crypto_state.version = session_version;
+
                                 // create confirmation messaging message
                                 let messaging_message =
                                     CryptoSessionManager::create_second_handshake_message(
@@ -415,6 +628,7 @@ impl Crypto {
                                         &user_account,
                                         &remote_id,
                                         encrypted_message,
+                                        messaging::MessagingServiceType::Crypto,
                                         message_id,
                                         true,
                                     ) {
@@ -430,6 +644,16 @@ impl Crypto {
                                     log::error!("failed encrypting cryptosession 2nd handshake confirmation");
                                 }
 
+                                // announce our capabilities for the new session
+                                MessagingCompression::announce(&user_account, &remote_id);
+
+                                // send the messages that waited for our own handshake,
+                                // they can now be sent via the established session
+                                messaging::Messaging::send_pending_messages(
+                                    &user_account,
+                                    &remote_id,
+                                );
+
                                 // return decrypted first handshake message
                                 return Some(decrypted_data);
                             } else {
@@ -449,4 +673,124 @@ impl Crypto {
 
         None
     }
+
+    /// Process incoming RPC request messages for the crypto module
+    pub fn rpc(data: Vec<u8>, user_id: Vec<u8>) {
+        let user_account = match PeerId::from_bytes(&user_id) {
+            Ok(account_id) => match UserAccounts::get_by_id(account_id) {
+                Some(user_account) => user_account,
+                None => {
+                    log::error!("user account not found");
+                    return;
+                }
+            },
+            Err(e) => {
+                log::error!("invalid user id: {}", e);
+                return;
+            }
+        };
+        let crypto_account = CryptoStorage::get_db_ref(user_account.id.clone());
+
+        match proto_rpc::Crypto::decode(&data[..]) {
+            Ok(crypto) => match crypto.message {
+                Some(proto_rpc::crypto::Message::SessionsRequest(req)) => {
+                    let remote_ids = if req.remote_id.is_empty() {
+                        crypto_account.get_remote_ids()
+                    } else {
+                        match PeerId::from_bytes(&req.remote_id) {
+                            Ok(remote_id) => vec![remote_id],
+                            Err(e) => {
+                                log::error!("invalid remote user id: {}", e);
+                                return;
+                            }
+                        }
+                    };
+
+                    let mut sessions: Vec<proto_rpc::CryptoSession> = Vec::new();
+                    for remote_id in remote_ids {
+                        let active_id = crypto_account
+                            .get_state(remote_id)
+                            .map(|active| active.session_id);
+
+                        for crypto_state in crypto_account.get_states(remote_id) {
+                            let state = match crypto_state.state {
+                                CryptoProcessState::HalfOutgoing => {
+                                    proto_rpc::CryptoSessionState::HalfOutgoing
+                                }
+                                CryptoProcessState::HalfIncoming => {
+                                    proto_rpc::CryptoSessionState::HalfIncoming
+                                }
+                                CryptoProcessState::Transport => {
+                                    proto_rpc::CryptoSessionState::Transport
+                                }
+                            };
+
+                            sessions.push(proto_rpc::CryptoSession {
+                                remote_id: remote_id.to_bytes(),
+                                session_id: crypto_state.session_id,
+                                state: state.into(),
+                                initiator: crypto_state.initiator,
+                                active: active_id == Some(crypto_state.session_id),
+                                created_at: crypto_state.created_at,
+                                last_used: crypto_state.last_used,
+                                sent: crypto_state.index_nonce_out,
+                                received: crypto_state.highest_index_nonce_in,
+                            });
+                        }
+                    }
+
+                    let proto_message = proto_rpc::Crypto {
+                        message: Some(proto_rpc::crypto::Message::SessionsResponse(
+                            proto_rpc::CryptoSessionsResponse { sessions },
+                        )),
+                    };
+
+                    // send message
+                    Rpc::send_message(
+                        proto_message.encode_to_vec(),
+                        crate::rpc::proto::Modules::Crypto.into(),
+                        "".to_string(),
+                        Vec::new(),
+                    );
+                }
+                Some(proto_rpc::crypto::Message::ResetRequest(req)) => {
+                    let mut response = proto_rpc::CryptoResetResponse {
+                        remote_id: req.remote_id.clone(),
+                        success: false,
+                        error_message: "".to_string(),
+                    };
+
+                    match PeerId::from_bytes(&req.remote_id) {
+                        Ok(remote_id) => {
+                            log::info!("reset crypto sessions with {}", remote_id.to_base58());
+                            crypto_account.delete_sessions(remote_id);
+
+                            // queued messages start a new handshake
+                            messaging::Messaging::send_pending_messages(&user_account, &remote_id);
+                            response.success = true;
+                        }
+                        Err(e) => response.error_message = e.to_string(),
+                    }
+
+                    let proto_message = proto_rpc::Crypto {
+                        message: Some(proto_rpc::crypto::Message::ResetResponse(response)),
+                    };
+
+                    // send message
+                    Rpc::send_message(
+                        proto_message.encode_to_vec(),
+                        crate::rpc::proto::Modules::Crypto.into(),
+                        "".to_string(),
+                        Vec::new(),
+                    );
+                }
+                _ => {
+                    log::error!("Unhandled Protobuf Crypto RPC message");
+                }
+            },
+            Err(error) => {
+                log::error!("{:?}", error);
+            }
+        }
+    }
 }
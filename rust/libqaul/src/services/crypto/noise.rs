@@ -13,6 +13,34 @@ use rand::{thread_rng, Rng};
 use super::{Crypto25519, CryptoAccount, CryptoProcessState, CryptoState};
 use crate::node::user_accounts::UserAccount;
 use crate::router::users::Users;
+use crate::storage::configuration::Configuration;
+use crate::utilities::timestamp::Timestamp;
+
+/// Crypto session version of this node
+///
+/// The version is announced in the handshake messages.
+/// A session uses the lower version of both sides.
+pub const SESSION_VERSION: u32 = 1;
+
+/// First session version that ratchets the transport keys
+///
+/// Sessions with nodes of a lower version keep the
+/// transport keys of the handshake.
+const RATCHET_SESSION_VERSION: u32 = 1;
+
+/// Amount of messages encrypted with the same transport key
+///
+/// After this amount of messages, the transport key is
+/// ratcheted forward.
+/// Both sides of a session need to use the same interval.
+pub const RATCHET_INTERVAL: u64 = 100;
+
+/// Maximal amount of ratchet epochs an incoming message
+/// is allowed to be ahead of the current incoming epoch
+const MAX_RATCHET_STEPS: u64 = 10000;
+
+/// Key derivation label of the transport key ratchet
+const RATCHET_LABEL: &[u8] = b"qaul transport key ratchet";
 
 pub struct CryptoNoise {}
 
@@ -75,7 +103,7 @@ impl CryptoNoise {
         match handshake.write_message_vec(data.as_slice()) {
             Ok(output) => message = Some(output),
             Err(e) => {
-                // TODO: delete old handshake
+                // the state has not been saved yet
                 log::error!("{}", e);
                 return (None, 0, 0);
             }
@@ -133,7 +161,7 @@ impl CryptoNoise {
                 message = Some(output);
             }
             Err(e) => {
-                // TODO: delete old handshake
+                // the incoming handshake state has not been saved yet
                 log::error!("{}", e);
                 return (message, nonce);
             }
@@ -149,6 +177,8 @@ impl CryptoNoise {
         state.highest_index_nonce_in = 0;
         state.cipher_out = Some(key_out.as_slice().to_vec());
         state.index_nonce_out = 0;
+        state.established_at = Timestamp::get_timestamp();
+        state.last_used = state.established_at;
 
         // save crypto state to data base
         storage.save_state(remote_id, state.session_id, state);
@@ -177,15 +207,23 @@ impl CryptoNoise {
         // the handshake has been done, we can encrypt messages
         nonce = state.index_nonce_out;
 
+        // ratchet the key forward to the epoch of this message
+        let mut key = state.cipher_out.clone().unwrap();
+        while state.epoch_out < Self::epoch(&state, nonce) {
+            key = Self::ratchet_key::<H>(&key);
+            state.epoch_out += 1;
+        }
+        state.cipher_out = Some(key.clone());
+
         // create cipher
-        let mut cipher: CipherState<C> =
-            CipherState::new(state.clone().cipher_out.unwrap().as_slice(), nonce);
+        let mut cipher: CipherState<C> = CipherState::new(key.as_slice(), nonce);
 
         // encrypt message
         message = Some(cipher.encrypt_vec(data.as_slice()));
 
         // save new nonce to state
         state.index_nonce_out = nonce + 1;
+        state.last_used = Timestamp::get_timestamp();
 
         // save state
         storage.save_state(remote_id, state.session_id, state);
@@ -263,7 +301,7 @@ impl CryptoNoise {
                 state.re = Some(Vec::from(re.as_slice()));
             }
             None => {
-                // TODO: remove state
+                // the state has not been saved yet
                 return None;
             }
         }
@@ -326,7 +364,9 @@ impl CryptoNoise {
                 state.re = Some(Vec::from(re.as_slice()));
             }
             None => {
-                // TODO: remove state
+                // keep the waiting handshake state, a forged message
+                // shall not abort the handshake.
+                // Unanswered handshakes are removed by the session cleanup.
                 return None;
             }
         }
@@ -347,6 +387,8 @@ impl CryptoNoise {
         state.highest_index_nonce_in = 0;
         state.cipher_out = Some(key_out.as_slice().to_vec());
         state.index_nonce_out = 0;
+        state.established_at = Timestamp::get_timestamp();
+        state.last_used = state.established_at;
 
         // save state to data base
         storage.save_state(remote_id, state.session_id, state);
@@ -375,16 +417,66 @@ impl CryptoNoise {
         // we had a successful handshake and are in transport state
         log::trace!("Decrypting with full encryption");
 
+        // get the key of the epoch of this message
+        let epoch = Self::epoch(&state, nonce);
+        let key: Vec<u8>;
+        let mut skipped: Vec<(u64, Vec<u8>)> = Vec::new();
+        if epoch < state.epoch_in {
+            // delayed message of a past epoch
+            match storage.get_skipped_key(remote_id, state.session_id, epoch) {
+                Some(skipped_key) => key = skipped_key,
+                None => {
+                    log::error!("decryption key of epoch {} not available anymore", epoch);
+                    return None;
+                }
+            }
+        } else {
+            if epoch - state.epoch_in > MAX_RATCHET_STEPS {
+                log::error!("message epoch {} too far ahead", epoch);
+                return None;
+            }
+
+            // ratchet the key forward and remember the skipped keys
+            let mut next_key = state.cipher_in.clone().unwrap();
+            for skipped_epoch in state.epoch_in..epoch {
+                skipped.push((skipped_epoch, next_key.clone()));
+                next_key = Self::ratchet_key::<H>(&next_key);
+            }
+            key = next_key;
+        }
+
         // create cipher
-        let mut cipher: CipherState<C> =
-            CipherState::new(state.cipher_in.clone().unwrap().as_slice(), nonce);
+        let mut cipher: CipherState<C> = CipherState::new(key.as_slice(), nonce);
 
         // decrypt message
         match cipher.decrypt_vec(data.as_slice()) {
             Ok(decrypted) => {
                 message = Some(decrypted);
 
-                state.highest_index_nonce_in = nonce;
+                // move the incoming key to the new epoch
+                if epoch > state.epoch_in {
+                    for (skipped_epoch, skipped_key) in skipped {
+                        storage.save_skipped_key(
+                            remote_id,
+                            state.session_id,
+                            skipped_epoch,
+                            skipped_key,
+                        );
+                    }
+                    storage.prune_skipped_keys(
+                        remote_id,
+                        state.session_id,
+                        Configuration::get().crypto.skipped_keys,
+                    );
+
+                    state.epoch_in = epoch;
+                    state.cipher_in = Some(key);
+                }
+
+                if nonce > state.highest_index_nonce_in {
+                    state.highest_index_nonce_in = nonce;
+                }
+                state.last_used = Timestamp::get_timestamp();
                 storage.save_state(remote_id, state.session_id, state);
             }
             Err(_) => {
@@ -396,6 +488,29 @@ impl CryptoNoise {
         message
     }
 
+    /// Ratchet epoch of a transport message
+    ///
+    /// Sessions without the key ratchet stay in epoch 0.
+    fn epoch(state: &CryptoState, nonce: u64) -> u64 {
+        if state.version >= RATCHET_SESSION_VERSION {
+            nonce / RATCHET_INTERVAL
+        } else {
+            0
+        }
+    }
+
+    /// Ratchet a transport key forward
+    ///
+    /// The next key is derived from the current key via HKDF.
+    /// The current key can't be derived from the next key.
+    fn ratchet_key<H>(key: &[u8]) -> Vec<u8>
+    where
+        H: Hash,
+    {
+        let (next_key, _) = H::hkdf(key, RATCHET_LABEL);
+        next_key.as_slice()[..key.len()].to_vec()
+    }
+
     /// Create CryptoState during handshake phase
     /// for outgoing or incoming
     fn create_crypto_state<D>(
@@ -442,6 +557,12 @@ impl CryptoNoise {
             cipher_in: None,
             highest_index_nonce_in: 0,
             out_of_order_indexes: false,
+            epoch_out: 0,
+            epoch_in: 0,
+            created_at: Timestamp::get_timestamp(),
+            established_at: 0,
+            last_used: Timestamp::get_timestamp(),
+            version: 0,
         };
 
         state
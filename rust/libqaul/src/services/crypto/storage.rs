@@ -6,14 +6,16 @@
 //! Handling of the data base access for the crypto handshake and session state.
 
 use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
 use sled;
 use state::InitCell;
 use std::collections::BTreeMap;
 use std::sync::RwLock;
 
-use super::CryptoState;
-use crate::services::messaging::proto;
+use super::{CryptoProcessState, CryptoState};
+use crate::services::messaging::{proto, MessagingServiceType};
 use crate::storage::database::DataBase;
+use crate::utilities::timestamp::Timestamp;
 
 /// mutable state of messages, scheduled for sending
 pub static CRYPTOSTORAGE: InitCell<RwLock<CryptoStorage>> = InitCell::new();
@@ -21,6 +23,8 @@ pub static CRYPTOSTORAGE: InitCell<RwLock<CryptoStorage>> = InitCell::new();
 /// Group DB links for user account
 #[derive(Clone)]
 pub struct CryptoAccount {
+    /// user account id of this storage
+    pub user_id: PeerId,
     /// user crypto session state storage
     ///
     /// value: bincode of `CryptoState`
@@ -30,6 +34,54 @@ pub struct CryptoAccount {
     ///
     /// value: bincode of `proto::Encrypted`
     pub cache: sled::Tree,
+    /// outgoing messages waiting for the completion
+    /// of the handshake
+    ///
+    /// value: bincode of `CryptoPendingMessage`
+    pub pending: sled::Tree,
+    /// keys of past ratchet epochs, needed to
+    /// decrypt delayed messages
+    ///
+    /// key: {remote_id}{session_id}{epoch}
+    /// value: cipher key
+    pub skipped: sled::Tree,
+}
+
+/// CryptoState format before the introduction of the key ratchet
+#[derive(Deserialize)]
+struct LegacyCryptoState {
+    session_id: u32,
+    state: CryptoProcessState,
+    initiator: bool,
+    s: Vec<u8>,
+    rs: Vec<u8>,
+    e: Vec<u8>,
+    re: Option<Vec<u8>>,
+    cipher_out: Option<Vec<u8>>,
+    index_nonce_out: u64,
+    cipher_in: Option<Vec<u8>>,
+    highest_index_nonce_in: u64,
+    out_of_order_indexes: bool,
+}
+
+/// Outgoing Message waiting for a Handshake
+///
+/// Messages to a user, for whom we sent a first handshake message
+/// but have not received the second handshake message yet,
+/// can't be encrypted.
+/// They are kept unencrypted until the session is established.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CryptoPendingMessage {
+    /// messaging service type of the message
+    pub message_type: MessagingServiceType,
+    /// message id
+    pub message_id: Vec<u8>,
+    /// unencrypted messaging data
+    pub data: Vec<u8>,
+    /// does the message need to be confirmed by the receiver
+    pub needs_confirmation: bool,
+    /// time the message was queued first
+    pub created_at: u64,
 }
 
 impl CryptoAccount {
@@ -64,7 +116,6 @@ impl CryptoAccount {
     }
 
     /// Create cache storage key range
-    #[allow(dead_code)]
     fn create_cache_key_range(remote_id: PeerId, session_id: u32) -> (Vec<u8>, Vec<u8>) {
         let first_key = Self::create_cache_key(remote_id, session_id, 0);
         let last_key = Self::create_cache_key(remote_id, session_id, u64::MAX);
@@ -72,35 +123,122 @@ impl CryptoAccount {
         (first_key, last_key)
     }
 
-    /// get currently active CryptoState from db
-    pub fn get_state(&self, remote_id: PeerId) -> Option<CryptoState> {
+    /// Create pending message storage key
+    ///
+    /// The db key for the pending messages is:
+    /// {remote_id}{index}
+    fn create_pending_key(remote_id: PeerId, index: u64) -> Vec<u8> {
+        let mut key = remote_id.to_bytes();
+        key.extend_from_slice(&index.to_be_bytes());
+        key
+    }
+
+    /// get all CryptoStates of a remote user from db
+    pub fn get_states(&self, remote_id: PeerId) -> Vec<CryptoState> {
         // get key range
         let (first_key, last_key) = Self::create_state_key_range(remote_id);
 
         // create return value
-        let mut state_option: Option<CryptoState> = None;
+        let mut states: Vec<CryptoState> = Vec::new();
 
         // get results from data base
         let iterator = self.state.range(first_key..last_key);
 
         for result in iterator {
             match result {
-                Ok((_key, crypto_state_bytes)) => {
-                    let crypto_state: CryptoState =
-                        bincode::deserialize(&crypto_state_bytes).unwrap();
-                    match crypto_state.state {
-                        super::CryptoProcessState::HalfOutgoing => {
-                            state_option = Some(crypto_state)
-                        }
-                        super::CryptoProcessState::HalfIncoming => return Some(crypto_state),
-                        super::CryptoProcessState::Transport => return Some(crypto_state),
+                Ok((key, crypto_state_bytes)) => {
+                    if let Some(crypto_state) = self.deserialize_state(&key, &crypto_state_bytes) {
+                        states.push(crypto_state);
                     }
                 }
                 Err(e) => log::error!("{}", e),
             }
         }
 
-        state_option
+        states
+    }
+
+    /// get currently active CryptoState from db
+    ///
+    /// A session waiting for our second handshake message is
+    /// returned first.
+    /// Otherwise the preferred session in transport state is returned.
+    /// A session waiting for the second handshake message of the
+    /// remote user is only returned, if there is no session in
+    /// transport state.
+    pub fn get_state(&self, remote_id: PeerId) -> Option<CryptoState> {
+        self.select_state(remote_id, self.get_states(remote_id))
+    }
+
+    /// select the active session out of all sessions of a remote user
+    fn select_state(&self, remote_id: PeerId, states: Vec<CryptoState>) -> Option<CryptoState> {
+        let mut transport_option: Option<CryptoState> = None;
+        let mut state_option: Option<CryptoState> = None;
+
+        for crypto_state in states {
+            match crypto_state.state {
+                super::CryptoProcessState::HalfOutgoing => state_option = Some(crypto_state),
+                super::CryptoProcessState::HalfIncoming => return Some(crypto_state),
+                super::CryptoProcessState::Transport => match &transport_option {
+                    Some(transport) if !self.is_preferred(remote_id, &crypto_state, transport) => {}
+                    _ => transport_option = Some(crypto_state),
+                },
+            }
+        }
+
+        transport_option.or(state_option)
+    }
+
+    /// check if session `a` is preferred over session `b`
+    ///
+    /// The newer session is preferred.
+    ///
+    /// When both users start a handshake at the same time, two
+    /// sessions are created, each of them before the other one
+    /// was established.
+    /// In this case the session initiated by the user with the
+    /// lower user ID is preferred.
+    /// Thereby both users decide on the same session.
+    fn is_preferred(&self, remote_id: PeerId, a: &CryptoState, b: &CryptoState) -> bool {
+        if a.created_at < Self::established(b) && b.created_at < Self::established(a) {
+            let initiator_a = self.initiator_id(remote_id, a);
+            let initiator_b = self.initiator_id(remote_id, b);
+            if initiator_a != initiator_b {
+                return initiator_a < initiator_b;
+            }
+        }
+
+        a.created_at > b.created_at
+    }
+
+    /// get the time a session was established
+    ///
+    /// Sessions which are not yet established return `u64::MAX`.
+    fn established(crypto_state: &CryptoState) -> u64 {
+        match crypto_state.state {
+            CryptoProcessState::Transport => crypto_state.established_at,
+            _ => u64::MAX,
+        }
+    }
+
+    /// get the user ID of the initiator of a session
+    fn initiator_id(&self, remote_id: PeerId, crypto_state: &CryptoState) -> Vec<u8> {
+        if crypto_state.initiator {
+            self.user_id.to_bytes()
+        } else {
+            remote_id.to_bytes()
+        }
+    }
+
+    /// check if we are waiting for a second handshake message
+    /// of the remote user
+    pub fn handshake_in_progress(&self, remote_id: PeerId) -> bool {
+        self.get_states(remote_id)
+            .iter()
+            .any(|crypto_state| match crypto_state.state {
+                super::CryptoProcessState::HalfOutgoing => true,
+                _ => false,
+            })
     }
 
     /// get a specific CryptoState by ID
@@ -109,10 +247,9 @@ impl CryptoAccount {
         let key = Self::create_state_key(remote_id, session_id);
 
         // get result from data base
-        match self.state.get(key) {
+        match self.state.get(&key) {
             Ok(Some(crypto_state_bytes)) => {
-                let crypto_state: CryptoState = bincode::deserialize(&crypto_state_bytes).unwrap();
-                return Some(crypto_state);
+                return self.deserialize_state(&key, &crypto_state_bytes);
             }
             Ok(None) => return None,
             Err(e) => log::error!("{}", e),
@@ -121,6 +258,236 @@ impl CryptoAccount {
         None
     }
 
+    /// deserialize a CryptoState from db
+    ///
+    /// States saved before the introduction of the key
+    /// ratchet are converted to the current format and saved.
+    /// Their age is counted from the time of the conversion,
+    /// so that they are not all renegotiated at once.
+    fn deserialize_state(&self, key: &[u8], crypto_state_bytes: &[u8]) -> Option<CryptoState> {
+        if let Ok(crypto_state) = bincode::deserialize::<CryptoState>(crypto_state_bytes) {
+            return Some(crypto_state);
+        }
+
+        let now = Timestamp::get_timestamp();
+        let crypto_state = match bincode::deserialize::<LegacyCryptoState>(crypto_state_bytes) {
+            Ok(legacy) => CryptoState {
+                session_id: legacy.session_id,
+                state: legacy.state,
+                initiator: legacy.initiator,
+                s: legacy.s,
+                rs: legacy.rs,
+                e: legacy.e,
+                re: legacy.re,
+                cipher_out: legacy.cipher_out,
+                index_nonce_out: legacy.index_nonce_out,
+                cipher_in: legacy.cipher_in,
+                highest_index_nonce_in: legacy.highest_index_nonce_in,
+                out_of_order_indexes: legacy.out_of_order_indexes,
+                epoch_out: 0,
+                epoch_in: 0,
+                created_at: now,
+                established_at: match legacy.state {
+                    CryptoProcessState::Transport => now,
+                    _ => 0,
+                },
+                last_used: now,
+                version: 0,
+            },
+            Err(e) => {
+                log::error!("crypto state deserialization error: {}", e);
+                return None;
+            }
+        };
+
+        // save the converted state
+        match bincode::serialize(&crypto_state) {
+            Ok(bytes) => {
+                if let Err(e) = self.state.insert(key, bytes) {
+                    log::error!("Error saving crypto state to data base: {}", e);
+                }
+            }
+            Err(e) => log::error!("{}", e),
+        }
+
+        Some(crypto_state)
+    }
+
+    /// get the IDs of all remote users we have sessions with
+    pub fn get_remote_ids(&self) -> Vec<PeerId> {
+        let mut remote_ids: Vec<PeerId> = Vec::new();
+
+        for result in self.state.iter().keys() {
+            match result {
+                // the session id are the last 4 bytes of the key
+                Ok(key) if key.len() > 4 => {
+                    if let Ok(remote_id) = PeerId::from_bytes(&key[..key.len() - 4]) {
+                        if !remote_ids.contains(&remote_id) {
+                            remote_ids.push(remote_id);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => log::error!("{}", e),
+            }
+        }
+
+        remote_ids
+    }
+
+    /// delete a session with all its cached messages and skipped keys
+    pub fn delete_session(&self, remote_id: PeerId, session_id: u32) {
+        log::debug!(
+            "delete crypto session {} with {}",
+            session_id,
+            remote_id.to_base58()
+        );
+
+        if let Err(e) = self
+            .state
+            .remove(Self::create_state_key(remote_id, session_id))
+        {
+            log::error!("{}", e);
+        }
+
+        let (first_key, last_key) = Self::create_cache_key_range(remote_id, session_id);
+        for tree in [&self.cache, &self.skipped] {
+            for key in tree.range(first_key.clone()..=last_key.clone()).keys() {
+                match key {
+                    Ok(key) => {
+                        if let Err(e) = tree.remove(key) {
+                            log::error!("{}", e);
+                        }
+                    }
+                    Err(e) => log::error!("{}", e),
+                }
+            }
+        }
+
+        // flush trees to disk
+        for tree in [&self.state, &self.cache, &self.skipped] {
+            if let Err(e) = tree.flush() {
+                log::error!("Error db flush: {}", e);
+            }
+        }
+    }
+
+    /// delete all sessions with a remote user
+    pub fn delete_sessions(&self, remote_id: PeerId) {
+        for crypto_state in self.get_states(remote_id) {
+            self.delete_session(remote_id, crypto_state.session_id);
+        }
+    }
+
+    /// remove expired sessions
+    ///
+    /// The following sessions are removed:
+    ///
+    /// * handshakes, superseded by a preferred session in transport state
+    /// * handshakes, which were not answered within `handshake_timeout`
+    /// * sessions in transport state, which are not the active session
+    ///   and have not been used within `session_expiry`
+    ///
+    /// Both time values are in milliseconds.
+    ///
+    /// Returns the IDs of the remote users whose active handshake
+    /// expired.
+    pub fn cleanup(&self, handshake_timeout: u64, session_expiry: u64) -> Vec<PeerId> {
+        let now = Timestamp::get_timestamp();
+        let mut expired: Vec<PeerId> = Vec::new();
+
+        for remote_id in self.get_remote_ids() {
+            let states = self.get_states(remote_id);
+            let active_id = match self.select_state(remote_id, states.clone()) {
+                Some(active) => active.session_id,
+                None => continue,
+            };
+
+            for crypto_state in states.iter() {
+                let is_active = crypto_state.session_id == active_id;
+                let remove = match crypto_state.state {
+                    CryptoProcessState::HalfOutgoing | CryptoProcessState::HalfIncoming => {
+                        if now.saturating_sub(crypto_state.created_at) > handshake_timeout {
+                            if is_active {
+                                expired.push(remote_id);
+                            }
+                            true
+                        } else {
+                            states.iter().any(|transport| match transport.state {
+                                CryptoProcessState::Transport => {
+                                    self.is_preferred(remote_id, transport, crypto_state)
+                                }
+                                _ => false,
+                            })
+                        }
+                    }
+                    CryptoProcessState::Transport => {
+                        !is_active && now.saturating_sub(crypto_state.last_used) > session_expiry
+                    }
+                };
+
+                if remove {
+                    self.delete_session(remote_id, crypto_state.session_id);
+                }
+            }
+        }
+
+        expired
+    }
+
+    /// save the key of a past ratchet epoch
+    ///
+    /// The key is needed to decrypt delayed messages of that epoch.
+    pub fn save_skipped_key(&self, remote_id: PeerId, session_id: u32, epoch: u64, key: Vec<u8>) {
+        let db_key = Self::create_cache_key(remote_id, session_id, epoch);
+        if let Err(e) = self.skipped.insert(db_key, key) {
+            log::error!("Error skipped key to db: {}", e);
+        }
+    }
+
+    /// get the key of a past ratchet epoch
+    pub fn get_skipped_key(
+        &self,
+        remote_id: PeerId,
+        session_id: u32,
+        epoch: u64,
+    ) -> Option<Vec<u8>> {
+        let db_key = Self::create_cache_key(remote_id, session_id, epoch);
+        match self.skipped.get(db_key) {
+            Ok(Some(key)) => Some(key.to_vec()),
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// delete the oldest skipped keys of a session
+    ///
+    /// Only the newest `max` keys are kept.
+    pub fn prune_skipped_keys(&self, remote_id: PeerId, session_id: u32, max: u64) {
+        let (first_key, last_key) = Self::create_cache_key_range(remote_id, session_id);
+        let keys: Vec<sled::IVec> = self
+            .skipped
+            .range(first_key..=last_key)
+            .keys()
+            .filter_map(|key| key.ok())
+            .collect();
+
+        let remove = keys.len().saturating_sub(max as usize);
+        for key in keys.into_iter().take(remove) {
+            if let Err(e) = self.skipped.remove(key) {
+                log::error!("{}", e);
+            }
+        }
+
+        // flush trees to disk
+        if let Err(e) = self.skipped.flush() {
+            log::error!("Error db flush: {}", e);
+        }
+    }
+
     /// save CryptoState to db
     pub fn save_state(&self, remote_id: PeerId, session_id: u32, crypto_state: CryptoState) {
         // create key
@@ -138,6 +505,125 @@ impl CryptoAccount {
         }
     }
 
+    /// add an outgoing message to the pending queue of a remote user
+    ///
+    /// Returns false if the queue already contains
+    /// `max_messages` messages.
+    pub fn save_pending_message(
+        &self,
+        remote_id: PeerId,
+        message: CryptoPendingMessage,
+        max_messages: usize,
+    ) -> bool {
+        if self.pending.scan_prefix(remote_id.to_bytes()).count() >= max_messages {
+            return false;
+        }
+
+        // get next index of the queue
+        let last_key = Self::create_pending_key(remote_id, u64::MAX);
+        let index = match self.pending.get_lt(&last_key) {
+            Ok(Some((key, _))) if key.starts_with(&remote_id.to_bytes()) => {
+                let mut index_bytes = [0u8; 8];
+                index_bytes.copy_from_slice(&key[key.len() - 8..]);
+                u64::from_be_bytes(index_bytes) + 1
+            }
+            _ => 0,
+        };
+
+        // save message in data base
+        let key = Self::create_pending_key(remote_id, index);
+        let message_bytes = bincode::serialize(&message).unwrap();
+        if let Err(e) = self.pending.insert(key, message_bytes) {
+            log::error!("Error pending message to db: {}", e);
+        }
+
+        // flush trees to disk
+        if let Err(e) = self.pending.flush() {
+            log::error!("Error db flush: {}", e);
+        }
+
+        true
+    }
+
+    /// remove and return all pending messages older than `max_age`
+    ///
+    /// `max_age` is in milliseconds.
+    /// The messages are returned with their remote user id.
+    pub fn take_expired_pending_messages(
+        &self,
+        max_age: u64,
+    ) -> Vec<(PeerId, CryptoPendingMessage)> {
+        let now = Timestamp::get_timestamp();
+        let mut messages: Vec<(PeerId, CryptoPendingMessage)> = Vec::new();
+
+        for result in self.pending.iter() {
+            let (key, message_bytes) = match result {
+                Ok(entry) => entry,
+                Err(e) => {
+                    log::error!("{}", e);
+                    continue;
+                }
+            };
+            let message = match bincode::deserialize::<CryptoPendingMessage>(&message_bytes) {
+                Ok(message) => message,
+                Err(e) => {
+                    log::error!("{}", e);
+                    continue;
+                }
+            };
+            if now.saturating_sub(message.created_at) <= max_age || key.len() < 8 {
+                continue;
+            }
+
+            match PeerId::from_bytes(&key[..key.len() - 8]) {
+                Ok(remote_id) => messages.push((remote_id, message)),
+                Err(e) => log::error!("{}", e),
+            }
+            if let Err(e) = self.pending.remove(key) {
+                log::error!("{}", e);
+            }
+        }
+
+        if !messages.is_empty() {
+            if let Err(e) = self.pending.flush() {
+                log::error!("Error db flush: {}", e);
+            }
+        }
+
+        messages
+    }
+
+    /// remove and return all pending messages of a remote user
+    ///
+    /// The messages are returned in the order they were queued.
+    pub fn take_pending_messages(&self, remote_id: PeerId) -> Vec<CryptoPendingMessage> {
+        let mut messages: Vec<CryptoPendingMessage> = Vec::new();
+        let first_key = Self::create_pending_key(remote_id, 0);
+        let last_key = Self::create_pending_key(remote_id, u64::MAX);
+
+        for result in self.pending.range(first_key..=last_key) {
+            match result {
+                Ok((key, message_bytes)) => {
+                    match bincode::deserialize::<CryptoPendingMessage>(&message_bytes) {
+                        Ok(message) => messages.push(message),
+                        Err(e) => log::error!("{}", e),
+                    }
+                    if let Err(e) = self.pending.remove(key) {
+                        log::error!("{}", e);
+                    }
+                }
+                Err(e) => log::error!("{}", e),
+            }
+        }
+
+        // flush trees to disk
+        if let Err(e) = self.pending.flush() {
+            log::error!("Error db flush: {}", e);
+        }
+
+        messages
+    }
+
     /// save an incoming, out of order message to cache
     pub fn save_cache_message(
         &self,
@@ -191,10 +677,7 @@ impl CryptoStorage {
 
             // check if user account ID is in map
             if let Some(crypto_account_db) = crypto_storage.db_ref.get(&account_id.to_bytes()) {
-                return CryptoAccount {
-                    state: crypto_account_db.state.clone(),
-                    cache: crypto_account_db.cache.clone(),
-                };
+                return crypto_account_db.clone();
             }
         }
 
@@ -217,8 +700,16 @@ impl CryptoStorage {
         // open trees
         let state: sled::Tree = db.open_tree("crypto_state").unwrap();
         let cache: sled::Tree = db.open_tree("crypto_cache").unwrap();
-
-        let crypto_account = CryptoAccount { state, cache };
+        let pending: sled::Tree = db.open_tree("crypto_pending").unwrap();
+        let skipped: sled::Tree = db.open_tree("crypto_skipped_keys").unwrap();
+
+        let crypto_account = CryptoAccount {
+            user_id: account_id,
+            state,
+            cache,
+            pending,
+            skipped,
+        };
 
         // get group storage for writing
         let mut crypto_storage = CRYPTOSTORAGE.get().write().unwrap();
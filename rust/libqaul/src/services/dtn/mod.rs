@@ -18,6 +18,7 @@ use crate::node::user_accounts::{UserAccount, UserAccounts};
 use crate::rpc::Rpc;
 use crate::storage::configuration::Configuration;
 use crate::storage::database::DataBase;
+use crate::utilities::timestamp::Timestamp;
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -195,6 +196,7 @@ impl Dtn {
             sender_id: user_account.id.to_bytes(),
             receiver_id: receiver_id.to_bytes(),
             payload: payload.encode_to_vec(),
+            created_at: Timestamp::get_timestamp(),
         };
 
         if let Ok(signature) = user_account.keys.sign(&envelop.encode_to_vec()) {
@@ -234,6 +236,7 @@ impl Dtn {
             let container = super::messaging::proto::Container {
                 signature: signature.clone(),
                 envelope: Some(envelop),
+                hop_limit: Configuration::get().messaging.hop_limit,
             };
             super::messaging::Messaging::save_unconfirmed_message(
                 MessagingServiceType::DtnStored,
@@ -368,6 +371,7 @@ impl Dtn {
                     let state = STORAGESTATE.get().read().unwrap();
                     let unconfirmed = super::messaging::UNCONFIRMED.get().read().unwrap();
                     let unconfrimed_len = unconfirmed.unconfirmed.len();
+                    let depth = super::messaging::Messaging::get_queue_depth();
 
                     let proto_message = proto_rpc::Dtn {
                         message: Some(proto_rpc::dtn::Message::DtnStateResponse(
@@ -375,6 +379,14 @@ impl Dtn {
                                 used_size: state.used_size,
                                 dtn_message_count: state.message_counts,
                                 unconfirmed_count: unconfrimed_len as u32,
+                                send_queue: Some(proto_rpc::MessagingQueueDepth {
+                                    control: depth.control,
+                                    confirmation: depth.confirmation,
+                                    chat: depth.chat,
+                                    group: depth.group,
+                                    file: depth.file,
+                                    forward: depth.forward,
+                                }),
                             },
                         )),
                     };
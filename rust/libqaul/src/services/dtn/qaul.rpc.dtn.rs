@@ -61,6 +61,35 @@ pub struct DtnStateResponse {
     /// unconfirmed count
     #[prost(uint32, tag = "3")]
     pub unconfirmed_count: u32,
+    /// messages scheduled for sending
+    #[prost(message, optional, tag = "4")]
+    pub send_queue: ::core::option::Option<MessagingQueueDepth>,
+}
+/// Messaging Queue Depth
+///
+/// Number of messages scheduled for sending
+/// per priority class
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MessagingQueueDepth {
+    /// crypto handshakes
+    #[prost(uint32, tag = "1")]
+    pub control: u32,
+    /// confirmations
+    #[prost(uint32, tag = "2")]
+    pub confirmation: u32,
+    /// chat messages
+    #[prost(uint32, tag = "3")]
+    pub chat: u32,
+    /// group management messages
+    #[prost(uint32, tag = "4")]
+    pub group: u32,
+    /// file data
+    #[prost(uint32, tag = "5")]
+    pub file: u32,
+    /// forwarded messages
+    #[prost(uint32, tag = "6")]
+    pub forward: u32,
 }
 /// Dtn Config Request
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -28,6 +28,7 @@ use crate::connections::{internet::Internet, lan::Lan, ConnectionModule};
 use crate::router;
 use crate::router::flooder::Flooder;
 use crate::rpc::Rpc;
+use crate::services::chat::ChatSearch;
 use crate::storage::database::DataBase;
 use crate::utilities::timestamp;
 
@@ -352,6 +353,10 @@ impl Feed {
 
         // update key
         feed.last_message = last_message;
+        drop(feed);
+
+        // add message to the search indexes
+        ChatSearch::index_feed_message(last_message, &content);
     }
 
     /// Save a Message
@@ -404,6 +409,10 @@ impl Feed {
 
         // update key
         feed.last_message = last_message;
+        drop(feed);
+
+        // add message to the search indexes
+        ChatSearch::index_feed_message(last_message, &message.content);
     }
 
     pub fn get_latest_message_ids(count: usize) -> Vec<Vec<u8>> {
@@ -468,6 +477,32 @@ impl Feed {
         res
     }
 
+    /// Get a message from data base by its index
+    pub fn get_message_data(index: u64) -> Option<FeedMessageData> {
+        let feed = FEED.get().read().unwrap();
+        match feed.tree.get(index.to_be_bytes()) {
+            Ok(Some(message_bytes)) => bincode::deserialize(&message_bytes).ok(),
+            _ => None,
+        }
+    }
+
+    /// Call a function for every message in the data base
+    pub fn for_each_message<F: FnMut(FeedMessageData)>(mut f: F) {
+        let feed = FEED.get().read().unwrap();
+        for res in feed.tree.iter() {
+            match res {
+                Ok((_id, message_bytes)) => {
+                    if let Ok(message) = bincode::deserialize(&message_bytes) {
+                        f(message);
+                    }
+                }
+                Err(e) => {
+                    log::error!("Error retrieving feed message from data base: {}", e);
+                }
+            }
+        }
+    }
+
     /// Get messages from data base
     ///
     /// This function get messages from data base
@@ -7,7 +7,7 @@ use libp2p::PeerId;
 use std::collections::BTreeMap;
 
 use super::group_id::GroupId;
-use super::{Group, GroupInvited, GroupStorage};
+use super::{Group, GroupInvited, GroupSenderKey, GroupStorage};
 use crate::services::chat::{self, Chat, ChatStorage};
 use crate::utilities::timestamp::Timestamp;
 
@@ -463,6 +463,9 @@ impl GroupManage {
             }
 
             for left_member in orign_members.keys() {
+                // replace our sender key
+                GroupSenderKey::remove_member(&account_id, &group_id.to_bytes(), left_member);
+
                 let event = chat::rpc_proto::ChatContentMessage {
                     message: Some(chat::rpc_proto::chat_content_message::Message::GroupEvent(
                         chat::rpc_proto::GroupEvent {
@@ -11,7 +11,7 @@ use prost::Message;
 
 use super::chat::{self, ChatStorage};
 use super::group_id::GroupId;
-use super::{Group, GroupMember, GroupStorage};
+use super::{Group, GroupMember, GroupSenderKey, GroupStorage};
 use crate::utilities::timestamp::Timestamp;
 use crate::{node::user_accounts::UserAccounts, utilities::timestamp};
 
@@ -216,6 +216,9 @@ impl Member {
 
             // save to data base
             GroupStorage::save_group(account_id.to_owned(), group);
+
+            // replace our sender key
+            GroupSenderKey::remove_member(account_id, group_id, &user_id.to_bytes());
         } else {
             return Err("this user is not member of this group".to_string());
         }
@@ -338,6 +341,9 @@ impl Member {
         // save group
         GroupStorage::save_group(account_id.to_owned(), group.clone());
 
+        // remove all sender keys of this group
+        GroupSenderKey::remove_group(account_id, &group.id);
+
         // save event
         let event = chat::rpc_proto::ChatContentMessage {
             message: Some(chat::rpc_proto::chat_content_message::Message::GroupEvent(
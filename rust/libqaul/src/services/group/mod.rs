@@ -24,12 +24,14 @@ pub mod group_id;
 mod manage;
 mod member;
 mod message;
+mod sender_key;
 pub mod storage;
 
 pub use group_id::GroupId;
 pub use manage::GroupManage;
 use member::Member;
 pub use message::GroupMessage;
+pub use sender_key::GroupSenderKey;
 pub use storage::GroupStorage;
 
 /// Import protobuf message definition generated by
@@ -200,28 +202,81 @@ impl Group {
         }
     }
 
-    /// Send capsuled group message through messaging service
-    #[allow(dead_code)]
-    pub fn send_group_message(
+    /// Send capsuled group message to all group members
+    /// through messaging service
+    ///
+    /// If sender keys are enabled, the message is encrypted
+    /// only once for all members supporting them.
+    pub fn send_group_message(user_account: &UserAccount, group_id: Vec<u8>, data: &Vec<u8>) {
+        let group;
+        match GroupStorage::get_group(user_account.id, group_id.clone()) {
+            Some(v) => group = v,
+            None => return,
+        }
+
+        if let Some((message_id, send_message)) =
+            Self::pack_group_message(user_account, &group, data)
+        {
+            // send message to all members
+            GroupSenderKey::send_to_members(
+                user_account,
+                &group,
+                send_message,
+                MessagingServiceType::Group,
+                &message_id,
+            );
+        }
+    }
+
+    /// Send capsuled group message to a single group member
+    /// through messaging service
+    ///
+    /// The receiver processes it like a message to the whole group.
+    pub fn send_member_message(
         user_account: &UserAccount,
-        receiver: &PeerId,
         group_id: Vec<u8>,
+        receiver: &PeerId,
         data: &Vec<u8>,
     ) {
-        // get last index
         let group;
-        match GroupStorage::get_group(user_account.id, group_id.clone()) {
+        match GroupStorage::get_group(user_account.id, group_id) {
             Some(v) => group = v,
             None => return,
         }
 
+        if let Some((message_id, send_message)) =
+            Self::pack_group_message(user_account, &group, data)
+        {
+            if let Err(error) = Messaging::pack_and_send_message(
+                user_account,
+                receiver,
+                send_message,
+                MessagingServiceType::Group,
+                &message_id,
+                true,
+            ) {
+                log::error!("group member message send error {}", error);
+            }
+        }
+    }
+
+    /// Pack a group container into a group message
+    ///
+    /// Returns the message id and the encoded messaging message.
+    /// The message index of our group membership is increased.
+    fn pack_group_message(
+        user_account: &UserAccount,
+        group: &Group,
+        data: &Vec<u8>,
+    ) -> Option<(Vec<u8>, Vec<u8>)> {
+        // get last index
         let mut my_member;
         match group.get_member(&user_account.id.to_bytes()) {
             Some(v) => {
                 my_member = v.clone();
             }
             _ => {
-                return;
+                return None;
             }
         }
 
@@ -244,24 +299,11 @@ impl Group {
             )),
         };
 
-        // send message via messaging
-        match Messaging::pack_and_send_message(
-            user_account,
-            &receiver,
-            send_message.encode_to_vec(),
-            MessagingServiceType::Group,
-            &message_id,
-            true,
-        ) {
-            Ok(_) => {
-                // update member state
-                my_member.last_message_index = last_index;
-                Self::update_group_member(&user_account.id, &group_id, &my_member);
-            }
-            Err(err) => {
-                log::error!("group message sending failed {}", err);
-            }
-        }
+        // update member state
+        my_member.last_message_index = last_index;
+        Self::update_group_member(&user_account.id, &group.id, &my_member);
+
+        Some((message_id, send_message.encode_to_vec()))
     }
 
     /// Send group updated to all members
@@ -328,7 +370,12 @@ impl Group {
     }
 
     /// Process incoming NET messages for group chat module
-    pub fn net(sender_id: &PeerId, receiver_id: &PeerId, data: &Vec<u8>) {
+    ///
+    /// `group_message` is set for messages that arrived as group
+    /// messages, whose sender has been checked to be a member of
+    /// the group. Chat message updates are only accepted from
+    /// group messages.
+    pub fn net(sender_id: &PeerId, receiver_id: &PeerId, data: &Vec<u8>, group_message: bool) {
         // check receiver id is in users list
         let user;
         match UserAccounts::get_by_id(receiver_id.clone()) {
@@ -366,6 +413,22 @@ impl Group {
                         }
                     }
                 }
+                Some(proto_net::group_container::Message::SenderKey(sender_key)) => {
+                    log::trace!("group sender key arrived");
+                    if let Err(error) =
+                        GroupSenderKey::on_sender_key(sender_id, receiver_id, &sender_key)
+                    {
+                        log::error!("group on_sender_key error {}", error);
+                    }
+                }
+                Some(proto_net::group_container::Message::SenderKeyAck(ack)) => {
+                    log::trace!("group sender key confirmation arrived");
+                    if let Err(error) =
+                        GroupSenderKey::on_sender_key_ack(sender_id, receiver_id, &ack)
+                    {
+                        log::error!("group on_sender_key_ack error {}", error);
+                    }
+                }
                 Some(proto_net::group_container::Message::GroupInfo(group_info)) => {
                     log::trace!("group info arrived");
                     manage::GroupManage::on_group_notify(
@@ -374,6 +437,43 @@ impl Group {
                         &group_info,
                     );
                 }
+                Some(proto_net::group_container::Message::ChatEdit(_))
+                | Some(proto_net::group_container::Message::ChatDelete(_))
+                | Some(proto_net::group_container::Message::ChatReaction(_))
+                | Some(proto_net::group_container::Message::ChatRead(_))
+                    if !group_message =>
+                {
+                    log::warn!(
+                        "chat message update from {} outside of a group message",
+                        sender_id.to_base58()
+                    );
+                }
+                Some(proto_net::group_container::Message::ChatEdit(edit)) => {
+                    log::trace!("chat message edit arrived");
+                    if let Err(error) = chat::ChatMessage::on_edit(sender_id, receiver_id, &edit) {
+                        log::error!("chat message edit error {}", error);
+                    }
+                }
+                Some(proto_net::group_container::Message::ChatDelete(delete)) => {
+                    log::trace!("chat message deletion arrived");
+                    if let Err(error) =
+                        chat::ChatMessage::on_delete(sender_id, receiver_id, &delete)
+                    {
+                        log::error!("chat message delete error {}", error);
+                    }
+                }
+                Some(proto_net::group_container::Message::ChatReaction(reaction)) => {
+                    log::trace!("chat message reaction arrived");
+                    if let Err(error) =
+                        chat::ChatMessage::on_reaction(sender_id, receiver_id, &reaction)
+                    {
+                        log::error!("chat message reaction error {}", error);
+                    }
+                }
+                Some(proto_net::group_container::Message::ChatRead(receipt)) => {
+                    log::trace!("chat read receipt arrived");
+                    chat::ChatMessage::on_read_receipt(sender_id, receiver_id, &receipt);
+                }
                 None => {
                     log::error!("group message from {} was empty", sender_id.to_base58())
                 }
@@ -2,7 +2,7 @@
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GroupContainer {
-    #[prost(oneof = "group_container::Message", tags = "1, 2, 3, 4")]
+    #[prost(oneof = "group_container::Message", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10")]
     pub message: ::core::option::Option<group_container::Message>,
 }
 /// Nested message and enum types in `GroupContainer`.
@@ -22,6 +22,24 @@ pub mod group_container {
         /// member removed
         #[prost(message, tag = "4")]
         Removed(super::RemovedMember),
+        /// group sender key
+        #[prost(message, tag = "5")]
+        SenderKey(super::SenderKey),
+        /// sender key received confirmation
+        #[prost(message, tag = "6")]
+        SenderKeyAck(super::SenderKeyAck),
+        /// chat message edited by its sender
+        #[prost(message, tag = "7")]
+        ChatEdit(super::ChatMessageEdit),
+        /// chat message deleted by its sender
+        #[prost(message, tag = "8")]
+        ChatDelete(super::ChatMessageDelete),
+        /// reaction on a chat message
+        #[prost(message, tag = "9")]
+        ChatReaction(super::ChatMessageReaction),
+        /// chat messages read by the sender
+        #[prost(message, tag = "10")]
+        ChatRead(super::ChatReadReceipt),
     }
 }
 /// Invite member
@@ -95,6 +113,112 @@ pub struct RemovedMember {
     #[prost(bytes = "vec", tag = "1")]
     pub group_id: ::prost::alloc::vec::Vec<u8>,
 }
+/// Group Sender Key
+///
+/// Each member distributes its group sending key
+/// to all other members via the pairwise encrypted sessions.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SenderKey {
+    /// group id
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// id of the key
+    #[prost(uint32, tag = "2")]
+    pub key_id: u32,
+    /// symmetric key
+    #[prost(bytes = "vec", tag = "3")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+}
+/// Sender Key Received Confirmation
+///
+/// Confirms that the member has received the sender key
+/// and is able to decrypt messages encrypted with it.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SenderKeyAck {
+    /// group id
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// id of the received key
+    #[prost(uint32, tag = "2")]
+    pub key_id: u32,
+}
+/// Chat Message Edit
+///
+/// Replaces the text of a chat message.
+/// Only the original sender of the message can edit it.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatMessageEdit {
+    /// group id
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// id of the edited message
+    #[prost(bytes = "vec", tag = "2")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// new message text
+    #[prost(string, tag = "3")]
+    pub text: ::prost::alloc::string::String,
+    /// time of the edit
+    #[prost(uint64, tag = "4")]
+    pub edited_at: u64,
+}
+/// Chat Message Delete
+///
+/// Deletes a chat message for all group members.
+/// Only the original sender of the message can delete it.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatMessageDelete {
+    /// group id
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// id of the deleted message
+    #[prost(bytes = "vec", tag = "2")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// time of the deletion
+    #[prost(uint64, tag = "3")]
+    pub deleted_at: u64,
+}
+/// Chat Message Reaction
+///
+/// Adds or removes an emoji reaction of the sender
+/// on a chat message of the group.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatMessageReaction {
+    /// group id
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// id of the message reacted to
+    #[prost(bytes = "vec", tag = "2")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// reaction emoji
+    #[prost(string, tag = "3")]
+    pub emoji: ::prost::alloc::string::String,
+    /// true if the reaction was removed
+    #[prost(bool, tag = "4")]
+    pub remove: bool,
+}
+/// Chat Read Receipt
+///
+/// Informs the sender of chat messages, that
+/// the messages have been read.
+/// It is only sent to the sender of the messages.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatReadReceipt {
+    /// group id
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// ids of the read messages
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub message_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// time the messages were read
+    #[prost(uint64, tag = "3")]
+    pub read_at: u64,
+}
 /// Group member state
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -0,0 +1,545 @@
+// Copyright (c) 2022 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Group Sender Keys
+//!
+//! Group messages can be encrypted once with a group sending
+//! key of the sender, instead of once for every group member.
+//!
+//! Every member creates a random sending key per group and
+//! distributes it to the other members via the pairwise encrypted
+//! crypto sessions.
+//! The members confirm the receipt of the key.
+//! Only members that confirmed the current key receive messages
+//! encrypted with it, all other members, e.g. members running
+//! an older version of qaul, receive pairwise encrypted messages.
+//!
+//! The sending key is replaced as soon as a member leaves or
+//! is removed from the group.
+//!
+//! Every nonce of a sending key is only accepted once,
+//! replayed group messages are rejected.
+
+use libp2p::PeerId;
+use noise_protocol::CipherState;
+use noise_rust_crypto::ChaCha20Poly1305;
+use prost::Message;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+
+use super::{proto_net, proto_rpc::GroupMemberState, Group, GroupStorage};
+use crate::node::user_accounts::{UserAccount, UserAccounts};
+use crate::services::messaging::{proto, Messaging, MessagingServiceType};
+use crate::storage::configuration::Configuration;
+use crate::utilities::timestamp::Timestamp;
+
+/// Amount of keys kept per member, in order to decrypt
+/// delayed messages after a key rotation
+const MAX_MEMBER_KEYS: usize = 3;
+
+/// Amount of nonces below the highest received nonce,
+/// that are still accepted to allow out of order messages
+const NONCE_WINDOW: u64 = 64;
+
+/// Our own Sending Key for a Group
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SenderKeyState {
+    /// random id of the key
+    pub key_id: u32,
+    /// symmetric key
+    pub key: Vec<u8>,
+    /// nonce of the next message
+    pub nonce: u64,
+    /// members the key was sent to
+    pub members: Vec<Vec<u8>>,
+    /// members that confirmed the receipt of the key
+    pub acknowledged: Vec<Vec<u8>>,
+}
+
+/// Sending Key of a Group Member
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MemberKey {
+    /// symmetric key
+    pub key: Vec<u8>,
+    /// time the key was received
+    pub received_at: u64,
+    /// one above the highest nonce received with this key,
+    /// 0 if no message was received yet
+    pub next_nonce: u64,
+    /// received nonces below `next_nonce`,
+    /// bit n is set, if nonce `next_nonce - 1 - n` was received
+    pub received_nonces: u64,
+}
+
+/// Group Sender Key Structure
+pub struct GroupSenderKey {}
+
+impl GroupSenderKey {
+    /// send a message to all members of a group
+    ///
+    /// If sender keys are enabled, the message is encrypted once
+    /// with our sending key and sent to all members that confirmed
+    /// the key.
+    /// All other members receive a pairwise encrypted message.
+    pub fn send_to_members(
+        user_account: &UserAccount,
+        group: &Group,
+        data: Vec<u8>,
+        message_type: MessagingServiceType,
+        message_id: &Vec<u8>,
+    ) {
+        let my_id = user_account.id.to_bytes();
+        let mut pairwise: Vec<Vec<u8>> = group
+            .members
+            .values()
+            .filter(|member| {
+                member.user_id != my_id && member.state == GroupMemberState::Activated as i32
+            })
+            .map(|member| member.user_id.clone())
+            .collect();
+
+        if Configuration::get().group.sender_keys && !group.is_direct_chat {
+            let mut state = Self::get_sender_key(user_account, group);
+
+            let receivers: Vec<Vec<u8>> = pairwise
+                .iter()
+                .filter(|member_id| state.acknowledged.contains(member_id))
+                .cloned()
+                .collect();
+
+            if !receivers.is_empty() {
+                // encrypt message once
+                let group_encrypted = proto::GroupEncrypted {
+                    group_id: group.id.clone(),
+                    key_id: state.key_id,
+                    nonce: state.nonce,
+                    data: Self::encrypt(&state.key, state.nonce, &data),
+                };
+                state.nonce = state.nonce + 1;
+                Self::save_sender_key(&user_account.id, &group.id, &state);
+
+                for member_id in &receivers {
+                    let receiver = PeerId::from_bytes(member_id).unwrap();
+                    match Messaging::pack_and_send_group_encrypted_data(
+                        user_account,
+                        &receiver,
+                        group_encrypted.clone(),
+                        message_type.clone(),
+                        message_id,
+                        true,
+                    ) {
+                        Ok(signature) => {
+                            Messaging::save_unconfirmed_data(&message_type, &signature, &data)
+                        }
+                        Err(error) => log::error!("group message send error {}", error),
+                    }
+                }
+
+                pairwise.retain(|member_id| !receivers.contains(member_id));
+            }
+        }
+
+        // send pairwise encrypted messages
+        for member_id in pairwise {
+            let receiver = PeerId::from_bytes(&member_id).unwrap();
+            log::trace!("send message to {}", receiver.to_base58());
+            if let Err(error) = Messaging::pack_and_send_message(
+                user_account,
+                &receiver,
+                data.clone(),
+                message_type.clone(),
+                message_id,
+                true,
+            ) {
+                log::error!("group message send error {}", error);
+            }
+        }
+    }
+
+    /// get our sending key for a group
+    ///
+    /// A new key is created if there is none yet, or if a member,
+    /// who received the current key, is not in the group anymore.
+    /// The key is sent to all active members that haven't
+    /// received it yet.
+    fn get_sender_key(user_account: &UserAccount, group: &Group) -> SenderKeyState {
+        let mut state = match Self::load_sender_key(&user_account.id, &group.id) {
+            Some(state)
+                if state
+                    .members
+                    .iter()
+                    .all(|member_id| group.members.contains_key(member_id)) =>
+            {
+                state
+            }
+            _ => {
+                log::debug!("create new group sender key");
+                Self::create_sender_key()
+            }
+        };
+
+        // distribute key to new members
+        let my_id = user_account.id.to_bytes();
+        for (member_id, member) in &group.members {
+            if *member_id == my_id
+                || member.state != GroupMemberState::Activated as i32
+                || state.members.contains(member_id)
+            {
+                continue;
+            }
+
+            let container = proto_net::GroupContainer {
+                message: Some(proto_net::group_container::Message::SenderKey(
+                    proto_net::SenderKey {
+                        group_id: group.id.clone(),
+                        key_id: state.key_id,
+                        key: state.key.clone(),
+                    },
+                )),
+            };
+            let receiver = PeerId::from_bytes(member_id).unwrap();
+            Group::send_notify_message(user_account, &receiver, container.encode_to_vec());
+
+            state.members.push(member_id.clone());
+        }
+
+        Self::save_sender_key(&user_account.id, &group.id, &state);
+
+        state
+    }
+
+    /// create a new random sending key
+    fn create_sender_key() -> SenderKeyState {
+        let mut rng = thread_rng();
+        let key: [u8; 32] = rng.gen();
+
+        SenderKeyState {
+            key_id: rng.gen(),
+            key: key.to_vec(),
+            nonce: 0,
+            members: Vec::new(),
+            acknowledged: Vec::new(),
+        }
+    }
+
+    /// encrypt data with a sending key
+    fn encrypt(key: &Vec<u8>, nonce: u64, data: &Vec<u8>) -> Vec<u8> {
+        let mut cipher: CipherState<ChaCha20Poly1305> = CipherState::new(key.as_slice(), nonce);
+        cipher.encrypt_vec(data.as_slice())
+    }
+
+    /// decrypt a group message with the sending key of the sender
+    pub fn decrypt(
+        account_id: &PeerId,
+        sender_id: &PeerId,
+        group_encrypted: &proto::GroupEncrypted,
+    ) -> Option<Vec<u8>> {
+        // check if the sender is a member of the group
+        match GroupStorage::get_group(account_id.to_owned(), group_encrypted.group_id.clone()) {
+            Some(group) if group.is_member(&sender_id.to_bytes()) => {}
+            _ => {
+                log::error!("group sender key message from non member");
+                return None;
+            }
+        }
+
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+        let db_key = Self::member_key(
+            &group_encrypted.group_id,
+            sender_id,
+            Some(group_encrypted.key_id),
+        );
+        let mut member_key = match db_ref.member_keys.get(&db_key) {
+            Ok(Some(member_key_bytes)) => {
+                match bincode::deserialize::<MemberKey>(&member_key_bytes) {
+                    Ok(member_key) => member_key,
+                    Err(e) => {
+                        log::error!("{}", e);
+                        return None;
+                    }
+                }
+            }
+            Ok(None) => {
+                log::error!("group sender key {} not found", group_encrypted.key_id);
+                return None;
+            }
+            Err(e) => {
+                log::error!("{}", e);
+                return None;
+            }
+        };
+
+        let mut cipher: CipherState<ChaCha20Poly1305> =
+            CipherState::new(member_key.key.as_slice(), group_encrypted.nonce);
+        let decrypted = match cipher.decrypt_vec(group_encrypted.data.as_slice()) {
+            Ok(decrypted) => decrypted,
+            Err(_) => return None,
+        };
+
+        // reject replayed messages
+        if !Self::receive_nonce(&mut member_key, group_encrypted.nonce) {
+            log::error!(
+                "group sender key {} nonce {} replayed",
+                group_encrypted.key_id,
+                group_encrypted.nonce
+            );
+            return None;
+        }
+        let member_key_bytes = bincode::serialize(&member_key).unwrap();
+        if let Err(e) = db_ref.member_keys.insert(db_key, member_key_bytes) {
+            log::error!("Error saving group member key: {}", e);
+        }
+        if let Err(e) = db_ref.member_keys.flush() {
+            log::error!("Error member_keys flush: {}", e);
+        }
+
+        Some(decrypted)
+    }
+
+    /// register the nonce of a received message
+    ///
+    /// Returns false if the nonce was already received,
+    /// or is too old to be checked.
+    fn receive_nonce(member_key: &mut MemberKey, nonce: u64) -> bool {
+        if nonce == u64::MAX {
+            return false;
+        }
+
+        // newer nonce, move the window
+        if nonce >= member_key.next_nonce {
+            let shift = nonce + 1 - member_key.next_nonce;
+            member_key.received_nonces = if shift >= NONCE_WINDOW {
+                0
+            } else {
+                member_key.received_nonces << shift
+            };
+            member_key.received_nonces |= 1;
+            member_key.next_nonce = nonce + 1;
+            return true;
+        }
+
+        // older nonce within the window
+        let offset = member_key.next_nonce - 1 - nonce;
+        if offset >= NONCE_WINDOW || member_key.received_nonces & (1 << offset) != 0 {
+            return false;
+        }
+        member_key.received_nonces |= 1 << offset;
+
+        true
+    }
+
+    /// process a received sending key of a group member
+    pub fn on_sender_key(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        sender_key: &proto_net::SenderKey,
+    ) -> Result<bool, String> {
+        // check if the sender is a member of the group
+        match GroupStorage::get_group(account_id.to_owned(), sender_key.group_id.clone()) {
+            Some(group) => {
+                if !group.is_member(&sender_id.to_bytes()) {
+                    return Err("the sender is not member in this group".to_string());
+                }
+            }
+            None => return Err("group not found".to_string()),
+        }
+
+        // save key
+        // a key received again keeps its received nonces
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+        let db_key = Self::member_key(&sender_key.group_id, sender_id, Some(sender_key.key_id));
+        if db_ref.member_keys.contains_key(&db_key).unwrap_or(false) {
+            log::debug!("group sender key {} already received", sender_key.key_id);
+        } else {
+            let member_key = MemberKey {
+                key: sender_key.key.clone(),
+                received_at: Timestamp::get_timestamp(),
+                next_nonce: 0,
+                received_nonces: 0,
+            };
+            let member_key_bytes = bincode::serialize(&member_key).unwrap();
+            if let Err(e) = db_ref.member_keys.insert(db_key, member_key_bytes) {
+                log::error!("Error saving group member key: {}", e);
+            }
+        }
+        Self::prune_member_keys(account_id, &sender_key.group_id, sender_id);
+
+        // confirm the receipt of the key
+        if Configuration::get().group.sender_keys {
+            if let Some(user_account) = UserAccounts::get_by_id(account_id.to_owned()) {
+                let container = proto_net::GroupContainer {
+                    message: Some(proto_net::group_container::Message::SenderKeyAck(
+                        proto_net::SenderKeyAck {
+                            group_id: sender_key.group_id.clone(),
+                            key_id: sender_key.key_id,
+                        },
+                    )),
+                };
+                Group::send_notify_message(&user_account, sender_id, container.encode_to_vec());
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// process the receipt confirmation of our sending key
+    pub fn on_sender_key_ack(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        ack: &proto_net::SenderKeyAck,
+    ) -> Result<bool, String> {
+        let mut state;
+        match Self::load_sender_key(account_id, &ack.group_id) {
+            Some(my_state) => state = my_state,
+            None => return Err("no sender key for this group".to_string()),
+        }
+
+        // the confirmation is only valid for the current key
+        let member_id = sender_id.to_bytes();
+        if state.key_id != ack.key_id || !state.members.contains(&member_id) {
+            return Err("outdated sender key confirmation".to_string());
+        }
+
+        if !state.acknowledged.contains(&member_id) {
+            state.acknowledged.push(member_id);
+            Self::save_sender_key(account_id, &ack.group_id, &state);
+        }
+
+        Ok(true)
+    }
+
+    /// a member left or was removed from the group
+    ///
+    /// The keys of the member are deleted and our sending key
+    /// is replaced with the next message.
+    pub fn remove_member(account_id: &PeerId, group_id: &Vec<u8>, member_id: &Vec<u8>) {
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+
+        if let Ok(member_id) = PeerId::from_bytes(member_id) {
+            let prefix = Self::member_key(group_id, &member_id, None);
+            Self::remove_prefix(&db_ref.member_keys, &prefix);
+        }
+
+        if let Err(e) = db_ref.sender_keys.remove(group_id) {
+            log::error!("Error removing group sender key: {}", e);
+        }
+        if let Err(e) = db_ref.sender_keys.flush() {
+            log::error!("Error sender_keys flush: {}", e);
+        }
+    }
+
+    /// we left or were removed from the group
+    ///
+    /// All keys of this group are deleted.
+    pub fn remove_group(account_id: &PeerId, group_id: &Vec<u8>) {
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+
+        Self::remove_prefix(&db_ref.member_keys, group_id);
+
+        if let Err(e) = db_ref.sender_keys.remove(group_id) {
+            log::error!("Error removing group sender key: {}", e);
+        }
+        if let Err(e) = db_ref.sender_keys.flush() {
+            log::error!("Error sender_keys flush: {}", e);
+        }
+    }
+
+    /// load our sending key of a group from data base
+    fn load_sender_key(account_id: &PeerId, group_id: &Vec<u8>) -> Option<SenderKeyState> {
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+
+        match db_ref.sender_keys.get(group_id) {
+            Ok(Some(state_bytes)) => match bincode::deserialize(&state_bytes) {
+                Ok(state) => Some(state),
+                Err(e) => {
+                    log::error!("{}", e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// save our sending key of a group to data base
+    fn save_sender_key(account_id: &PeerId, group_id: &Vec<u8>, state: &SenderKeyState) {
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+
+        let state_bytes = bincode::serialize(state).unwrap();
+        if let Err(e) = db_ref.sender_keys.insert(group_id.clone(), state_bytes) {
+            log::error!("Error saving group sender key: {}", e);
+        }
+        if let Err(e) = db_ref.sender_keys.flush() {
+            log::error!("Error sender_keys flush: {}", e);
+        }
+    }
+
+    /// create the data base key of a member key
+    ///
+    /// The db key is a Vec<u8> of:
+    /// {group_id}{member_id}{key_id}
+    ///
+    /// Without a key id, the prefix of all keys of
+    /// this member is returned.
+    fn member_key(group_id: &Vec<u8>, member_id: &PeerId, key_id: Option<u32>) -> Vec<u8> {
+        let mut db_key = group_id.clone();
+        db_key.extend(member_id.to_bytes());
+        if let Some(key_id) = key_id {
+            db_key.extend(key_id.to_be_bytes());
+        }
+        db_key
+    }
+
+    /// only keep the newest keys of a member
+    fn prune_member_keys(account_id: &PeerId, group_id: &Vec<u8>, member_id: &PeerId) {
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+        let prefix = Self::member_key(group_id, member_id, None);
+
+        // the key ids are random, sort the keys by receipt
+        let mut keys: Vec<(u64, sled::IVec)> = Vec::new();
+        for result in db_ref.member_keys.scan_prefix(&prefix) {
+            match result {
+                Ok((key, member_key_bytes)) => {
+                    let received_at = bincode::deserialize::<MemberKey>(&member_key_bytes)
+                        .map(|member_key| member_key.received_at)
+                        .unwrap_or(0);
+                    keys.push((received_at, key));
+                }
+                Err(e) => log::error!("{}", e),
+            }
+        }
+        keys.sort_by_key(|(received_at, _)| *received_at);
+
+        let remove = keys.len().saturating_sub(MAX_MEMBER_KEYS);
+        for (_, key) in keys.into_iter().take(remove) {
+            if let Err(e) = db_ref.member_keys.remove(key) {
+                log::error!("{}", e);
+            }
+        }
+
+        if let Err(e) = db_ref.member_keys.flush() {
+            log::error!("Error member_keys flush: {}", e);
+        }
+    }
+
+    /// remove all entries of a tree with a key prefix
+    fn remove_prefix(tree: &sled::Tree, prefix: &Vec<u8>) {
+        for key in tree.scan_prefix(prefix).keys() {
+            match key {
+                Ok(key) => {
+                    if let Err(e) = tree.remove(key) {
+                        log::error!("{}", e);
+                    }
+                }
+                Err(e) => log::error!("{}", e),
+            }
+        }
+
+        if let Err(e) = tree.flush() {
+            log::error!("Error flush: {}", e);
+        }
+    }
+}
@@ -26,6 +26,16 @@ pub struct GroupAccountDb {
     /// invited DB ref
     /// bincode of `GroupInvited`
     pub invited: sled::Tree,
+    /// our own group sending keys
+    ///
+    /// key: {group_id}
+    /// value: bincode of `SenderKeyState`
+    pub sender_keys: sled::Tree,
+    /// sending keys received from the group members
+    ///
+    /// key: {group_id}{member_id}{key_id}
+    /// value: bincode of `MemberKey`
+    pub member_keys: sled::Tree,
 }
 
 /// qaul Chat Conversation Storage
@@ -53,10 +63,7 @@ impl GroupStorage {
 
             // check if user account ID is in map
             if let Some(group_account_db) = group_storage.db_ref.get(&account_id.to_bytes()) {
-                return GroupAccountDb {
-                    groups: group_account_db.groups.clone(),
-                    invited: group_account_db.invited.clone(),
-                };
+                return group_account_db.clone();
             }
         }
 
@@ -64,10 +71,7 @@ impl GroupStorage {
         let group_account_db = Self::create_groupaccountdb(account_id);
 
         // return group_account_db structure
-        GroupAccountDb {
-            groups: group_account_db.groups.clone(),
-            invited: group_account_db.invited.clone(),
-        }
+        group_account_db
     }
 
     /// create group account db entry when it does not exist
@@ -78,8 +82,15 @@ impl GroupStorage {
         // open trees
         let groups: sled::Tree = db.open_tree("groups").unwrap();
         let invited: sled::Tree = db.open_tree("invited").unwrap();
-
-        let group_account_db = GroupAccountDb { groups, invited };
+        let sender_keys: sled::Tree = db.open_tree("group_sender_keys").unwrap();
+        let member_keys: sled::Tree = db.open_tree("group_member_keys").unwrap();
+
+        let group_account_db = GroupAccountDb {
+            groups,
+            invited,
+            sender_keys,
+            member_keys,
+        };
 
         // get group storage for writing
         let mut group_storage = GROUPSTORAGE.get().write().unwrap();
@@ -111,6 +122,25 @@ impl GroupStorage {
         None
     }
 
+    /// get all groups of a user account from data base
+    pub fn get_groups(account_id: PeerId) -> Vec<Group> {
+        // get DB ref
+        let db_ref = Self::get_db_ref(account_id);
+
+        let mut groups: Vec<Group> = Vec::new();
+        for res in db_ref.groups.iter() {
+            match res {
+                Ok((_, group_bytes)) => match bincode::deserialize(&group_bytes) {
+                    Ok(group) => groups.push(group),
+                    Err(e) => log::error!("{}", e),
+                },
+                Err(e) => log::error!("{}", e),
+            }
+        }
+
+        groups
+    }
+
     /// Check if a group exists in the data base
     pub fn group_exists(account_id: PeerId, group_id: Vec<u8>) -> bool {
         // get DB ref
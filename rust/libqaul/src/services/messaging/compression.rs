@@ -0,0 +1,239 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Messaging Compression
+//!
+//! Messaging messages are compressed before encryption.
+//! The compression flag is therefore only visible to the receiver.
+//!
+//! Once a crypto session has been established, both users announce
+//! the compression algorithms they support for this session.
+//! Messages are only compressed for users that announced
+//! an algorithm for the session the message is encrypted with.
+//! Older versions of qaul never announce any capabilities and
+//! therefore always receive uncompressed messages.
+
+use libp2p::PeerId;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use sled;
+use state::InitCell;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use super::{proto, Messaging, MessagingServiceType};
+use crate::node::user_accounts::UserAccount;
+use crate::services::crypto::Crypto;
+use crate::storage::configuration::Configuration;
+use crate::storage::database::DataBase;
+use crate::utilities::compression::{Compression, CompressionStats};
+
+/// maximal number of sessions for which the capabilities
+/// of a remote user are kept
+const MAX_SESSIONS: usize = 4;
+
+/// mutable state of the messaging compression
+static COMPRESSION: InitCell<RwLock<MessagingCompression>> = InitCell::new();
+
+/// Capabilities of a Remote User for a Crypto Session
+#[derive(Serialize, Deserialize, Clone)]
+struct SessionCapabilities {
+    /// crypto session id
+    session_id: u32,
+    /// supported compression algorithms
+    compression: Vec<i32>,
+}
+
+/// Messaging Compression Structure
+pub struct MessagingCompression {
+    /// capabilities data base trees per user account
+    ///
+    /// remote user id => Vec<SessionCapabilities>
+    ///
+    /// value: bincode of `Vec<SessionCapabilities>`,
+    /// the latest session first
+    db_ref: BTreeMap<Vec<u8>, sled::Tree>,
+    /// statistics of the compressed messages
+    stats: CompressionStats,
+}
+
+impl MessagingCompression {
+    /// initialize messaging compression state
+    pub fn init() {
+        let compression = MessagingCompression {
+            db_ref: BTreeMap::new(),
+            stats: CompressionStats::default(),
+        };
+        COMPRESSION.set(RwLock::new(compression));
+    }
+
+    /// Announce our capabilities to a remote user
+    ///
+    /// This function is called once a crypto session with the
+    /// remote user has been established.
+    pub fn announce(user_account: &UserAccount, remote_id: &PeerId) {
+        let session_id = match Crypto::session_id(user_account, remote_id.clone()) {
+            Some(session_id) => session_id,
+            None => return,
+        };
+
+        let mut compression = Vec::new();
+        if Configuration::get().messaging.compression {
+            compression.push(proto::CompressionAlgorithm::Deflate as i32);
+        }
+
+        let send_message = proto::Messaging {
+            message: Some(proto::messaging::Message::Capabilities(
+                proto::Capabilities {
+                    session_id,
+                    compression,
+                },
+            )),
+        };
+
+        if let Err(e) = Messaging::pack_and_send_message(
+            user_account,
+            remote_id,
+            send_message.encode_to_vec(),
+            MessagingServiceType::Unconfirmed,
+            &Vec::new(),
+            false,
+        ) {
+            log::error!("sending capabilities failed: {}", e);
+        }
+    }
+
+    /// Save the announced capabilities of a remote user
+    pub fn on_capabilities(
+        user_account: &UserAccount,
+        remote_id: &PeerId,
+        capabilities: proto::Capabilities,
+    ) {
+        log::trace!(
+            "capabilities of {} for session {} received",
+            remote_id.to_base58(),
+            capabilities.session_id
+        );
+
+        let tree = Self::get_db_ref(user_account.id);
+        let mut sessions = Self::get_sessions(&tree, remote_id);
+        sessions.retain(|session| session.session_id != capabilities.session_id);
+        sessions.insert(
+            0,
+            SessionCapabilities {
+                session_id: capabilities.session_id,
+                compression: capabilities.compression,
+            },
+        );
+        sessions.truncate(MAX_SESSIONS);
+
+        if let Err(e) = tree.insert(remote_id.to_bytes(), bincode::serialize(&sessions).unwrap()) {
+            log::error!("Error saving capabilities: {}", e);
+        }
+        if let Err(e) = tree.flush() {
+            log::error!("Error capabilities flush: {}", e);
+        }
+    }
+
+    /// Compress an encoded messaging message
+    ///
+    /// The message is compressed, if the receiver announced
+    /// support for compression in the current crypto session,
+    /// and if the message size is reduced by the compression.
+    /// Otherwise the data is returned unchanged.
+    pub fn compress(user_account: &UserAccount, receiver: &PeerId, data: Vec<u8>) -> Vec<u8> {
+        if !Configuration::get().messaging.compression {
+            return data;
+        }
+        if !Self::supports_deflate(user_account, receiver) {
+            return data;
+        }
+
+        let compressed = match Compression::deflate(&data) {
+            Some(compressed) => compressed,
+            None => return data,
+        };
+
+        let send_message = proto::Messaging {
+            message: Some(proto::messaging::Message::Compressed(proto::Compressed {
+                algorithm: proto::CompressionAlgorithm::Deflate as i32,
+                data: compressed,
+            })),
+        };
+        let buf = send_message.encode_to_vec();
+
+        {
+            let mut state = COMPRESSION.get().write().unwrap();
+            state.stats.add(data.len(), buf.len());
+        }
+
+        buf
+    }
+
+    /// Decompress a received compressed messaging message
+    ///
+    /// Returns the encoded messaging message.
+    pub fn decompress(compressed: &proto::Compressed) -> Option<Vec<u8>> {
+        match proto::CompressionAlgorithm::try_from(compressed.algorithm) {
+            Ok(proto::CompressionAlgorithm::Deflate) => Compression::inflate(&compressed.data),
+            _ => {
+                log::error!("unsupported compression algorithm {}", compressed.algorithm);
+                None
+            }
+        }
+    }
+
+    /// Get the statistics of the compressed messages
+    pub fn get_stats() -> CompressionStats {
+        let state = COMPRESSION.get().read().unwrap();
+        state.stats
+    }
+
+    /// check if the receiver supports deflate in the current session
+    fn supports_deflate(user_account: &UserAccount, receiver: &PeerId) -> bool {
+        let session_id = match Crypto::session_id(user_account, receiver.clone()) {
+            Some(session_id) => session_id,
+            None => return false,
+        };
+
+        let tree = Self::get_db_ref(user_account.id);
+        Self::get_sessions(&tree, receiver)
+            .iter()
+            .find(|session| session.session_id == session_id)
+            .map(|session| {
+                session
+                    .compression
+                    .contains(&(proto::CompressionAlgorithm::Deflate as i32))
+            })
+            .unwrap_or(false)
+    }
+
+    /// get the saved session capabilities of a remote user
+    fn get_sessions(tree: &sled::Tree, remote_id: &PeerId) -> Vec<SessionCapabilities> {
+        match tree.get(remote_id.to_bytes()) {
+            Ok(Some(sessions_bytes)) => bincode::deserialize(&sessions_bytes).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// get capabilities data base tree of a user account
+    fn get_db_ref(account_id: PeerId) -> sled::Tree {
+        // check if user account tree is already open
+        {
+            let state = COMPRESSION.get().read().unwrap();
+            if let Some(tree) = state.db_ref.get(&account_id.to_bytes()) {
+                return tree.clone();
+            }
+        }
+
+        // open tree
+        let db = DataBase::get_user_db(account_id);
+        let tree: sled::Tree = db.open_tree("capabilities").unwrap();
+
+        // add tree to state
+        let mut state = COMPRESSION.get().write().unwrap();
+        state.db_ref.insert(account_id.to_bytes(), tree.clone());
+
+        tree
+    }
+}
@@ -0,0 +1,311 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Failed Qaul Messages
+//!
+//! Messages that couldn't be delivered within the configured
+//! amount of attempts or time are removed from the unconfirmed table
+//! and saved as failed messages per user account.
+//! The same applies to messages that waited too long for a
+//! crypto handshake with their receiver.
+//!
+//! The client is informed about the failed delivery of chat messages
+//! and can request to resend them.
+
+use libp2p::PeerId;
+use prost::Message;
+use sled;
+use state::InitCell;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use super::{FailedMessage, Messaging, MessagingServiceType, UnConfirmedMessage};
+use crate::node::user_accounts::UserAccounts;
+use crate::rpc::Rpc;
+use crate::services::chat::{rpc_proto, ChatStorage};
+use crate::services::crypto::CryptoPendingMessage;
+use crate::storage::database::DataBase;
+
+/// mutable state of the failed messages data base references
+static FAILED: InitCell<RwLock<MessagingFailed>> = InitCell::new();
+
+/// Failed Messages Structure
+pub struct MessagingFailed {
+    /// failed messages data base trees per user account
+    ///
+    /// signature => FailedMessage
+    ///
+    /// Messages that were never sent are saved with a random key.
+    ///
+    /// value: bincode of `FailedMessage`
+    db_ref: BTreeMap<Vec<u8>, sled::Tree>,
+}
+
+impl MessagingFailed {
+    /// initialize failed messages state
+    pub fn init() {
+        let failed = MessagingFailed {
+            db_ref: BTreeMap::new(),
+        };
+        FAILED.set(RwLock::new(failed));
+    }
+
+    /// Mark the delivery of an unconfirmed message as failed
+    ///
+    /// The message is saved in the failed messages table of
+    /// the sending user account.
+    /// The status of chat messages is set to failed and the
+    /// client is informed via RPC.
+    pub fn on_failed(signature: &Vec<u8>, unconfirmed: UnConfirmedMessage, reason: &str) {
+        let container = match super::proto::Container::decode(&unconfirmed.container[..]) {
+            Ok(container) => container,
+            Err(e) => {
+                log::error!("failed message container decoding error: {}", e);
+                return;
+            }
+        };
+        let envelope = match container.envelope {
+            Some(envelope) => envelope,
+            None => return,
+        };
+
+        // only messages of local user accounts are saved
+        let account_id = match PeerId::from_bytes(&envelope.sender_id) {
+            Ok(account_id) => account_id,
+            Err(_) => return,
+        };
+        if UserAccounts::get_by_id(account_id).is_none() {
+            return;
+        }
+
+        log::debug!(
+            "delivery of message {} failed: {}",
+            bs58::encode(signature).into_string(),
+            reason
+        );
+
+        // update chat message status
+        let mut group_id = Vec::new();
+        if let MessagingServiceType::Chat = unconfirmed.message_type {
+            if let Some(id) = ChatStorage::on_delivery_failed(&account_id, &unconfirmed.message_id)
+            {
+                group_id = id;
+            }
+        }
+
+        let failed = FailedMessage {
+            user_id: unconfirmed.receiver_id.clone(),
+            group_id,
+            created_at: envelope.created_at,
+            last_try: unconfirmed.last_sent,
+            try_count: unconfirmed.retry,
+            message: reason.to_string(),
+            message_id: unconfirmed.message_id.clone(),
+            message_type: unconfirmed.message_type.clone(),
+            data: Messaging::take_unconfirmed_data(signature).unwrap_or_default(),
+        };
+
+        Self::save_failed(account_id, signature.clone(), failed);
+    }
+
+    /// Mark a message as failed, that waited too long for a crypto handshake
+    ///
+    /// Such messages have never been sent and have no signature,
+    /// they are saved with a random key.
+    /// Messages that don't need a confirmation are dropped.
+    pub fn on_pending_failed(
+        account_id: PeerId,
+        receiver: &PeerId,
+        pending: CryptoPendingMessage,
+        reason: &str,
+    ) {
+        if !pending.needs_confirmation {
+            return;
+        }
+
+        log::debug!(
+            "message to {} waiting for key exchange failed: {}",
+            receiver.to_base58(),
+            reason
+        );
+
+        // update chat message status
+        let mut group_id = Vec::new();
+        if let MessagingServiceType::Chat = pending.message_type {
+            if let Some(id) = ChatStorage::on_delivery_failed(&account_id, &pending.message_id) {
+                group_id = id;
+            }
+        }
+
+        let failed = FailedMessage {
+            user_id: receiver.to_bytes(),
+            group_id,
+            created_at: pending.created_at,
+            last_try: pending.created_at,
+            try_count: 0,
+            message: reason.to_string(),
+            message_id: pending.message_id,
+            message_type: pending.message_type,
+            data: pending.data,
+        };
+
+        Self::save_failed(account_id, uuid::Uuid::new_v4().as_bytes().to_vec(), failed);
+    }
+
+    /// save a failed message and inform the client
+    fn save_failed(account_id: PeerId, key: Vec<u8>, failed: FailedMessage) {
+        let tree = Self::get_db_ref(account_id);
+        if let Err(e) = tree.insert(key, bincode::serialize(&failed).unwrap()) {
+            log::error!("Error saving failed message: {}", e);
+        }
+        if let Err(e) = tree.flush() {
+            log::error!("Error failed messages flush: {}", e);
+        }
+
+        // inform client
+        match failed.message_type {
+            MessagingServiceType::Chat | MessagingServiceType::ChatFile => {
+                let proto_message = rpc_proto::Chat {
+                    message: Some(rpc_proto::chat::Message::DeliveryFailed(
+                        rpc_proto::ChatDeliveryFailed {
+                            message: Some(Self::to_chat_rpc(&failed)),
+                        },
+                    )),
+                };
+                Rpc::send_message(
+                    proto_message.encode_to_vec(),
+                    crate::rpc::proto::Modules::Chat.into(),
+                    "".to_string(),
+                    account_id.to_bytes(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Get all failed messages of a user account
+    pub fn get_failed_messages(account_id: PeerId) -> Vec<FailedMessage> {
+        let tree = Self::get_db_ref(account_id);
+        let mut failed_messages: Vec<FailedMessage> = Vec::new();
+
+        for entry in tree.iter() {
+            if let Ok((_signature, failed_bytes)) = entry {
+                if let Ok(failed) = bincode::deserialize::<FailedMessage>(&failed_bytes) {
+                    failed_messages.push(failed);
+                }
+            }
+        }
+
+        failed_messages
+    }
+
+    /// Resend a failed message
+    ///
+    /// The message is packed again for all receivers, to
+    /// which its delivery failed, and gets a new envelope and signature.
+    /// The amount of resent messages is returned.
+    pub fn resend(account_id: PeerId, message_id: &Vec<u8>) -> usize {
+        let user_account = match UserAccounts::get_by_id(account_id) {
+            Some(user_account) => user_account,
+            None => return 0,
+        };
+        let tree = Self::get_db_ref(account_id);
+        let mut count = 0;
+
+        for entry in tree.iter() {
+            let (signature, failed_bytes) = match entry {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            let failed: FailedMessage = match bincode::deserialize(&failed_bytes) {
+                Ok(failed) => failed,
+                Err(_) => continue,
+            };
+            if &failed.message_id != message_id {
+                continue;
+            }
+
+            // only messages whose data was kept can be packed again
+            if failed.data.is_empty() {
+                log::warn!(
+                    "failed message {} can't be resent",
+                    bs58::encode(&signature).into_string()
+                );
+                continue;
+            }
+            let receiver = match PeerId::from_bytes(&failed.user_id) {
+                Ok(receiver) => receiver,
+                Err(_) => continue,
+            };
+
+            // pack and send message again
+            if let Err(e) = Messaging::pack_and_send_message(
+                &user_account,
+                &receiver,
+                failed.data,
+                failed.message_type,
+                &failed.message_id,
+                true,
+            ) {
+                log::error!("resending failed message error: {}", e);
+                continue;
+            }
+
+            // remove failed message
+            if let Err(e) = tree.remove(signature) {
+                log::error!("Error removing failed message: {}", e);
+            }
+            count += 1;
+        }
+
+        if count > 0 {
+            if let Err(e) = tree.flush() {
+                log::error!("Error failed messages flush: {}", e);
+            }
+
+            // update chat message status
+            ChatStorage::update_status_from(
+                &account_id,
+                message_id,
+                rpc_proto::MessageStatus::Failed,
+                rpc_proto::MessageStatus::Sending,
+            );
+        }
+
+        count
+    }
+
+    /// Convert a failed message to the chat RPC structure
+    pub fn to_chat_rpc(failed: &FailedMessage) -> rpc_proto::ChatFailedMessage {
+        rpc_proto::ChatFailedMessage {
+            message_id: failed.message_id.clone(),
+            group_id: failed.group_id.clone(),
+            receiver_id: failed.user_id.clone(),
+            created_at: failed.created_at,
+            last_try: failed.last_try,
+            try_count: failed.try_count,
+            reason: failed.message.clone(),
+        }
+    }
+
+    /// get failed messages data base tree of a user account
+    fn get_db_ref(account_id: PeerId) -> sled::Tree {
+        // check if user account tree is already open
+        {
+            let failed = FAILED.get().read().unwrap();
+            if let Some(tree) = failed.db_ref.get(&account_id.to_bytes()) {
+                return tree.clone();
+            }
+        }
+
+        // open tree
+        let db = DataBase::get_user_db(account_id);
+        let tree: sled::Tree = db.open_tree("failed_messages").unwrap();
+
+        // add tree to state
+        let mut failed = FAILED.get().write().unwrap();
+        failed.db_ref.insert(account_id.to_bytes(), tree.clone());
+
+        tree
+    }
+}
@@ -0,0 +1,376 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Fragmentation of Big Messages
+//!
+//! The connection modules can only transfer messages up to a maximal
+//! size. Envelope payloads exceeding the maximal size of the
+//! connection module of the next hop are split into fragments.
+//! Every fragment is signed and sent in its own container.
+//!
+//! The first fragment is saved in the unconfirmed table with the
+//! message type of the payload. It is confirmed by the receiver,
+//! once the payload has been reassembled and processed.
+//! All other fragments are saved with the fragment message type,
+//! and are confirmed by the receiver on reception.
+//! This way every fragment is retransmitted individually.
+//!
+//! The receiver saves the fragments to the node data base, until
+//! all fragments of a payload have arrived.
+//! The fragment data buffered per sender is limited to
+//! `MAX_SENDER_BYTES`. Further fragments are not confirmed and
+//! are retransmitted by the sender later on.
+
+use libp2p::PeerId;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use sled;
+use state::InitCell;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use super::proto;
+use crate::connections::ConnectionModule;
+use crate::router::table::RoutingTable;
+use crate::storage::configuration::Configuration;
+use crate::storage::database::DataBase;
+use crate::utilities::timestamp::Timestamp;
+
+/// maximal payload size of a message sent via LAN or Internet
+///
+/// The frames of the messaging protocol are limited to 64KiB.
+/// A margin is left for the container, envelope and signature.
+pub const FRAGMENT_SIZE: usize = 64512;
+
+/// maximal payload size of a message sent via BLE
+pub const FRAGMENT_SIZE_BLE: usize = 16384;
+
+/// maximal number of fragments of a payload
+const MAX_FRAGMENTS: u32 = 4096;
+
+/// maximal amount of fragment data in bytes buffered per sender
+const MAX_SENDER_BYTES: usize = 32 * 1024 * 1024;
+
+/// length of the fragment id and index at the end of a fragment key
+const KEY_SUFFIX_LENGTH: usize = 12;
+
+/// interval between two cleanups of the fragment tables in milliseconds
+const CLEANUP_INTERVAL: u64 = 60 * 1000;
+
+/// mutable state of the fragment tables
+static FRAGMENTS: InitCell<RwLock<MessagingFragment>> = InitCell::new();
+
+/// Received Fragment
+#[derive(Serialize, Deserialize, Clone)]
+struct FragmentEntry {
+    /// total number of fragments
+    count: u32,
+    /// time the fragment was received
+    received_at: u64,
+    /// signature of the container
+    signature: Vec<u8>,
+    /// fragment data
+    data: Vec<u8>,
+}
+
+/// Fragmentation and Reassembly Structure
+pub struct MessagingFragment {
+    /// received fragments
+    ///
+    /// key: {sender_id}{fragment_id}{index}
+    ///
+    /// value: bincode of `FragmentEntry`
+    fragments: sled::Tree,
+    /// reassembled payloads
+    ///
+    /// Duplicate fragments of reassembled payloads are
+    /// confirmed again, but not processed.
+    ///
+    /// key: {sender_id}{fragment_id}
+    ///
+    /// value: reassembly time as big endian u64
+    completed: sled::Tree,
+    /// amount of fragment data buffered per sender
+    ///
+    /// key: sender_id
+    buffered: BTreeMap<Vec<u8>, usize>,
+    /// time of the last cleanup
+    last_cleanup: u64,
+}
+
+impl MessagingFragment {
+    /// initialize the fragment tables
+    pub fn init() {
+        let db = DataBase::get_node_db();
+        let fragments: sled::Tree = db.open_tree("fragments").unwrap();
+        let completed: sled::Tree = db.open_tree("fragments_completed").unwrap();
+
+        // count the buffered fragment data of each sender
+        let mut buffered: BTreeMap<Vec<u8>, usize> = BTreeMap::new();
+        for result in fragments.iter() {
+            if let Ok((key, entry_bytes)) = result {
+                if let Ok(entry) = bincode::deserialize::<FragmentEntry>(&entry_bytes) {
+                    *buffered.entry(Self::sender_of(&key)).or_insert(0) += entry.data.len();
+                }
+            }
+        }
+
+        let fragment = MessagingFragment {
+            fragments,
+            completed,
+            buffered,
+            last_cleanup: 0,
+        };
+        FRAGMENTS.set(RwLock::new(fragment));
+    }
+
+    /// Get the maximal payload size for a receiver
+    ///
+    /// The size depends on the connection module of the
+    /// next hop towards the receiver.
+    pub fn fragment_size(receiver: &PeerId) -> usize {
+        match RoutingTable::get_route_to_user(receiver.clone()) {
+            Some(route) => match route.module {
+                ConnectionModule::Ble => FRAGMENT_SIZE_BLE,
+                _ => FRAGMENT_SIZE,
+            },
+            None => FRAGMENT_SIZE,
+        }
+    }
+
+    /// Split an encoded envelope payload into fragments
+    pub fn split(payload: &Vec<u8>, fragment_size: usize) -> Vec<proto::EnvelopPayload> {
+        let fragment_id: u64 = rand::random();
+        let chunks: Vec<&[u8]> = payload.chunks(fragment_size).collect();
+        let count = chunks.len() as u32;
+
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| proto::EnvelopPayload {
+                payload: Some(proto::envelop_payload::Payload::Fragment(proto::Fragment {
+                    fragment_id,
+                    index: index as u32,
+                    count,
+                    data: data.to_vec(),
+                })),
+            })
+            .collect()
+    }
+
+    /// Process a received fragment
+    ///
+    /// Returns whether the reception of this fragment shall be
+    /// confirmed, and the reassembled payload with the signature of
+    /// its first fragment, once all fragments have arrived.
+    pub fn on_fragment(
+        sender_id: &PeerId,
+        fragment: proto::Fragment,
+        signature: &Vec<u8>,
+    ) -> (bool, Option<(proto::EnvelopPayload, Vec<u8>)>) {
+        if fragment.count == 0 || fragment.count > MAX_FRAGMENTS || fragment.index >= fragment.count
+        {
+            log::error!("invalid fragment from {}", sender_id.to_base58());
+            return (false, None);
+        }
+
+        let mut state = FRAGMENTS.get().write().unwrap();
+        let prefix = Self::prefix(sender_id, fragment.fragment_id);
+
+        // confirm duplicate fragments of reassembled payloads
+        if state.completed.contains_key(&prefix).unwrap_or(false) {
+            return (true, None);
+        }
+
+        // all fragments of a payload need to have the same count
+        // as the first fragment received
+        let first_entry = state.fragments.scan_prefix(&prefix).next();
+        if let Some(Ok((_key, entry_bytes))) = first_entry {
+            match bincode::deserialize::<FragmentEntry>(&entry_bytes) {
+                Ok(first) if first.count == fragment.count => {}
+                Ok(_) => {
+                    log::error!("fragment count mismatch from {}", sender_id.to_base58());
+                    return (false, None);
+                }
+                Err(e) => {
+                    log::error!("Error reading fragment: {}", e);
+                    state.remove_fragments(&prefix);
+                    return (false, None);
+                }
+            }
+        }
+
+        // limit the buffered data per sender
+        let sender = sender_id.to_bytes();
+        let buffered = state.buffered.get(&sender).cloned().unwrap_or(0);
+        if buffered + fragment.data.len() > MAX_SENDER_BYTES {
+            log::warn!("fragment buffer of {} is full", sender_id.to_base58());
+            return (false, None);
+        }
+
+        // save fragment
+        let data_length = fragment.data.len();
+        let entry = FragmentEntry {
+            count: fragment.count,
+            received_at: Timestamp::get_timestamp(),
+            signature: signature.clone(),
+            data: fragment.data,
+        };
+        let mut key = prefix.clone();
+        key.extend_from_slice(&fragment.index.to_be_bytes());
+        match state
+            .fragments
+            .insert(key, bincode::serialize(&entry).unwrap())
+        {
+            Ok(previous) => {
+                // a duplicate fragment replaces the previous one
+                let previous_length = previous
+                    .and_then(|bytes| bincode::deserialize::<FragmentEntry>(&bytes).ok())
+                    .map(|previous| previous.data.len())
+                    .unwrap_or(0);
+                let counter = state.buffered.entry(sender).or_insert(0);
+                *counter = (*counter + data_length).saturating_sub(previous_length);
+            }
+            Err(e) => {
+                log::error!("Error saving fragment: {}", e);
+                return (false, None);
+            }
+        }
+
+        // the first fragment is confirmed after processing the payload
+        let confirm = fragment.index != 0;
+
+        // check if all fragments have arrived
+        if state.fragments.scan_prefix(&prefix).count() < fragment.count as usize {
+            return (confirm, None);
+        }
+
+        // reassemble payload
+        let mut data: Vec<u8> = Vec::new();
+        let mut first_signature: Vec<u8> = Vec::new();
+        let results: Vec<_> = state.fragments.scan_prefix(&prefix).collect();
+        for (index, result) in results.into_iter().enumerate() {
+            let entry = match result
+                .map_err(|e| e.to_string())
+                .and_then(|(_key, entry_bytes)| {
+                    bincode::deserialize::<FragmentEntry>(&entry_bytes).map_err(|e| e.to_string())
+                }) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    // drop the reassembly
+                    log::error!("Error reading fragment: {}", e);
+                    state.remove_fragments(&prefix);
+                    return (false, None);
+                }
+            };
+            if index == 0 {
+                first_signature = entry.signature;
+            }
+            data.extend(entry.data);
+        }
+
+        // remove fragments
+        state.remove_fragments(&prefix);
+        if let Err(e) = state
+            .completed
+            .insert(prefix, Timestamp::get_timestamp().to_be_bytes().to_vec())
+        {
+            log::error!("Error saving reassembled fragment: {}", e);
+        }
+
+        match proto::EnvelopPayload::decode(&data[..]) {
+            Ok(payload) => (confirm, Some((payload, first_signature))),
+            Err(e) => {
+                log::error!("reassembled payload decoding error: {}", e);
+                (confirm, None)
+            }
+        }
+    }
+
+    /// Remove outdated fragments
+    ///
+    /// Incomplete payloads and the records of reassembled payloads
+    /// are removed after the maximal retransmission age.
+    pub fn cleanup() {
+        let now = Timestamp::get_timestamp();
+        let mut state = FRAGMENTS.get().write().unwrap();
+        if now - state.last_cleanup < CLEANUP_INTERVAL {
+            return;
+        }
+        state.last_cleanup = now;
+
+        let max_age = Configuration::get().messaging.retry_max_age * 1000;
+
+        let mut outdated: Vec<Vec<u8>> = Vec::new();
+        for result in state.fragments.iter() {
+            if let Ok((key, entry_bytes)) = result {
+                match bincode::deserialize::<FragmentEntry>(&entry_bytes) {
+                    Ok(entry) if now.saturating_sub(entry.received_at) <= max_age => {}
+                    _ => outdated.push(key[..key.len() - 4].to_vec()),
+                }
+            }
+        }
+        outdated.dedup();
+        for prefix in outdated {
+            state.remove_fragments(&prefix);
+        }
+
+        for result in state.completed.iter() {
+            if let Ok((key, completed_bytes)) = result {
+                if let Ok(arr) = completed_bytes.as_ref().try_into() {
+                    if now.saturating_sub(u64::from_be_bytes(arr)) > max_age {
+                        let _ = state.completed.remove(key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// remove all fragments of a payload
+    ///
+    /// The removed data is subtracted from the buffered
+    /// data of the sender.
+    fn remove_fragments(&mut self, prefix: &[u8]) {
+        let mut removed = 0;
+        let keys: Vec<sled::IVec> = self
+            .fragments
+            .scan_prefix(prefix)
+            .keys()
+            .filter_map(|key| key.ok())
+            .collect();
+        for key in keys {
+            match self.fragments.remove(&key) {
+                Ok(Some(entry_bytes)) => {
+                    if let Ok(entry) = bincode::deserialize::<FragmentEntry>(&entry_bytes) {
+                        removed += entry.data.len();
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => log::error!("Error removing fragment: {}", e),
+            }
+        }
+        if let Err(e) = self.fragments.flush() {
+            log::error!("Error fragments flush: {}", e);
+        }
+
+        let sender = prefix[..prefix.len() - 8].to_vec();
+        if let Some(buffered) = self.buffered.get_mut(&sender) {
+            *buffered = buffered.saturating_sub(removed);
+            if *buffered == 0 {
+                self.buffered.remove(&sender);
+            }
+        }
+    }
+
+    /// get the sender id out of a fragment key
+    fn sender_of(key: &[u8]) -> Vec<u8> {
+        key[..key.len().saturating_sub(KEY_SUFFIX_LENGTH)].to_vec()
+    }
+
+    /// create the data base key prefix of a fragmented payload
+    fn prefix(sender_id: &PeerId, fragment_id: u64) -> Vec<u8> {
+        let mut prefix = sender_id.to_bytes();
+        prefix.extend_from_slice(&fragment_id.to_be_bytes());
+        prefix
+    }
+}
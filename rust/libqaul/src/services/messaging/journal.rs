@@ -0,0 +1,208 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Send Queue Journal
+//!
+//! All messages scheduled for sending, including the messages
+//! forwarded for other users, are journaled to the node data base.
+//! A message is removed from the journal, once it was handed over
+//! to the next hop or to the DTN service, or was dropped because
+//! no route to its receiver exists.
+//! Forwarded messages stay journaled while they wait for a route.
+//!
+//! On startup, the journaled messages are scheduled again.
+//! Messages older than the configured maximal message age are dropped.
+//! Messages still waiting for a confirmation are skipped, as they
+//! are scheduled again by the retransmission.
+
+use libp2p::PeerId;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use sled;
+
+use super::queue::MessagePriority;
+use super::{proto, ScheduledMessage};
+use crate::storage::configuration::Configuration;
+use crate::utilities::timestamp::Timestamp;
+
+/// Journaled Scheduled Message
+#[derive(Serialize, Deserialize, Clone)]
+struct JournalEntry {
+    /// receiver id
+    receiver: Vec<u8>,
+    /// encoded container
+    container: Vec<u8>,
+    /// priority class
+    priority: MessagePriority,
+    is_common: bool,
+    is_forward: bool,
+    scheduled_dtn: bool,
+    is_dtn: bool,
+}
+
+/// Send Queue Journal
+pub struct MessagingJournal {
+    /// journal key => JournalEntry
+    ///
+    /// The journal key is an increasing index,
+    /// encoded as big endian u64.
+    ///
+    /// value: bincode of `JournalEntry`
+    tree: sled::Tree,
+    /// last journal key
+    index: u64,
+}
+
+impl MessagingJournal {
+    /// Open the journal and load the journaled messages
+    ///
+    /// Returns the journal and the messages to schedule again,
+    /// in the order they were scheduled.
+    pub fn open(
+        db: &sled::Db,
+        unconfirmed: &sled::Tree,
+    ) -> (Self, Vec<(MessagePriority, ScheduledMessage)>) {
+        let tree: sled::Tree = db.open_tree("scheduled").unwrap();
+        let max_age = Configuration::get().messaging.max_age * 1000;
+        let now = Timestamp::get_timestamp();
+
+        let mut index = 0;
+        let mut messages: Vec<(MessagePriority, ScheduledMessage)> = Vec::new();
+        let mut expired = 0;
+        let mut retransmitted = 0;
+
+        for entry in tree.iter() {
+            let (key, entry_bytes) = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    log::error!("scheduled messages journal error: {}", e);
+                    continue;
+                }
+            };
+            let journal_key = match key.as_ref().try_into() {
+                Ok(arr) => u64::from_be_bytes(arr),
+                Err(_) => continue,
+            };
+            index = journal_key;
+
+            let message = match Self::decode(journal_key, &entry_bytes) {
+                Some(message) => message,
+                None => {
+                    let _ = tree.remove(key);
+                    continue;
+                }
+            };
+
+            // check message age
+            let created_at = match &message.1.container.envelope {
+                Some(envelope) => envelope.created_at,
+                None => 0,
+            };
+            if created_at > 0 && now > created_at && now - created_at > max_age {
+                let _ = tree.remove(key);
+                expired += 1;
+                continue;
+            }
+
+            // unconfirmed messages are scheduled by the retransmission
+            if unconfirmed
+                .contains_key(&message.1.container.signature)
+                .unwrap_or(false)
+            {
+                let _ = tree.remove(key);
+                retransmitted += 1;
+                continue;
+            }
+
+            messages.push(message);
+        }
+
+        if expired > 0 {
+            log::debug!("{} expired scheduled messages dropped", expired);
+        }
+        if retransmitted > 0 {
+            log::debug!(
+                "{} scheduled messages left to the retransmission",
+                retransmitted
+            );
+        }
+        if expired > 0 || retransmitted > 0 {
+            if let Err(e) = tree.flush() {
+                log::error!("Error scheduled messages flush: {}", e);
+            }
+        }
+        if !messages.is_empty() {
+            log::debug!("{} journaled messages scheduled again", messages.len());
+        }
+
+        (MessagingJournal { tree, index }, messages)
+    }
+
+    /// Add a scheduled message to the journal
+    ///
+    /// The journal key is saved in the message.
+    /// The tree is not flushed explicitly, sled flushes
+    /// its data regularly to disk.
+    pub fn insert(&mut self, priority: MessagePriority, message: &mut ScheduledMessage) {
+        self.index += 1;
+        message.journal_key = self.index;
+
+        let entry = JournalEntry {
+            receiver: message.receiver.to_bytes(),
+            container: message.container.encode_to_vec(),
+            priority,
+            is_common: message.is_common,
+            is_forward: message.is_forward,
+            scheduled_dtn: message.scheduled_dtn,
+            is_dtn: message.is_dtn,
+        };
+        if let Err(e) = self.tree.insert(
+            self.index.to_be_bytes().to_vec(),
+            bincode::serialize(&entry).unwrap(),
+        ) {
+            log::error!("Error journaling scheduled message: {}", e);
+        }
+    }
+
+    /// Get a journaled message by its journal key
+    pub fn get(&self, journal_key: u64) -> Option<ScheduledMessage> {
+        match self.tree.get(journal_key.to_be_bytes().to_vec()) {
+            Ok(Some(entry_bytes)) => {
+                Self::decode(journal_key, &entry_bytes).map(|(_, message)| message)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("Error reading scheduled message from journal: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Remove a message from the journal by its journal key
+    pub fn remove(&self, journal_key: u64) {
+        if let Err(e) = self.tree.remove(journal_key.to_be_bytes().to_vec()) {
+            log::error!("Error removing scheduled message from journal: {}", e);
+        }
+    }
+
+    /// decode a journal entry
+    fn decode(journal_key: u64, entry_bytes: &[u8]) -> Option<(MessagePriority, ScheduledMessage)> {
+        let entry: JournalEntry = bincode::deserialize(entry_bytes).ok()?;
+        let receiver = PeerId::from_bytes(&entry.receiver).ok()?;
+        let container = proto::Container::decode(&entry.container[..]).ok()?;
+
+        Some((
+            entry.priority,
+            ScheduledMessage {
+                receiver,
+                container,
+                is_common: entry.is_common,
+                is_forward: entry.is_forward,
+                scheduled_dtn: entry.scheduled_dtn,
+                is_dtn: entry.is_dtn,
+                priority: entry.priority,
+                journal_key,
+            },
+        ))
+    }
+}
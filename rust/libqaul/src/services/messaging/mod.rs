@@ -11,24 +11,34 @@ use prost::Message;
 use serde::{Deserialize, Serialize};
 use sled;
 use state::InitCell;
-use std::collections::VecDeque;
 use std::sync::RwLock;
 
 #[cfg(emulate)]
 mod network_emul;
 
+pub mod compression;
+pub mod failed;
+pub mod fragment;
+pub mod journal;
 pub mod process;
+pub mod queue;
 pub mod retransmit;
 
+use super::chat::rpc_proto::MessageStatus;
 use super::chat::{ChatFile, ChatStorage};
-use super::crypto::Crypto;
+use super::crypto::{Crypto, CryptoPendingMessage};
 use crate::connections::ConnectionModule;
 use crate::node::user_accounts::{UserAccount, UserAccounts};
 use crate::router::table::RoutingTable;
+use crate::storage::configuration::Configuration;
 use crate::storage::database::DataBase;
 use crate::utilities::timestamp::Timestamp;
+use compression::MessagingCompression;
+use fragment::MessagingFragment;
+use journal::MessagingJournal;
 use process::MessagingProcess;
 use qaul_messaging::QaulMessagingReceived;
+use queue::{MessagePriority, QueueDepth, ScheduledQueue};
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -39,6 +49,9 @@ pub mod proto {
 /// mutable state of messages, scheduled for sending
 pub static MESSAGING: InitCell<RwLock<Messaging>> = InitCell::new();
 
+/// maximal number of messages sent per scheduler tick
+pub const SEND_BATCH_SIZE: usize = 10;
+
 /// Messaging Scheduling Structure
 pub struct ScheduledMessage {
     receiver: PeerId,
@@ -47,6 +60,19 @@ pub struct ScheduledMessage {
     is_forward: bool,
     scheduled_dtn: bool,
     is_dtn: bool,
+    /// priority class of the message
+    priority: MessagePriority,
+    /// key of the message in the send queue journal
+    journal_key: u64,
+}
+
+/// Outcome of packing a message for sending
+pub enum SendOutcome {
+    /// the message was scheduled for sending with this signature
+    Sent(Vec<u8>),
+    /// the message was queued until the crypto handshake
+    /// with the receiver is completed
+    Queued,
 }
 
 /// mutable state of messages, scheduled for sending
@@ -97,6 +123,11 @@ pub enum MessagingServiceType {
     ChatFile,
     /// RTC Message
     Rtc,
+    /// Fragment of a Message
+    ///
+    /// All fragments but the first one of a fragmented message
+    /// have this type.
+    Fragment,
 }
 
 /// Unconfirmed Messages Structure
@@ -105,43 +136,101 @@ pub struct UnConfirmedMessages {
     ///
     /// value: bincode of `UnConfirmedMessage`
     pub unconfirmed: sled::Tree,
+    /// signature => unencrypted message data
+    ///
+    /// The data of chat messages is kept until their confirmation,
+    /// to be able to pack them again, if their delivery failed.
+    pub data: sled::Tree,
 }
 
 /// Qaul Messaging Structure
 pub struct Messaging {
-    /// ring buffer of messages scheduled for sending
-    pub to_send: VecDeque<ScheduledMessage>,
+    /// queues of messages scheduled for sending,
+    /// one queue per priority class
+    pub to_send: Vec<ScheduledQueue>,
+    /// statistics of the forwarded messages
+    pub stats: MessagingStats,
+    /// journal of the scheduled messages
+    pub journal: MessagingJournal,
+}
+
+/// Statistics of the Messages Forwarded by this Node
+#[derive(Default, Clone)]
+pub struct MessagingStats {
+    /// number of messages forwarded
+    pub forwarded: u64,
+    /// number of messages dropped because their hop limit was exhausted
+    pub dropped_hop_limit: u64,
+    /// number of messages dropped because they exceeded the maximal age
+    pub dropped_expired: u64,
 }
 
 /// Qaul Failed Message Structure
 #[derive(Serialize, Deserialize, Clone)]
 pub struct FailedMessage {
+    /// receiver id
     pub user_id: Vec<u8>,
+    /// group id, empty if unknown
     pub group_id: Vec<u8>,
+    /// creation time of the message
     pub created_at: u64,
+    /// time of the last sending attempt
     pub last_try: u64,
+    /// number of sending attempts
     pub try_count: u32,
+    /// reason of the failure
     pub message: String,
+    /// message id
+    pub message_id: Vec<u8>,
+    /// message type
+    pub message_type: MessagingServiceType,
+    /// unencrypted message data, empty if the message can't be resent
+    pub data: Vec<u8>,
 }
 
 impl Messaging {
-    /// Initialize messaging and create the ring buffer.
+    /// Initialize messaging and create the send queues.
+    ///
+    /// The messages of the send queue journal are scheduled again.
     pub fn init() {
         #[cfg(emulate)]
         /// init emulator
         network_emul::NetworkEmulator::init();
 
-        let messaging = Messaging {
-            to_send: VecDeque::new(),
-        };
-        MESSAGING.set(RwLock::new(messaging));
-
         let db = DataBase::get_node_db();
 
         // open trees
         let unconfirmed: sled::Tree = db.open_tree("unconfirmed").unwrap();
-        let unconfirmed_messages = UnConfirmedMessages { unconfirmed };
+        let unconfirmed_data: sled::Tree = db.open_tree("unconfirmed_data").unwrap();
+
+        let (journal, journaled) = MessagingJournal::open(&db, &unconfirmed);
+        let mut messaging = Messaging {
+            to_send: MessagePriority::ALL
+                .iter()
+                .map(|_| ScheduledQueue::default())
+                .collect(),
+            stats: MessagingStats::default(),
+            journal,
+        };
+        for (priority, message) in journaled {
+            messaging.to_send[priority as usize].push(message);
+        }
+        MESSAGING.set(RwLock::new(messaging));
+
+        let unconfirmed_messages = UnConfirmedMessages {
+            unconfirmed,
+            data: unconfirmed_data,
+        };
         UNCONFIRMED.set(RwLock::new(unconfirmed_messages));
+
+        // initialize failed messages
+        failed::MessagingFailed::init();
+
+        // initialize fragment reassembly
+        MessagingFragment::init();
+
+        // initialize compression
+        MessagingCompression::init();
     }
 
     /// Save a message to the data base to wait for confirmation
@@ -179,6 +268,37 @@ impl Messaging {
         }
     }
 
+    /// Keep the unencrypted data of a chat message until its confirmation
+    ///
+    /// The data of other message types is not kept.
+    pub fn save_unconfirmed_data(
+        message_type: &MessagingServiceType,
+        signature: &Vec<u8>,
+        data: &Vec<u8>,
+    ) {
+        if let MessagingServiceType::Chat = message_type {
+            let unconfirmed = UNCONFIRMED.get().read().unwrap();
+            if let Err(e) = unconfirmed.data.insert(signature.clone(), data.clone()) {
+                log::error!("Error saving unconfirmed data: {}", e);
+            }
+            if let Err(e) = unconfirmed.data.flush() {
+                log::error!("Error unconfirmed data flush: {}", e);
+            }
+        }
+    }
+
+    /// Remove and return the unencrypted data of an unconfirmed message
+    pub fn take_unconfirmed_data(signature: &Vec<u8>) -> Option<Vec<u8>> {
+        let unconfirmed = UNCONFIRMED.get().read().unwrap();
+        match unconfirmed.data.remove(signature) {
+            Ok(data) => data.map(|data| data.to_vec()),
+            Err(e) => {
+                log::error!("Error removing unconfirmed data: {}", e);
+                None
+            }
+        }
+    }
+
     /// Process confirmation message
     ///
     /// Removes the message from the unconfirmed table and notifies
@@ -202,6 +322,9 @@ impl Messaging {
                 if let Err(e) = unconfirmed.unconfirmed.flush() {
                     log::error!("Error unconfirmed table flush: {}", e);
                 }
+                if let Err(e) = unconfirmed.data.remove(signature) {
+                    log::error!("Error removing unconfirmed data: {}", e);
+                }
 
                 match v {
                     Some(unconfirmed_bytes) => {
@@ -261,6 +384,9 @@ impl Messaging {
                                 log::trace!("Confirmation: Rtc");
                                 // TODO CONFIRM RTC MESSAGE
                             }
+                            MessagingServiceType::Fragment => {
+                                log::trace!("Confirmation: Fragment");
+                            }
                         }
                     }
                     _ => {}
@@ -328,22 +454,43 @@ impl Messaging {
 
     /// pack, sign and schedule a message for sending
     ///
-    /// The function returns the message signature on success,
+    /// The function returns the message signature, or that the
+    /// message was queued until the crypto handshake with the
+    /// receiver is completed, on success,
     /// otherwise an error message string.
-
     pub fn pack_and_send_message(
         user_account: &UserAccount,
         receiver: &PeerId,
         data: Vec<u8>,
-        _message_type: MessagingServiceType,
+        message_type: MessagingServiceType,
         message_id: &Vec<u8>,
         message_needs_confirmation: bool,
-    ) -> Result<Vec<u8>, String> {
+    ) -> Result<SendOutcome, String> {
         log::trace!("pack_and_send_message to {}", receiver.to_base58());
 
+        // queue the message, if the crypto handshake with
+        // the receiver has not been completed yet
+        if Crypto::handshake_pending(user_account, receiver.clone()) {
+            return Self::queue_pending_message(
+                user_account,
+                receiver,
+                CryptoPendingMessage {
+                    message_type,
+                    message_id: message_id.to_owned(),
+                    data,
+                    needs_confirmation: message_needs_confirmation,
+                    created_at: Timestamp::get_timestamp(),
+                },
+            );
+        }
+
+        // compress data, if supported by the receiver
+        let compressed = MessagingCompression::compress(user_account, receiver, data.clone());
+
         // encrypt data
         let encrypted_message: proto::Encrypted;
-        let encryption_result = Crypto::encrypt(data, user_account.to_owned(), receiver.clone());
+        let encryption_result =
+            Crypto::encrypt(compressed, user_account.to_owned(), receiver.clone());
 
         match encryption_result {
             Some(encrypted) => {
@@ -352,13 +499,96 @@ impl Messaging {
             None => return Err("Encryption error occurred".to_string()),
         }
 
-        return Self::pack_and_send_encrypted_data(
+        let signature = Self::pack_and_send_encrypted_data(
             user_account,
             receiver,
             encrypted_message,
+            message_type.clone(),
             message_id,
             message_needs_confirmation,
+        )?;
+
+        // keep the data to be able to resend the message
+        if message_needs_confirmation {
+            Self::save_unconfirmed_data(&message_type, &signature, &data);
+        }
+
+        Ok(SendOutcome::Sent(signature))
+    }
+
+    /// queue a message until the crypto handshake with
+    /// the receiver is completed
+    ///
+    /// If the queue of the receiver is full, the message
+    /// is marked as failed.
+    fn queue_pending_message(
+        user_account: &UserAccount,
+        receiver: &PeerId,
+        pending: CryptoPendingMessage,
+    ) -> Result<SendOutcome, String> {
+        log::trace!(
+            "queue message to {} until key exchange is completed",
+            receiver.to_base58()
         );
+
+        if let MessagingServiceType::Chat = pending.message_type {
+            ChatStorage::update_status_from(
+                &user_account.id,
+                &pending.message_id,
+                MessageStatus::Sending,
+                MessageStatus::WaitingForKeyExchange,
+            );
+        }
+
+        if !Crypto::save_pending_message(user_account, receiver.clone(), pending.clone()) {
+            let reason = "too many messages waiting for key exchange";
+            failed::MessagingFailed::on_pending_failed(user_account.id, receiver, pending, reason);
+            return Err(reason.to_string());
+        }
+
+        Ok(SendOutcome::Queued)
+    }
+
+    /// send all messages that waited for the crypto handshake
+    ///
+    /// This function is called once the crypto session with
+    /// the receiver is established, or the handshake expired.
+    /// If a new handshake is pending, the messages are
+    /// queued again and keep their queuing time.
+    pub fn send_pending_messages(user_account: &UserAccount, receiver: &PeerId) {
+        for pending in Crypto::take_pending_messages(user_account, receiver.clone()) {
+            if Crypto::handshake_pending(user_account, receiver.clone()) {
+                if let Err(e) = Self::queue_pending_message(user_account, receiver, pending) {
+                    log::error!("queuing pending message failed: {}", e);
+                }
+                continue;
+            }
+
+            log::trace!(
+                "send pending message to {} after key exchange",
+                receiver.to_base58()
+            );
+
+            if let MessagingServiceType::Chat = pending.message_type {
+                ChatStorage::update_status_from(
+                    &user_account.id,
+                    &pending.message_id,
+                    MessageStatus::WaitingForKeyExchange,
+                    MessageStatus::Sending,
+                );
+            }
+
+            if let Err(e) = Self::pack_and_send_message(
+                user_account,
+                receiver,
+                pending.data,
+                pending.message_type,
+                &pending.message_id,
+                pending.needs_confirmation,
+            ) {
+                log::error!("sending pending message failed: {}", e);
+            }
+        }
     }
 
     /// pack, sign and schedule encrypted message data
@@ -369,6 +599,7 @@ impl Messaging {
         user_account: &UserAccount,
         receiver: &PeerId,
         encrypted_message: proto::Encrypted,
+        message_type: MessagingServiceType,
         message_id: &Vec<u8>,
         message_needs_confirmation: bool,
     ) -> Result<Vec<u8>, String> {
@@ -385,11 +616,133 @@ impl Messaging {
             )),
         };
 
+        Self::pack_and_send_payload(
+            user_account,
+            receiver,
+            envelop_payload,
+            message_type,
+            message_id,
+            message_needs_confirmation,
+        )
+    }
+
+    /// pack, sign and schedule a group message encrypted
+    /// with the group sender key
+    ///
+    /// The function returns the message signature on success,
+    /// otherwise an error message string.
+    pub fn pack_and_send_group_encrypted_data(
+        user_account: &UserAccount,
+        receiver: &PeerId,
+        group_encrypted: proto::GroupEncrypted,
+        message_type: MessagingServiceType,
+        message_id: &Vec<u8>,
+        message_needs_confirmation: bool,
+    ) -> Result<Vec<u8>, String> {
+        log::trace!(
+            "pack_and_send_group_encrypted_data\n\tsender_id: {},\n\treceiver_id: {}",
+            user_account.id.to_base58(),
+            receiver.to_base58()
+        );
+
+        let envelop_payload = proto::EnvelopPayload {
+            payload: Some(proto::envelop_payload::Payload::GroupEncrypted(
+                group_encrypted,
+            )),
+        };
+
+        Self::pack_and_send_payload(
+            user_account,
+            receiver,
+            envelop_payload,
+            message_type,
+            message_id,
+            message_needs_confirmation,
+        )
+    }
+
+    /// pack, sign and schedule an envelope payload
+    ///
+    /// Payloads exceeding the maximal message size of the
+    /// next hop are split into fragments.
+    /// The signature of the first fragment is returned.
+    fn pack_and_send_payload(
+        user_account: &UserAccount,
+        receiver: &PeerId,
+        envelop_payload: proto::EnvelopPayload,
+        message_type: MessagingServiceType,
+        message_id: &Vec<u8>,
+        message_needs_confirmation: bool,
+    ) -> Result<Vec<u8>, String> {
+        let payload = envelop_payload.encode_to_vec();
+        let priority = MessagePriority::from_service_type(&message_type);
+
+        let fragment_size = MessagingFragment::fragment_size(receiver);
+        if payload.len() <= fragment_size {
+            return Self::pack_and_send_envelope(
+                user_account,
+                receiver,
+                payload,
+                message_type,
+                priority,
+                message_id,
+                message_needs_confirmation,
+            );
+        }
+
+        log::trace!(
+            "fragment payload of {} bytes to {}",
+            payload.len(),
+            receiver.to_base58()
+        );
+
+        let mut signature = Vec::new();
+        for (index, fragment) in MessagingFragment::split(&payload, fragment_size)
+            .into_iter()
+            .enumerate()
+        {
+            if index == 0 {
+                signature = Self::pack_and_send_envelope(
+                    user_account,
+                    receiver,
+                    fragment.encode_to_vec(),
+                    message_type.clone(),
+                    priority,
+                    message_id,
+                    message_needs_confirmation,
+                )?;
+            } else {
+                Self::pack_and_send_envelope(
+                    user_account,
+                    receiver,
+                    fragment.encode_to_vec(),
+                    MessagingServiceType::Fragment,
+                    priority,
+                    message_id,
+                    true,
+                )?;
+            }
+        }
+
+        Ok(signature)
+    }
+
+    /// pack, sign and schedule an encoded envelope payload
+    fn pack_and_send_envelope(
+        user_account: &UserAccount,
+        receiver: &PeerId,
+        payload: Vec<u8>,
+        message_type: MessagingServiceType,
+        priority: MessagePriority,
+        message_id: &Vec<u8>,
+        message_needs_confirmation: bool,
+    ) -> Result<Vec<u8>, String> {
         // create envelope
         let envelope = proto::Envelope {
             sender_id: user_account.id.to_bytes(),
             receiver_id: receiver.to_bytes(),
-            payload: envelop_payload.encode_to_vec(),
+            payload,
+            created_at: Timestamp::get_timestamp(),
         };
 
         // encode envelope
@@ -404,12 +757,13 @@ impl Messaging {
             let container = proto::Container {
                 signature: signature.clone(),
                 envelope: Some(envelope),
+                hop_limit: Configuration::get().messaging.hop_limit,
             };
 
             // in common message case, save into unconfirmed table
             if message_needs_confirmation {
                 Self::save_unconfirmed_message(
-                    MessagingServiceType::Chat,
+                    message_type,
                     message_id,
                     receiver,
                     &container,
@@ -421,6 +775,7 @@ impl Messaging {
             Self::schedule_message(
                 receiver.clone(),
                 container,
+                priority,
                 message_needs_confirmation,
                 false,
                 false,
@@ -450,6 +805,7 @@ impl Messaging {
             sender_id: user_account.id.to_bytes(),
             receiver_id: storage_node_id.to_bytes(),
             payload: dtn_payload.encode_to_vec(),
+            created_at: Timestamp::get_timestamp(),
         };
 
         if let Ok(signature_dtn) = user_account.keys.sign(&envelope_dtn.encode_to_vec()) {
@@ -457,11 +813,12 @@ impl Messaging {
             let container_dtn = proto::Container {
                 signature: signature_dtn.clone(),
                 envelope: Some(envelope_dtn),
+                hop_limit: Configuration::get().messaging.hop_limit,
             };
 
             // in common message case, save into unconfirmed table
             Self::save_unconfirmed_message(
-                MessagingServiceType::Chat,
+                MessagingServiceType::DtnOrigin,
                 &Vec::new(),
                 &storage_node_id,
                 &container_dtn,
@@ -472,6 +829,7 @@ impl Messaging {
             Self::schedule_message(
                 storage_node_id.clone(),
                 container_dtn,
+                MessagePriority::from_service_type(&MessagingServiceType::DtnOrigin),
                 true,
                 false,
                 true,
@@ -488,12 +846,13 @@ impl Messaging {
     /// schedule a message
     ///
     /// schedule a message for sending.
-    /// This function adds the message to the ring buffer for sending.
-    /// This buffer is checked regularly by libqaul for sending.
+    /// This function adds the message to the queue of its priority class.
+    /// The queues are checked regularly by libqaul for sending.
     ///
     pub fn schedule_message(
         receiver: PeerId,
         container: proto::Container,
+        priority: MessagePriority,
         is_common: bool,
         is_forward: bool,
         scheduled_dtn: bool,
@@ -508,36 +867,122 @@ impl Messaging {
             return;
         }
 
-        let msg = ScheduledMessage {
+        let mut msg = ScheduledMessage {
             receiver,
             container,
             is_common,
             is_forward,
             scheduled_dtn,
             is_dtn,
+            priority,
+            journal_key: 0,
         };
 
-        // add it to sending queue
+        // add it to journal and sending queue
         let mut messaging = MESSAGING.get().write().unwrap();
-        messaging.to_send.push_back(msg);
+        messaging.journal.insert(priority, &mut msg);
+        messaging.to_send[priority as usize].push(msg);
     }
 
-    /// Check Scheduler
+    /// Get the number of messages scheduled per priority class
+    pub fn get_queue_depth() -> QueueDepth {
+        let messaging = MESSAGING.get().read().unwrap();
+        let depth = |priority: MessagePriority| messaging.to_send[priority as usize].len() as u32;
+
+        QueueDepth {
+            control: depth(MessagePriority::Control),
+            confirmation: depth(MessagePriority::Confirmation),
+            chat: depth(MessagePriority::Chat),
+            group: depth(MessagePriority::Group),
+            file: depth(MessagePriority::File),
+            forward: depth(MessagePriority::Forward),
+        }
+    }
+
+    /// take the next message with the highest priority
     ///
-    /// Check if there is a message scheduled for sending.
+    /// The message stays in the journal until it was handed over.
+    fn next_scheduled_message() -> Option<ScheduledMessage> {
+        let mut messaging = MESSAGING.get().write().unwrap();
+        messaging.to_send.iter_mut().find_map(|queue| queue.pop())
+    }
+
+    /// Remove a message from the send queue journal
     ///
-    pub fn check_scheduler() -> Option<(PeerId, ConnectionModule, Vec<u8>)> {
-        let message_item: Option<ScheduledMessage>;
+    /// This function is called, once the message returned by
+    /// `check_scheduler` was handed over to the next hop.
+    pub fn on_handed_over(journal_key: u64) {
+        let messaging = MESSAGING.get().read().unwrap();
+        messaging.journal.remove(journal_key);
+    }
 
-        // get scheduled messaging buffer
-        {
+    /// Schedule a message again, that couldn't be handed over
+    ///
+    /// The message is restored from the send queue journal
+    /// and keeps its journal entry.
+    pub fn on_not_handed_over(journal_key: u64) {
+        let mut messaging = MESSAGING.get().write().unwrap();
+        match messaging.journal.get(journal_key) {
+            Some(message) => messaging.to_send[message.priority as usize].push(message),
+            None => log::error!("scheduled message {} not in journal", journal_key),
+        }
+    }
+
+    /// add messages back to the send queue
+    ///
+    /// The messages keep their journal entries.
+    fn requeue(messages: Vec<ScheduledMessage>) {
+        if messages.is_empty() {
+            return;
+        }
+        let mut messaging = MESSAGING.get().write().unwrap();
+        for message in messages {
+            messaging.to_send[message.priority as usize].push(message);
+        }
+    }
+
+    /// Check if a forwarded message waits for a route
+    ///
+    /// Expired messages and messages of older nodes without
+    /// a creation time don't wait.
+    fn waits_for_route(message: &ScheduledMessage) -> bool {
+        let created_at = match &message.container.envelope {
+            Some(envelope) => envelope.created_at,
+            None => 0,
+        };
+        if created_at == 0 {
+            return false;
+        }
+
+        let now = Timestamp::get_timestamp();
+        if now > created_at && now - created_at > Configuration::get().messaging.max_age * 1000 {
             let mut messaging = MESSAGING.get().write().unwrap();
-            message_item = messaging.to_send.pop_front();
+            messaging.stats.dropped_expired += 1;
+            return false;
         }
 
-        if let Some(message) = message_item {
+        true
+    }
+
+    /// Check Scheduler
+    ///
+    /// Check if there is a message scheduled for sending.
+    /// Returns the next message with the highest priority,
+    /// for which a route exists, and its journal key.
+    /// Messages to unreachable users are handed over to the
+    /// DTN service.
+    /// Forwarded messages to unreachable users stay in the
+    /// send queue until a route exists or they expire.
+    ///
+    pub fn check_scheduler() -> Option<(PeerId, ConnectionModule, Vec<u8>, u64)> {
+        let mut waiting: Vec<ScheduledMessage> = Vec::new();
+        let mut next = None;
+
+        while let Some(message) = Self::next_scheduled_message() {
             // check for route
-            if let Some(route) = RoutingTable::get_route_to_user(message.receiver) {
+            if let Some(route) = RoutingTable::get_route_to_user(message.receiver)
+                .filter(|route| route.module != ConnectionModule::None)
+            {
                 // update unconfirmed table set scheduled flag.
                 Self::on_scheduled_message(&message.container.signature);
 
@@ -545,41 +990,52 @@ impl Messaging {
                 let data = message.container.encode_to_vec();
 
                 // return information
-                return Some((route.node, route.module, data));
-            } else {
-                // user is offline we schedule through DTN service
-                if !message.is_forward
-                    && !message.is_dtn
-                    && !message.scheduled_dtn
-                    && message.is_common
+                next = Some((route.node, route.module, data, message.journal_key));
+                break;
+            }
+
+            // forwarded messages wait for a route
+            if message.is_forward {
+                if Self::waits_for_route(&message) {
+                    waiting.push(message);
+                } else {
+                    Self::on_handed_over(message.journal_key);
+                }
+                continue;
+            }
+
+            // user is offline we schedule through DTN service
+            if !message.is_dtn && !message.scheduled_dtn && message.is_common {
+                // get storage node id
+                if let Ok(my_user_id) =
+                    PeerId::from_bytes(&message.container.envelope.as_ref().unwrap().sender_id)
                 {
-                    // get storage node id
-                    if let Ok(my_user_id) =
-                        PeerId::from_bytes(&message.container.envelope.as_ref().unwrap().sender_id)
-                    {
-                        if let Some(storage_node_id) =
-                            super::dtn::Dtn::get_storage_user(&my_user_id)
-                        {
-                            if let Some(user_account) = UserAccounts::get_by_id(my_user_id) {
-                                if let Err(_e) = Self::send_dtn_message(
-                                    &user_account,
-                                    &storage_node_id,
-                                    &message.container,
-                                ) {
-                                    log::error!("DTN scheduling error!");
-                                } else {
-                                    log::error!("DTN scheduled...");
-                                    // update unconfirmed table
-                                    Self::on_scheduled_as_dtn_message(&message.container.signature);
-                                }
+                    if let Some(storage_node_id) = super::dtn::Dtn::get_storage_user(&my_user_id) {
+                        if let Some(user_account) = UserAccounts::get_by_id(my_user_id) {
+                            if let Err(_e) = Self::send_dtn_message(
+                                &user_account,
+                                &storage_node_id,
+                                &message.container,
+                            ) {
+                                log::error!("DTN scheduling error!");
+                            } else {
+                                log::error!("DTN scheduled...");
+                                // update unconfirmed table
+                                Self::on_scheduled_as_dtn_message(&message.container.signature);
                             }
                         }
                     }
                 }
             }
+
+            // the message leaves the send queue,
+            // unconfirmed messages are scheduled again by the retransmission
+            Self::on_handed_over(message.journal_key);
         }
 
-        None
+        Self::requeue(waiting);
+
+        next
     }
 
     /// Send a confirmation message for a received message
@@ -587,7 +1043,7 @@ impl Messaging {
         user_id: &PeerId,
         receiver_id: &PeerId,
         signature: &Vec<u8>,
-    ) -> Result<Vec<u8>, String> {
+    ) -> Result<SendOutcome, String> {
         log::trace!(
             "send confirmation message to\n\tuser_id: {}\n\tfor signature: {}",
             user_id.to_string(),
@@ -629,6 +1085,55 @@ impl Messaging {
         }
     }
 
+    /// Get the statistics of the forwarded messages
+    pub fn get_stats() -> MessagingStats {
+        let messaging = MESSAGING.get().read().unwrap();
+        messaging.stats.clone()
+    }
+
+    /// Check whether a message shall be forwarded
+    ///
+    /// Messages older than the configured maximal age are dropped.
+    /// The hop limit of the container is decremented and the message
+    /// is dropped once it is exhausted.
+    /// Messages of older nodes contain neither a creation time nor
+    /// a hop limit, their hop limit is initialized with the
+    /// configured value.
+    fn check_forward(container: &mut proto::Container, envelope: &proto::Envelope) -> bool {
+        let options = Configuration::get().messaging.clone();
+        let mut messaging = MESSAGING.get().write().unwrap();
+
+        // check message age
+        if envelope.created_at > 0 {
+            let now = Timestamp::get_timestamp();
+            if now > envelope.created_at && now - envelope.created_at > options.max_age * 1000 {
+                log::debug!(
+                    "drop expired message {}",
+                    bs58::encode(&container.signature).into_string()
+                );
+                messaging.stats.dropped_expired += 1;
+                return false;
+            }
+        }
+
+        // check hop limit
+        if container.hop_limit == 0 {
+            container.hop_limit = options.hop_limit;
+        }
+        container.hop_limit = container.hop_limit.saturating_sub(1);
+        if container.hop_limit == 0 {
+            log::debug!(
+                "drop message {} with exhausted hop limit",
+                bs58::encode(&container.signature).into_string()
+            );
+            messaging.stats.dropped_hop_limit += 1;
+            return false;
+        }
+
+        messaging.stats.forwarded += 1;
+        true
+    }
+
     /// received message from qaul_messaging behaviour
     pub fn received(received: QaulMessagingReceived) {
         // decode message container
@@ -647,14 +1152,20 @@ impl Messaging {
                                 ),
 
                                 // schedule it for further sending otherwise
-                                None => Self::schedule_message(
-                                    receiver_id,
-                                    container,
-                                    true,
-                                    true,
-                                    false,
-                                    false,
-                                ),
+                                None => {
+                                    let mut container = container;
+                                    if Self::check_forward(&mut container, &envelope) {
+                                        Self::schedule_message(
+                                            receiver_id,
+                                            container,
+                                            MessagePriority::Forward,
+                                            true,
+                                            true,
+                                            false,
+                                            false,
+                                        );
+                                    }
+                                }
                             }
                         }
                         Err(e) => log::error!(
@@ -9,6 +9,8 @@
 use libp2p::PeerId;
 use prost::Message;
 
+use super::compression::MessagingCompression;
+use super::fragment::MessagingFragment;
 use crate::node::user_accounts::UserAccount;
 use crate::router;
 use crate::services::chat::{self, rpc_proto, ChatFile, ChatStorage};
@@ -94,9 +96,36 @@ impl MessagingProcess {
                 );
             }
             Some(super::proto::messaging::Message::RtcStreamMessage(_rtc_stream)) => {}
+            Some(super::proto::messaging::Message::Compressed(compressed)) => {
+                let decompressed = match MessagingCompression::decompress(&compressed) {
+                    Some(decompressed) => decompressed,
+                    None => {
+                        log::error!(
+                            "Error decompressing Messaging Message {} from {}",
+                            bs58::encode(signature).into_string(),
+                            sender_id.to_base58()
+                        );
+                        return;
+                    }
+                };
+
+                // compressed messages must not contain compressed messages
+                if let Ok(super::proto::Messaging {
+                    message: Some(super::proto::messaging::Message::Compressed(_)),
+                }) = super::proto::Messaging::decode(&decompressed[..])
+                {
+                    log::error!("nested compressed message from {}", sender_id.to_base58());
+                    return;
+                }
+
+                Self::on_decrypted_message(sender_id, user_account, &decompressed, signature);
+            }
+            Some(super::proto::messaging::Message::Capabilities(capabilities)) => {
+                MessagingCompression::on_capabilities(&user_account, sender_id, capabilities);
+            }
             Some(super::proto::messaging::Message::GroupInviteMessage(group_invite)) => {
                 // TODO: pass on user_account
-                group::Group::net(sender_id, &user_account.id, &group_invite.content);
+                group::Group::net(sender_id, &user_account.id, &group_invite.content, false);
                 //group::Group::on_notify(sender_id, receiver_id, &group_notify.content);
 
                 // send confirm message
@@ -143,12 +172,35 @@ impl MessagingProcess {
                 match common.payload {
                     Some(super::proto::common_message::Payload::ChatMessage(ref chat_message)) => {
                         // create ChatContentMessage
-                        let content_message = rpc_proto::ChatContentMessage {
-                            message: Some(rpc_proto::chat_content_message::Message::ChatContent(
-                                rpc_proto::ChatContent {
-                                    text: chat_message.content.clone(),
-                                },
-                            )),
+                        let content_message = if chat_message.reply_to.is_empty() {
+                            rpc_proto::ChatContentMessage {
+                                message: Some(
+                                    rpc_proto::chat_content_message::Message::ChatContent(
+                                        rpc_proto::ChatContent {
+                                            text: chat_message.content.clone(),
+                                            edited_at: 0,
+                                        },
+                                    ),
+                                ),
+                            }
+                        } else {
+                            // the sender of the message replied to is
+                            // unknown, if we don't have the message
+                            let reply_sender_id =
+                                ChatStorage::get_message(&user_account.id, &chat_message.reply_to)
+                                    .map(|message| message.sender_id)
+                                    .unwrap_or_default();
+                            rpc_proto::ChatContentMessage {
+                                message: Some(rpc_proto::chat_content_message::Message::Reply(
+                                    rpc_proto::ChatReply {
+                                        message_id: chat_message.reply_to.clone(),
+                                        sender_id: reply_sender_id,
+                                        quote: chat_message.quote.clone(),
+                                        text: chat_message.content.clone(),
+                                        edited_at: 0,
+                                    },
+                                )),
+                            }
                         };
 
                         ChatStorage::save_message(
@@ -176,7 +228,12 @@ impl MessagingProcess {
                     )) => {
                         // TODO: pass on user_account
                         // process group message
-                        group::Group::net(&sender_id, &user_account.id, &group_message.content);
+                        group::Group::net(
+                            &sender_id,
+                            &user_account.id,
+                            &group_message.content,
+                            true,
+                        );
                     }
                     Some(super::proto::common_message::Payload::RtcMessage(ref rtc_message)) => {
                         // process message in RTC module
@@ -279,42 +336,99 @@ impl MessagingProcess {
         }
 
         match super::proto::EnvelopPayload::decode(&envelope.payload[..]) {
-            Ok(payload) => {
-                match payload.payload {
-                    Some(super::proto::envelop_payload::Payload::Encrypted(encrypted)) => {
-                        // decrypt data
-                        let decrypted: Vec<u8>;
-                        match Crypto::decrypt(
-                            encrypted,
-                            user_account.clone(),
-                            sender_id.clone(),
-                            &container.signature,
-                        ) {
-                            Some(decryption_result) => decrypted = decryption_result,
-                            None => {
-                                log::error!("decryption error");
-                                return;
-                            }
-                        }
+            Ok(payload) => Self::process_payload(
+                user_account,
+                sender_id,
+                receiver_id,
+                payload,
+                &container.signature,
+            ),
+            _ => {
+                log::error!("envelop payload decode error");
+                return;
+            }
+        }
+    }
 
-                        Self::on_decrypted_message(
-                            &sender_id,
-                            user_account,
-                            &decrypted,
-                            &container.signature,
-                        );
+    /// process a verified envelope payload
+    ///
+    /// The signature is the signature of the container
+    /// the payload was received in.
+    fn process_payload(
+        user_account: UserAccount,
+        sender_id: PeerId,
+        receiver_id: PeerId,
+        payload: super::proto::EnvelopPayload,
+        signature: &Vec<u8>,
+    ) {
+        match payload.payload {
+            Some(super::proto::envelop_payload::Payload::Encrypted(encrypted)) => {
+                // decrypt data
+                let decrypted: Vec<u8>;
+                match Crypto::decrypt(
+                    encrypted,
+                    user_account.clone(),
+                    sender_id.clone(),
+                    signature,
+                ) {
+                    Some(decryption_result) => decrypted = decryption_result,
+                    None => {
+                        log::error!("decryption error");
+                        return;
+                    }
+                }
+
+                Self::on_decrypted_message(&sender_id, user_account, &decrypted, signature);
+            }
+            Some(super::proto::envelop_payload::Payload::GroupEncrypted(group_encrypted)) => {
+                // decrypt data with the sender key of the group member
+                let decrypted: Vec<u8>;
+                match group::GroupSenderKey::decrypt(&user_account.id, &sender_id, &group_encrypted)
+                {
+                    Some(decryption_result) => decrypted = decryption_result,
+                    None => {
+                        log::error!("group decryption error");
+                        return;
                     }
-                    Some(super::proto::envelop_payload::Payload::Dtn(dtn)) => {
-                        dtn::Dtn::net(&receiver_id, &sender_id, &container.signature, &dtn);
+                }
+
+                Self::on_decrypted_message(&sender_id, user_account, &decrypted, signature);
+            }
+            Some(super::proto::envelop_payload::Payload::Dtn(dtn)) => {
+                dtn::Dtn::net(&receiver_id, &sender_id, signature, &dtn);
+            }
+            Some(super::proto::envelop_payload::Payload::Fragment(fragment)) => {
+                let (confirm, reassembled) =
+                    MessagingFragment::on_fragment(&sender_id, fragment, signature);
+
+                // confirm reception of the fragment
+                if confirm {
+                    if let Err(e) =
+                        super::Messaging::send_confirmation(&user_account.id, &sender_id, signature)
+                    {
+                        log::error!("send confirmation failed {}", e);
                     }
-                    _ => {
-                        log::error!("unknown envelop payload");
+                }
+
+                // process reassembled payload
+                if let Some((payload, first_signature)) = reassembled {
+                    if let Some(super::proto::envelop_payload::Payload::Fragment(_)) =
+                        payload.payload
+                    {
+                        log::error!("nested fragments are not supported");
                         return;
                     }
+                    Self::process_payload(
+                        user_account,
+                        sender_id,
+                        receiver_id,
+                        payload,
+                        &first_signature,
+                    );
                 }
             }
             _ => {
-                log::error!("envelop payload decode error");
+                log::error!("unknown envelop payload");
                 return;
             }
         }
@@ -0,0 +1,137 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Messaging Send Queue
+//!
+//! Messages scheduled for sending are queued by priority class.
+//! A class is only served, when all classes of higher priority
+//! are empty.
+//!
+//! Within a class, the messages are queued per receiver,
+//! and the receivers are served in turns.
+//! This way a big file transfer to one user doesn't block
+//! the messages to other users.
+
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::{MessagingServiceType, ScheduledMessage};
+
+/// Priority Classes of Scheduled Messages
+///
+/// The classes are ordered from the highest
+/// to the lowest priority.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum MessagePriority {
+    /// crypto handshakes
+    Control = 0,
+    /// confirmations and other messages without confirmation
+    Confirmation = 1,
+    /// chat text messages and RTC messages
+    Chat = 2,
+    /// group management messages
+    Group = 3,
+    /// file data
+    File = 4,
+    /// messages forwarded for other nodes and DTN storage
+    Forward = 5,
+}
+
+impl MessagePriority {
+    /// all priority classes, ordered by priority
+    pub const ALL: [MessagePriority; 6] = [
+        MessagePriority::Control,
+        MessagePriority::Confirmation,
+        MessagePriority::Chat,
+        MessagePriority::Group,
+        MessagePriority::File,
+        MessagePriority::Forward,
+    ];
+
+    /// get the priority class of a messaging service type
+    pub fn from_service_type(message_type: &MessagingServiceType) -> Self {
+        match message_type {
+            MessagingServiceType::Crypto => MessagePriority::Control,
+            MessagingServiceType::Unconfirmed => MessagePriority::Confirmation,
+            MessagingServiceType::Chat => MessagePriority::Chat,
+            MessagingServiceType::Rtc => MessagePriority::Chat,
+            MessagingServiceType::DtnOrigin => MessagePriority::Chat,
+            MessagingServiceType::Group => MessagePriority::Group,
+            MessagingServiceType::ChatFile => MessagePriority::File,
+            MessagingServiceType::Fragment => MessagePriority::File,
+            MessagingServiceType::DtnStored => MessagePriority::Forward,
+        }
+    }
+}
+
+/// Number of Scheduled Messages per Priority Class
+#[derive(Default, Clone)]
+pub struct QueueDepth {
+    /// crypto handshakes
+    pub control: u32,
+    /// confirmations
+    pub confirmation: u32,
+    /// chat messages
+    pub chat: u32,
+    /// group management messages
+    pub group: u32,
+    /// file data
+    pub file: u32,
+    /// forwarded messages
+    pub forward: u32,
+}
+
+/// Send Queue of a Priority Class
+#[derive(Default)]
+pub struct ScheduledQueue {
+    /// message queues per receiver,
+    /// in the order the receivers are served
+    receivers: VecDeque<(PeerId, VecDeque<ScheduledMessage>)>,
+    /// number of queued messages
+    len: usize,
+}
+
+impl ScheduledQueue {
+    /// add a message to the queue of its receiver
+    pub fn push(&mut self, message: ScheduledMessage) {
+        self.len += 1;
+        for (receiver, queue) in self.receivers.iter_mut() {
+            if *receiver == message.receiver {
+                queue.push_back(message);
+                return;
+            }
+        }
+
+        let receiver = message.receiver.clone();
+        let mut queue = VecDeque::new();
+        queue.push_back(message);
+        self.receivers.push_back((receiver, queue));
+    }
+
+    /// take the next message of the next receiver in turn
+    pub fn pop(&mut self) -> Option<ScheduledMessage> {
+        let (receiver, mut queue) = self.receivers.pop_front()?;
+        let message = queue.pop_front();
+
+        // the receiver is served again after all others
+        if !queue.is_empty() {
+            self.receivers.push_back((receiver, queue));
+        }
+
+        if message.is_some() {
+            self.len -= 1;
+        }
+        message
+    }
+
+    /// number of queued messages
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// check if there are no queued messages
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
@@ -4,12 +4,20 @@
 //! # Retransmit Qaul Messages
 //!
 //! Messages that couldn't be sent to a user are scheduled for retransmission.
+//!
+//! The interval between two retransmissions doubles with every attempt,
+//! up to the configured maximal interval.
+//! Once the maximal amount of attempts or the maximal age of a message
+//! is reached, its delivery is marked as failed.
 
 use libp2p::PeerId;
 use prost::Message;
 
+use super::failed::MessagingFailed;
+use super::queue::MessagePriority;
 use super::UnConfirmedMessage;
 use crate::router;
+use crate::storage::configuration::{Configuration, MessagingOptions};
 use crate::utilities::qaul_id::QaulId;
 use crate::utilities::timestamp::Timestamp;
 
@@ -19,6 +27,9 @@ pub struct MessagingRetransmit {}
 impl MessagingRetransmit {
     /// process retransmission
     pub fn process() {
+        // remove outdated fragments
+        super::fragment::MessagingFragment::cleanup();
+
         // get unconfirmed table
         let unconfirmed = super::UNCONFIRMED.get().write().unwrap();
         if unconfirmed.unconfirmed.len() == 0 {
@@ -29,6 +40,8 @@ impl MessagingRetransmit {
         // get online users from route table
         let online_users = router::table::RoutingTable::get_online_users();
 
+        let options = Configuration::get().messaging.clone();
+        let mut failed: Vec<(Vec<u8>, UnConfirmedMessage, &str)> = Vec::new();
         let mut updated = false;
         let cur_time = Timestamp::get_timestamp();
         for entry in unconfirmed.unconfirmed.iter() {
@@ -46,16 +59,41 @@ impl MessagingRetransmit {
                     continue;
                 }
 
+                // check message age
+                // DTN messages are kept by the DTN service
+                if !unconfirmed_message.is_dtn
+                    && Self::is_expired(&unconfirmed_message, &options, cur_time)
+                {
+                    failed.push((
+                        signature.to_vec(),
+                        unconfirmed_message,
+                        "maximal message age reached",
+                    ));
+                    continue;
+                }
+
                 let qaul_id = QaulId::bytes_to_q8id(unconfirmed_message.receiver_id.clone());
                 //1. check receiver is online
                 if let Some(_hc) = online_users.get(&qaul_id) {
                     let mut timeout: u64 = 0;
                     if unconfirmed_message.scheduled {
-                        timeout = 20 * 1000;
+                        timeout = Self::backoff(unconfirmed_message.retry, &options);
                     }
 
                     //check if expired timeout
                     if cur_time > (timeout + unconfirmed_message.last_sent) {
+                        // check amount of attempts
+                        if !unconfirmed_message.is_dtn
+                            && unconfirmed_message.retry >= options.retry_max_attempts
+                        {
+                            failed.push((
+                                signature.to_vec(),
+                                unconfirmed_message,
+                                "maximal amount of attempts reached",
+                            ));
+                            continue;
+                        }
+
                         // queue into messaging queue
                         if let Ok(container) =
                             super::proto::Container::decode(&unconfirmed_message.container[..])
@@ -70,6 +108,9 @@ impl MessagingRetransmit {
                             super::Messaging::schedule_message(
                                 receiver.clone(),
                                 container.clone(),
+                                MessagePriority::from_service_type(
+                                    &unconfirmed_message.message_type,
+                                ),
                                 true,
                                 false,
                                 unconfirmed_message.scheduled_dtn,
@@ -77,12 +118,7 @@ impl MessagingRetransmit {
                             );
 
                             // update entry
-                            let mut new_retry = unconfirmed_message.retry;
-                            if new_retry > 10 {
-                                new_retry = 1;
-                            }
-
-                            unconfirmed_message.retry = new_retry;
+                            unconfirmed_message.retry += 1;
                             unconfirmed_message.last_sent = cur_time;
                             let unconfirmed_message_todb =
                                 bincode::serialize(&unconfirmed_message).unwrap();
@@ -100,10 +136,62 @@ impl MessagingRetransmit {
             }
         }
 
+        // remove failed messages
+        for (signature, _, _) in &failed {
+            if let Err(_e) = unconfirmed.unconfirmed.remove(signature) {
+                log::error!("updating unconfirmed table error!");
+            } else {
+                updated = true;
+            }
+        }
+
         if updated {
             if let Err(_e) = unconfirmed.unconfirmed.flush() {
                 log::error!("updating unconfirmed table error!");
             }
         }
+
+        // release unconfirmed table before marking messages as failed
+        drop(unconfirmed);
+        for (signature, unconfirmed_message, reason) in failed {
+            MessagingFailed::on_failed(&signature, unconfirmed_message, reason);
+        }
+    }
+
+    /// Calculate the interval before the next retransmission
+    ///
+    /// The interval doubles with every attempt, up to the
+    /// configured maximal interval.
+    /// Returns the interval in milliseconds.
+    fn backoff(retry: u32, options: &MessagingOptions) -> u64 {
+        let exponent = retry.saturating_sub(1).min(32);
+        let interval = options
+            .retry_interval
+            .saturating_mul(1 << exponent)
+            .min(options.retry_interval_max);
+
+        interval * 1000
+    }
+
+    /// Check if a message exceeded the maximal age
+    ///
+    /// Messages of older nodes don't contain a creation time,
+    /// their age is not checked.
+    fn is_expired(
+        unconfirmed_message: &UnConfirmedMessage,
+        options: &MessagingOptions,
+        cur_time: u64,
+    ) -> bool {
+        let created_at = match super::proto::Container::decode(&unconfirmed_message.container[..]) {
+            Ok(container) => match container.envelope {
+                Some(envelope) => envelope.created_at,
+                None => 0,
+            },
+            Err(_) => 0,
+        };
+
+        created_at > 0
+            && cur_time > created_at
+            && cur_time - created_at > options.retry_max_age * 1000
     }
 }
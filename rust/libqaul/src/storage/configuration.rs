@@ -41,11 +41,80 @@ impl Default for Node {
     }
 }
 
+/// mDNS Discovery Options of the LAN Connection Module
+///
+/// All time units are seconds.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct LanMdns {
+    pub enabled: bool,
+    // time to live of the advertised mdns records
+    pub ttl: u64,
+    // interval to query the network for new peers
+    pub query_interval: u64,
+}
+
+impl Default for LanMdns {
+    fn default() -> Self {
+        LanMdns {
+            enabled: true,
+            ttl: 300,           //5min, unit: seconds
+            query_interval: 30, //30 seconds, unit: seconds
+        }
+    }
+}
+
+/// UDP Broadcast Beacon Discovery Options of the LAN Connection Module
+///
+/// The beacon is an alternative to mdns for networks
+/// that block multicast traffic.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct LanBeacon {
+    pub enabled: bool,
+    // UDP port the beacons are broadcasted to and received on
+    pub port: u16,
+    // interval between two beacons, unit: seconds
+    pub interval: u64,
+}
+
+impl Default for LanBeacon {
+    fn default() -> Self {
+        LanBeacon {
+            enabled: false,
+            port: 9228,
+            interval: 10, //10 seconds, unit: seconds
+        }
+    }
+}
+
+/// Static Peers of the LAN Connection Module
+///
+/// A list of multiaddresses of LAN nodes to connect to directly.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct LanStaticPeers {
+    pub enabled: bool,
+    pub peers: Vec<String>,
+}
+
+impl Default for LanStaticPeers {
+    fn default() -> Self {
+        LanStaticPeers {
+            enabled: true,
+            peers: vec![],
+        }
+    }
+}
+
 /// LAN Connection Module
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct Lan {
     pub active: bool,
     pub listen: Vec<String>,
+    #[serde(default)]
+    pub mdns: LanMdns,
+    #[serde(default)]
+    pub beacon: LanBeacon,
+    #[serde(default)]
+    pub static_peers: LanStaticPeers,
 }
 
 impl Default for Lan {
@@ -58,6 +127,9 @@ impl Default for Lan {
                 String::from("/ip6/::/udp/0/quic-v1"),
                 String::from("/ip6/::/tcp/0"),
             ],
+            mdns: LanMdns::default(),
+            beacon: LanBeacon::default(),
+            static_peers: LanStaticPeers::default(),
         }
     }
 }
@@ -69,6 +141,71 @@ pub struct InternetPeer {
     pub enabled: bool,
 }
 
+/// Reconnection Options of the Internet Overlay Connection Module
+///
+/// Unreachable peers are redialed with an exponentially
+/// growing delay, which is capped at `max_delay`.
+/// All delay units are seconds.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct InternetReconnect {
+    // delay before the first reconnection attempt
+    pub initial_delay: u64,
+    // maximal delay between two reconnection attempts
+    pub max_delay: u64,
+    // random jitter in percent of the delay
+    pub jitter: u8,
+}
+
+impl Default for InternetReconnect {
+    fn default() -> Self {
+        InternetReconnect {
+            initial_delay: 10, //10 seconds, unit: seconds
+            max_delay: 600,    //10min, unit: seconds
+            jitter: 20,        //+/- 20%, unit: percent
+        }
+    }
+}
+
+/// Websocket Options of the Internet Overlay Connection Module
+///
+/// The TLS certificate chain and private key are needed to listen
+/// on secure websocket (`/wss`) addresses.
+/// The paths point to PEM or DER encoded files.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct InternetWebsocket {
+    pub tls_certificate: String,
+    pub tls_private_key: String,
+}
+
+impl Default for InternetWebsocket {
+    fn default() -> Self {
+        InternetWebsocket {
+            tls_certificate: String::from(""),
+            tls_private_key: String::from(""),
+        }
+    }
+}
+
+/// SOCKS5 Proxy Options of the Internet Overlay Connection Module
+///
+/// If enabled, all outgoing connections are dialed via the SOCKS5
+/// proxy at `address`, e.g. a local Tor daemon.
+/// QUIC and listening for incoming connections are disabled in this mode.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct InternetProxy {
+    pub enabled: bool,
+    pub address: String,
+}
+
+impl Default for InternetProxy {
+    fn default() -> Self {
+        InternetProxy {
+            enabled: false,
+            address: String::from("127.0.0.1:9050"),
+        }
+    }
+}
+
 /// Internet Overlay Connection Module
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct Internet {
@@ -76,6 +213,12 @@ pub struct Internet {
     pub peers: Vec<InternetPeer>,
     pub do_listen: bool,
     pub listen: Vec<String>,
+    #[serde(default)]
+    pub websocket: InternetWebsocket,
+    #[serde(default)]
+    pub proxy: InternetProxy,
+    #[serde(default)]
+    pub reconnect: InternetReconnect,
 }
 
 impl Default for Internet {
@@ -116,6 +259,60 @@ impl Default for Internet {
             ],
             #[cfg(not(any(target_os = "android", target_os = "ios")))]
             listen: vec![listen_ipv4_quic, listen_ipv4, listen_ipv6_quic, listen_ipv6],
+            websocket: InternetWebsocket::default(),
+            proxy: InternetProxy::default(),
+            reconnect: InternetReconnect::default(),
+        }
+    }
+}
+
+/// BLE Simulator
+///
+/// A software stand-in for the BLE system module on Linux.
+/// All simulated nodes sharing the same directory can
+/// discover each other and exchange messages via Unix sockets.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct BleSimulator {
+    pub enabled: bool,
+    // directory shared by all simulated BLE devices
+    pub path: String,
+    // maximal size of a single BLE packet, unit: bytes
+    pub mtu: usize,
+    // simulated signal strength of the discovered devices, unit: dBm
+    pub rssi: i32,
+    // interval between two advertisements, unit: milliseconds
+    pub interval: u64,
+    // time after which a silent device becomes unavailable, unit: milliseconds
+    pub timeout: u64,
+    // small ids (hex encoded) of the devices in range, all devices are in range if empty
+    pub neighbours: Vec<String>,
+}
+
+impl Default for BleSimulator {
+    fn default() -> Self {
+        BleSimulator {
+            enabled: false,
+            path: String::from("/tmp/qaul-ble-simulator"),
+            mtu: 512,
+            rssi: -60,
+            interval: 1000, //1 second, unit: milliseconds
+            timeout: 5000,  //5 seconds, unit: milliseconds
+            neighbours: vec![],
+        }
+    }
+}
+
+/// BLE Connection Module
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct Ble {
+    #[serde(default)]
+    pub simulator: BleSimulator,
+}
+
+impl Default for Ble {
+    fn default() -> Self {
+        Ble {
+            simulator: BleSimulator::default(),
         }
     }
 }
@@ -127,6 +324,10 @@ pub struct UserAccount {
     pub id: String,
     pub keys: String,
     pub storage: StorageOptions,
+    #[serde(default)]
+    pub file_policy: FilePolicy,
+    #[serde(default)]
+    pub chat_privacy: ChatPrivacy,
 }
 
 impl Default for UserAccount {
@@ -136,6 +337,8 @@ impl Default for UserAccount {
             id: String::from(""),
             keys: String::from(""),
             storage: StorageOptions::default(),
+            file_policy: FilePolicy::default(),
+            chat_privacy: ChatPrivacy::default(),
         }
     }
 }
@@ -185,6 +388,157 @@ impl Default for RoutingOptions {
     }
 }
 
+/// Messaging Configuration Options
+///
+/// Messages, that are forwarded by this node, are dropped
+/// once their hop limit is exhausted, or they are older
+/// than the maximum message age.
+///
+/// Unconfirmed messages sent by this node are retransmitted
+/// with an exponential backoff.
+/// After the maximal amount of attempts or age, the delivery
+/// is marked as failed.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
+pub struct MessagingOptions {
+    // maximal amount of hops a message sent by this node is forwarded
+    pub hop_limit: u32,
+    // drop forwarded messages older than this age, unit: seconds
+    pub max_age: u64,
+    // interval before the first retransmission, unit: seconds
+    pub retry_interval: u64,
+    // maximal interval between two retransmissions, unit: seconds
+    pub retry_interval_max: u64,
+    // maximal amount of attempts to send a message
+    pub retry_max_attempts: u32,
+    // maximal age of an unconfirmed message, unit: seconds
+    pub retry_max_age: u64,
+    // compress messages for users supporting it
+    pub compression: bool,
+}
+
+impl Default for MessagingOptions {
+    fn default() -> Self {
+        MessagingOptions {
+            hop_limit: 32,
+            max_age: 1209600, //14 days, unit: seconds
+            retry_interval: 20,
+            retry_interval_max: 3600,
+            retry_max_attempts: 10,
+            retry_max_age: 604800, //7 days, unit: seconds
+            compression: true,
+        }
+    }
+}
+
+/// End to End Encryption Configuration Options
+///
+/// The transport keys of a crypto session are ratcheted forward
+/// regularly.
+/// After the configured amount of messages or time, a new
+/// crypto session is negotiated via a new handshake.
+/// Unanswered handshakes and superseded sessions are
+/// removed after the configured time.
+/// Messages waiting for a handshake are bounded per user.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
+pub struct CryptoOptions {
+    // start a new handshake after this amount of sent messages
+    pub rekey_messages: u64,
+    // start a new handshake after this session age, unit: seconds
+    pub rekey_age: u64,
+    // amount of old ratchet keys kept per session
+    // to decrypt delayed messages
+    pub skipped_keys: u64,
+    // remove unanswered handshakes after this time, unit: seconds
+    pub handshake_timeout: u64,
+    // remove superseded sessions after they have not been
+    // used for this time, unit: seconds
+    pub session_expiry: u64,
+    // maximal amount of messages per user waiting
+    // for the handshake to complete
+    pub pending_max_messages: usize,
+}
+
+impl Default for CryptoOptions {
+    fn default() -> Self {
+        CryptoOptions {
+            rekey_messages: 10000,
+            rekey_age: 604800, //7 days, unit: seconds
+            skipped_keys: 20,
+            handshake_timeout: 259200, //3 days, unit: seconds
+            session_expiry: 1209600,   //14 days, unit: seconds
+            pending_max_messages: 500,
+        }
+    }
+}
+
+/// Group Configuration Options
+///
+/// If sender keys are enabled, group messages are encrypted
+/// once with a group sending key, instead of once per member.
+/// Members, that don't support sender keys, still receive
+/// pairwise encrypted messages.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
+pub struct GroupOptions {
+    // distribute sender keys and encrypt group messages with them
+    pub sender_keys: bool,
+}
+
+impl Default for GroupOptions {
+    fn default() -> Self {
+        GroupOptions { sender_keys: false }
+    }
+}
+
+/// File Receive Policy of a User Account
+///
+/// Decides which incoming files are received automatically.
+/// Files of users that are not verified are rejected, if only
+/// trusted senders are accepted.
+/// All other files, that are not received automatically, wait
+/// for manual acceptance.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
+pub struct FilePolicy {
+    // maximal size of automatically accepted files, 0 means no limit, unit: bytes
+    pub max_auto_accept_size: u64,
+    // only receive files from verified users
+    pub trusted_only: bool,
+    // all files need to be accepted manually
+    pub manual_accept: bool,
+}
+
+impl Default for FilePolicy {
+    fn default() -> Self {
+        FilePolicy {
+            max_auto_accept_size: 0,
+            trusted_only: false,
+            manual_accept: false,
+        }
+    }
+}
+
+/// Chat Privacy Settings of a User Account
+///
+/// Read receipts are sent to the senders of received messages,
+/// when the messages are displayed.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
+pub struct ChatPrivacy {
+    // send read receipts
+    pub read_receipts: bool,
+}
+
+impl Default for ChatPrivacy {
+    fn default() -> Self {
+        ChatPrivacy {
+            read_receipts: true,
+        }
+    }
+}
+
 /// Storage Configuration Options
 ///
 /// The following options can be configured:
@@ -215,9 +569,17 @@ pub struct Configuration {
     pub node: Node,
     pub lan: Lan,
     pub internet: Internet,
+    #[serde(default)]
+    pub ble: Ble,
     pub user_accounts: Vec<UserAccount>,
     pub debug: DebugOption,
     pub routing: RoutingOptions,
+    #[serde(default)]
+    pub messaging: MessagingOptions,
+    #[serde(default)]
+    pub crypto: CryptoOptions,
+    #[serde(default)]
+    pub group: GroupOptions,
 }
 
 impl Default for Configuration {
@@ -226,9 +588,13 @@ impl Default for Configuration {
             node: Node::default(),
             lan: Lan::default(),
             internet: Internet::default(),
+            ble: Ble::default(),
             user_accounts: Vec::new(),
             debug: DebugOption::default(),
             routing: RoutingOptions::default(),
+            messaging: MessagingOptions::default(),
+            crypto: CryptoOptions::default(),
+            group: GroupOptions::default(),
         }
     }
 }
@@ -311,6 +677,28 @@ impl Configuration {
         }
     }
 
+    /// update the file receive policy of a user account
+    pub fn update_file_policy(user_id: String, file_policy: &FilePolicy) {
+        let mut config = CONFIG.get().write().unwrap();
+        for user in config.user_accounts.iter_mut() {
+            if user.id == user_id {
+                user.file_policy = file_policy.clone();
+                break;
+            }
+        }
+    }
+
+    /// update the chat privacy settings of a user account
+    pub fn update_chat_privacy(user_id: String, chat_privacy: &ChatPrivacy) {
+        let mut config = CONFIG.get().write().unwrap();
+        for user in config.user_accounts.iter_mut() {
+            if user.id == user_id {
+                user.chat_privacy = chat_privacy.clone();
+                break;
+            }
+        }
+    }
+
     /// lend configuration for writing
     pub fn get_mut<'a>() -> RwLockWriteGuard<'a, Configuration> {
         let config_mutable = CONFIG.get().write().unwrap();
@@ -0,0 +1,92 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Data Compression
+//!
+//! Compression of message payloads with the deflate algorithm.
+//!
+//! Compression is only used towards nodes that announced
+//! they are able to decompress the data.
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use std::io::{Read, Write};
+
+/// minimal data size in bytes from which on data is compressed
+///
+/// Smaller data doesn't gain enough to justify the compression.
+pub const MIN_COMPRESSION_SIZE: usize = 256;
+
+/// maximal size in bytes of decompressed data
+///
+/// Data exceeding this size is rejected, to protect
+/// against decompression bombs.
+pub const MAX_DECOMPRESSED_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Compression Statistics
+#[derive(Default, Clone, Copy)]
+pub struct CompressionStats {
+    /// number of compressed messages
+    pub messages: u64,
+    /// total size of the data before compression
+    pub bytes_uncompressed: u64,
+    /// total size of the compressed data
+    pub bytes_compressed: u64,
+}
+
+impl CompressionStats {
+    /// add a compressed message to the statistics
+    pub fn add(&mut self, uncompressed: usize, compressed: usize) {
+        self.messages += 1;
+        self.bytes_uncompressed += uncompressed as u64;
+        self.bytes_compressed += compressed as u64;
+    }
+}
+
+/// Data Compression Utilities
+pub struct Compression {}
+
+impl Compression {
+    /// Compress data with deflate
+    ///
+    /// Returns None if the data is too small to be compressed,
+    /// or if the compressed data isn't smaller than the original.
+    pub fn deflate(data: &[u8]) -> Option<Vec<u8>> {
+        if data.len() < MIN_COMPRESSION_SIZE {
+            return None;
+        }
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        if let Err(e) = encoder.write_all(data) {
+            log::error!("deflate error: {}", e);
+            return None;
+        }
+        match encoder.finish() {
+            Ok(compressed) if compressed.len() < data.len() => Some(compressed),
+            Ok(_) => None,
+            Err(e) => {
+                log::error!("deflate error: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Decompress deflate compressed data
+    ///
+    /// Returns None if the data is invalid or its decompressed
+    /// size exceeds `MAX_DECOMPRESSED_SIZE`.
+    pub fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+        let mut decompressed = Vec::new();
+        let mut decoder = DeflateDecoder::new(data).take(MAX_DECOMPRESSED_SIZE + 1);
+        if let Err(e) = decoder.read_to_end(&mut decompressed) {
+            log::error!("inflate error: {}", e);
+            return None;
+        }
+        if decompressed.len() as u64 > MAX_DECOMPRESSED_SIZE {
+            log::error!("inflate error: decompressed data too big");
+            return None;
+        }
+
+        Some(decompressed)
+    }
+}
@@ -7,9 +7,11 @@
 //!
 //! contains:
 //!
+//! * data compression
 //! * qaul ID conversions
 //! * timestamp handling
 
+pub mod compression;
 pub mod filelogger;
 pub mod qaul_id;
 pub mod timestamp;
@@ -74,6 +74,9 @@ impl VersionUpgrade {
             let lan = crate::storage::configuration::Lan {
                 active: old_cfg.lan.active,
                 listen: vec![old_cfg.lan.listen.clone(), String::from("/ip6/::/tcp/0")],
+                mdns: crate::storage::configuration::LanMdns::default(),
+                beacon: crate::storage::configuration::LanBeacon::default(),
+                static_peers: crate::storage::configuration::LanStaticPeers::default(),
             };
 
             let mut peers: Vec<crate::storage::configuration::InternetPeer> = vec![];
@@ -93,6 +96,9 @@ impl VersionUpgrade {
                     old_cfg.internet.listen.clone(),
                     String::from("/ip6/::/tcp/0"),
                 ],
+                websocket: crate::storage::configuration::InternetWebsocket::default(),
+                proxy: crate::storage::configuration::InternetProxy::default(),
+                reconnect: crate::storage::configuration::InternetReconnect::default(),
             };
 
             let mut user_accounts: Vec<crate::storage::configuration::UserAccount> = vec![];
@@ -105,6 +111,8 @@ impl VersionUpgrade {
                         users: user.storage.users.clone(),
                         size_total: user.storage.size_total,
                     },
+                    file_policy: crate::storage::configuration::FilePolicy::default(),
+                    chat_privacy: crate::storage::configuration::ChatPrivacy::default(),
                 });
             }
 
@@ -122,9 +130,13 @@ impl VersionUpgrade {
                 node,
                 lan,
                 internet,
+                ble: crate::storage::configuration::Ble::default(),
                 user_accounts,
                 debug,
                 routing,
+                messaging: crate::storage::configuration::MessagingOptions::default(),
+                crypto: crate::storage::configuration::CryptoOptions::default(),
+                group: crate::storage::configuration::GroupOptions::default(),
             };
 
             if let Ok(yaml) = serde_yaml::to_string(&new_config) {